@@ -0,0 +1,234 @@
+use crate::types::models::CorsConfigModel;
+use std::collections::HashMap;
+
+/// The check a preflight request failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CorsRejection {
+    Origin,
+    Method,
+    Header(String),
+}
+
+/// Outcome of evaluating a CORS preflight request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CorsDecision {
+    /// Lowercase response headers to send, in a fixed order
+    Allowed(Vec<(String, String)>),
+    Rejected(CorsRejection),
+}
+
+fn list_allows(list: &[String], value: &str) -> bool {
+    list.iter()
+        .any(|item| item == "*" || item.eq_ignore_ascii_case(value))
+}
+
+/// `Access-Control-Allow-Origin` and, when that names the origin, `Vary: Origin`;
+/// `None` if `origin` is not allowed
+fn allow_origin_headers(config: &CorsConfigModel, origin: &str) -> Option<Vec<(String, String)>> {
+    let wildcard_origin = config.allow_origins.iter().any(|o| o == "*");
+    if origin.is_empty() || !(wildcard_origin || config.allow_origins.iter().any(|o| o == origin)) {
+        return None;
+    }
+    // Credentialed responses may not use the "*" wildcard, so echo the origin
+    Some(if wildcard_origin && !config.allow_credentials {
+        vec![("access-control-allow-origin".to_string(), "*".to_string())]
+    } else {
+        vec![
+            (
+                "access-control-allow-origin".to_string(),
+                origin.to_string(),
+            ),
+            ("vary".to_string(), "Origin".to_string()),
+        ]
+    })
+}
+
+/// CORS headers for an actual (non-preflight) request from `origin`, or an
+/// empty map if the origin is not allowed
+pub fn response_headers_for_simple_request(
+    config: &CorsConfigModel,
+    origin: &str,
+) -> HashMap<String, String> {
+    let Some(mut headers) = allow_origin_headers(config, origin) else {
+        return HashMap::new();
+    };
+    if config.allow_credentials {
+        headers.push((
+            "access-control-allow-credentials".to_string(),
+            "true".to_string(),
+        ));
+    }
+    headers.into_iter().collect()
+}
+
+/// Check a preflight's origin, requested method and requested headers against
+/// `config`.
+///
+/// A credentialed response may not carry `Access-Control-Allow-Origin: *`, so
+/// with `allow_credentials` a wildcard origin list echoes the request origin.
+pub fn evaluate_preflight(
+    config: &CorsConfigModel,
+    origin: &str,
+    method: &str,
+    requested_headers: &[String],
+) -> CorsDecision {
+    let Some(mut headers) = allow_origin_headers(config, origin) else {
+        return CorsDecision::Rejected(CorsRejection::Origin);
+    };
+    if !list_allows(&config.allow_methods, method) {
+        return CorsDecision::Rejected(CorsRejection::Method);
+    }
+    if let Some(header) = requested_headers
+        .iter()
+        .find(|h| !list_allows(&config.allow_headers, h))
+    {
+        return CorsDecision::Rejected(CorsRejection::Header(header.clone()));
+    }
+
+    headers.push((
+        "access-control-allow-methods".to_string(),
+        method.to_uppercase(),
+    ));
+    if !requested_headers.is_empty() {
+        headers.push((
+            "access-control-allow-headers".to_string(),
+            requested_headers.join(", "),
+        ));
+    }
+    if config.allow_credentials {
+        headers.push((
+            "access-control-allow-credentials".to_string(),
+            "true".to_string(),
+        ));
+    }
+    if let Some(max_age) = config.max_age {
+        headers.push(("access-control-max-age".to_string(), max_age.to_string()));
+    }
+    CorsDecision::Allowed(headers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(origins: &[&str], credentials: bool) -> CorsConfigModel {
+        CorsConfigModel {
+            allow_origins: origins.iter().map(|o| o.to_string()).collect(),
+            allow_methods: vec!["GET".to_string(), "PUT".to_string()],
+            allow_headers: vec!["Content-Type".to_string()],
+            allow_credentials: credentials,
+            max_age: Some(600),
+        }
+    }
+
+    fn header<'a>(decision: &'a CorsDecision, name: &str) -> Option<&'a str> {
+        match decision {
+            CorsDecision::Allowed(headers) => headers
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, v)| v.as_str()),
+            CorsDecision::Rejected(_) => None,
+        }
+    }
+
+    #[test]
+    fn test_allowed_preflight() {
+        let cors = config(&["https://app.example"], true);
+        let decision = evaluate_preflight(
+            &cors,
+            "https://app.example",
+            "put",
+            &["content-type".to_string()],
+        );
+        assert_eq!(
+            decision,
+            CorsDecision::Allowed(vec![
+                (
+                    "access-control-allow-origin".into(),
+                    "https://app.example".into()
+                ),
+                ("vary".into(), "Origin".into()),
+                ("access-control-allow-methods".into(), "PUT".into()),
+                ("access-control-allow-headers".into(), "content-type".into()),
+                ("access-control-allow-credentials".into(), "true".into()),
+                ("access-control-max-age".into(), "600".into()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_rejected_preflight() {
+        let cors = config(&["https://app.example"], false);
+        assert_eq!(
+            evaluate_preflight(&cors, "https://evil.example", "GET", &[]),
+            CorsDecision::Rejected(CorsRejection::Origin)
+        );
+        assert_eq!(
+            evaluate_preflight(&cors, "", "GET", &[]),
+            CorsDecision::Rejected(CorsRejection::Origin)
+        );
+        assert_eq!(
+            evaluate_preflight(&cors, "https://app.example", "DELETE", &[]),
+            CorsDecision::Rejected(CorsRejection::Method)
+        );
+        assert_eq!(
+            evaluate_preflight(
+                &cors,
+                "https://app.example",
+                "GET",
+                &["content-type".to_string(), "x-secret".to_string()]
+            ),
+            CorsDecision::Rejected(CorsRejection::Header("x-secret".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_wildcard_origin_with_credentials_echoes_origin() {
+        let open = config(&["*"], false);
+        let decision = evaluate_preflight(&open, "https://any.example", "GET", &[]);
+        assert_eq!(header(&decision, "access-control-allow-origin"), Some("*"));
+        assert_eq!(header(&decision, "vary"), None);
+        assert_eq!(header(&decision, "access-control-allow-credentials"), None);
+
+        let credentialed = config(&["*"], true);
+        let decision = evaluate_preflight(&credentialed, "https://any.example", "GET", &[]);
+        assert_eq!(
+            header(&decision, "access-control-allow-origin"),
+            Some("https://any.example")
+        );
+        assert_eq!(header(&decision, "vary"), Some("Origin"));
+        assert_eq!(
+            header(&decision, "access-control-allow-credentials"),
+            Some("true")
+        );
+    }
+
+    #[test]
+    fn test_simple_request_headers() {
+        let cors = config(&["https://app.example"], false);
+        let headers = response_headers_for_simple_request(&cors, "https://app.example");
+        assert_eq!(headers.len(), 2);
+        assert_eq!(
+            headers["access-control-allow-origin"],
+            "https://app.example"
+        );
+        assert_eq!(headers["vary"], "Origin");
+
+        let open = config(&["*"], false);
+        let headers = response_headers_for_simple_request(&open, "https://any.example");
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers["access-control-allow-origin"], "*");
+
+        let credentialed = config(&["*"], true);
+        let headers = response_headers_for_simple_request(&credentialed, "https://any.example");
+        assert_eq!(
+            headers["access-control-allow-origin"],
+            "https://any.example"
+        );
+        assert_eq!(headers["vary"], "Origin");
+        assert_eq!(headers["access-control-allow-credentials"], "true");
+
+        assert!(response_headers_for_simple_request(&cors, "https://evil.example").is_empty());
+        assert!(response_headers_for_simple_request(&cors, "").is_empty());
+    }
+}
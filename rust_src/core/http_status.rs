@@ -0,0 +1,142 @@
+use crate::core::request::RequestError;
+use crate::core::routing::{MatchStatus, RoutingError};
+use crate::params::validation::ValidationError;
+use crate::security::utils::SecurityError;
+use crate::serialization::decoders::DecodingError;
+use crate::serialization::encoders::EncodingError;
+
+/// HTTP status a failure should be reported with
+pub trait ToHttpStatus {
+    fn status_code(&self) -> u16;
+}
+
+impl ToHttpStatus for ValidationError {
+    fn status_code(&self) -> u16 {
+        422
+    }
+}
+
+impl ToHttpStatus for RoutingError {
+    fn status_code(&self) -> u16 {
+        match self {
+            RoutingError::RouteNotFound => 404,
+            RoutingError::UnsupportedMediaType(_) => 415,
+            // A bad route pattern is a server-side configuration error
+            RoutingError::InvalidPath { .. }
+            | RoutingError::RegexError(_)
+            | RoutingError::PrefixMismatch { .. }
+            | RoutingError::InvalidConverter { .. } => 500,
+        }
+    }
+}
+
+impl ToHttpStatus for MatchStatus {
+    fn status_code(&self) -> u16 {
+        match self {
+            MatchStatus::Matched(..) => 200,
+            MatchStatus::MethodNotAllowed(_) => 405,
+            MatchStatus::RedirectTo(_) => 307,
+            MatchStatus::NotFound => 404,
+        }
+    }
+}
+
+impl ToHttpStatus for RequestError {
+    fn status_code(&self) -> u16 {
+        match self {
+            RequestError::InvalidContentType(_) => 415,
+            RequestError::BodyTooLarge { .. } => 413,
+            RequestError::BodyParsingError(_)
+            | RequestError::MissingHeader(_)
+            | RequestError::InvalidHeaderValue(_)
+            | RequestError::AmbiguousFraming(_) => 400,
+        }
+    }
+}
+
+impl ToHttpStatus for SecurityError {
+    fn status_code(&self) -> u16 {
+        match self {
+            SecurityError::VerificationFailed
+            | SecurityError::InvalidKeyFormat
+            | SecurityError::InvalidAuthorizationHeader(_) => 401,
+            SecurityError::InvalidAlgorithm(_)
+            | SecurityError::HashError(_)
+            | SecurityError::InvalidWeights(_)
+            | SecurityError::InvalidTtl(_) => 500,
+        }
+    }
+}
+
+impl ToHttpStatus for DecodingError {
+    fn status_code(&self) -> u16 {
+        match self {
+            DecodingError::UnsupportedContentType(_) => 415,
+            DecodingError::LimitExceeded(_)
+            | DecodingError::BodyTooLarge { .. }
+            | DecodingError::TooManyFields { .. }
+            | DecodingError::PartTooLarge { .. } => 413,
+            DecodingError::InvalidJson(_)
+            | DecodingError::InvalidCbor(_)
+            | DecodingError::EncodingError(_)
+            | DecodingError::ParseError(_) => 400,
+        }
+    }
+}
+
+impl ToHttpStatus for EncodingError {
+    fn status_code(&self) -> u16 {
+        500
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_validation_and_routing_status() {
+        assert_eq!(
+            ValidationError::MissingRequired("id".to_string()).status_code(),
+            422
+        );
+        assert_eq!(RoutingError::RouteNotFound.status_code(), 404);
+        assert_eq!(
+            MatchStatus::MethodNotAllowed(vec!["GET".to_string()]).status_code(),
+            405
+        );
+        assert_eq!(MatchStatus::NotFound.status_code(), 404);
+        assert_eq!(MatchStatus::Matched(0, HashMap::new()).status_code(), 200);
+    }
+
+    #[test]
+    fn test_request_and_security_status() {
+        assert_eq!(RequestError::BodyTooLarge { limit: 10 }.status_code(), 413);
+        assert_eq!(
+            RequestError::AmbiguousFraming("CL and TE".to_string()).status_code(),
+            400
+        );
+        assert_eq!(SecurityError::VerificationFailed.status_code(), 401);
+        assert_eq!(
+            SecurityError::HashError("boom".to_string()).status_code(),
+            500
+        );
+    }
+
+    #[test]
+    fn test_serialization_status() {
+        assert_eq!(
+            DecodingError::UnsupportedContentType("image/png".to_string()).status_code(),
+            415
+        );
+        assert_eq!(
+            DecodingError::InvalidJson("eof".to_string()).status_code(),
+            400
+        );
+        assert_eq!(
+            EncodingError::UnsupportedType("set".to_string()).status_code(),
+            500
+        );
+    }
+}
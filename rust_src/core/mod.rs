@@ -1,5 +1,9 @@
+pub mod cors;
+pub mod http_status;
 pub mod request;
 pub mod routing;
 
+pub use cors::*;
+pub use http_status::*;
 pub use request::*;
 pub use routing::*;
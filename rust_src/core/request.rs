@@ -13,6 +13,10 @@ pub enum RequestError {
     MissingHeader(String),
     #[error("Invalid header value: {0}")]
     InvalidHeaderValue(String),
+    #[error("Ambiguous request framing: {0}")]
+    AmbiguousFraming(String),
+    #[error("Body exceeds maximum size of {limit} bytes")]
+    BodyTooLarge { limit: usize },
 }
 
 pub type Result<T> = std::result::Result<T, RequestError>;
@@ -108,6 +112,44 @@ impl RequestData {
     }
 }
 
+/// Collects a request body from chunks, rejecting it as soon as it exceeds `max_size`
+#[derive(Debug)]
+pub struct BodyAccumulator {
+    buffer: Vec<u8>,
+    max_size: usize,
+}
+
+impl BodyAccumulator {
+    pub fn new(max_size: usize) -> Self {
+        Self {
+            buffer: Vec::new(),
+            max_size,
+        }
+    }
+
+    pub fn push(&mut self, chunk: &[u8]) -> Result<()> {
+        if chunk.len() > self.max_size - self.buffer.len() {
+            return Err(RequestError::BodyTooLarge {
+                limit: self.max_size,
+            });
+        }
+        self.buffer.extend_from_slice(chunk);
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.buffer
+    }
+}
+
 pub fn parse_query_string(query: &str) -> HashMap<String, Vec<String>> {
     let mut params = HashMap::new();
 
@@ -117,12 +159,12 @@ pub fn parse_query_string(query: &str) -> HashMap<String, Vec<String>> {
 
     for pair in query.split('&') {
         if let Some((key, value)) = pair.split_once('=') {
-            let key = percent_decode(key);
-            let value = percent_decode(value);
+            let key = form_urldecode(key);
+            let value = form_urldecode(value);
 
             params.entry(key).or_insert_with(Vec::new).push(value);
         } else if !pair.is_empty() {
-            let key = percent_decode(pair);
+            let key = form_urldecode(pair);
             params
                 .entry(key)
                 .or_insert_with(Vec::new)
@@ -143,6 +185,124 @@ pub fn percent_decode(input: &str) -> String {
         .into_owned()
 }
 
+/// `application/x-www-form-urlencoded` decoding: `+` is a space, `%2B` a literal plus
+pub fn form_urldecode(input: &str) -> String {
+    percent_decode(&input.replace('+', " "))
+}
+
+/// Reject requests whose body length is ambiguous, a prerequisite for request
+/// smuggling: Content-Length together with Transfer-Encoding, repeated or
+/// conflicting Content-Length values, or a length that is not a plain number.
+pub fn validate_framing(headers: &HashMap<String, String>) -> Result<()> {
+    let has_transfer_encoding = headers
+        .keys()
+        .any(|k| k.eq_ignore_ascii_case("transfer-encoding"));
+
+    let lengths: Vec<&str> = headers
+        .iter()
+        .filter(|(k, _)| k.eq_ignore_ascii_case("content-length"))
+        .flat_map(|(_, v)| v.split(','))
+        .map(str::trim)
+        .collect();
+
+    if lengths.is_empty() {
+        return Ok(());
+    }
+
+    if has_transfer_encoding {
+        return Err(RequestError::AmbiguousFraming(
+            "both Content-Length and Transfer-Encoding are present".to_string(),
+        ));
+    }
+
+    if lengths.len() > 1 {
+        return Err(RequestError::AmbiguousFraming(format!(
+            "multiple Content-Length values: {}",
+            lengths.join(", ")
+        )));
+    }
+
+    let length = lengths[0];
+    if length.is_empty()
+        || !length.bytes().all(|b| b.is_ascii_digit())
+        || length.parse::<u64>().is_err()
+    {
+        return Err(RequestError::InvalidHeaderValue(format!(
+            "content-length: {}",
+            length
+        )));
+    }
+
+    Ok(())
+}
+
+/// Method to route a request by: a POST carrying `X-HTTP-Method-Override`
+/// becomes the override when it is in `allowed_overrides`, otherwise the
+/// original method is kept.
+pub fn resolve_effective_method(
+    method: &str,
+    headers: &HashMap<String, String>,
+    allowed_overrides: &[String],
+) -> String {
+    if !method.eq_ignore_ascii_case("POST") {
+        return method.to_string();
+    }
+
+    headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case("x-http-method-override"))
+        .map(|(_, v)| v.trim().to_ascii_uppercase())
+        .filter(|target| {
+            allowed_overrides
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(target))
+        })
+        .unwrap_or_else(|| method.to_string())
+}
+
+/// Order-independent cache key for a request.
+///
+/// Query keys and values and the vary headers are sorted before hashing, so
+/// `?a=1&b=2` and `?b=2&a=1` share a key. Header names are case-insensitive.
+pub fn cache_key(
+    method: &str,
+    path: &str,
+    query: &HashMap<String, Vec<String>>,
+    vary_headers: &[(String, String)],
+) -> String {
+    let mut query_pairs: Vec<(&str, &str)> = query
+        .iter()
+        .flat_map(|(k, values)| values.iter().map(move |v| (k.as_str(), v.as_str())))
+        .collect();
+    query_pairs.sort_unstable();
+
+    let mut headers: Vec<(String, &str)> = vary_headers
+        .iter()
+        .map(|(name, value)| (name.to_lowercase(), value.as_str()))
+        .collect();
+    headers.sort_unstable();
+
+    // Length-prefix every component so no two distinct requests share an encoding
+    let mut hasher = blake3::Hasher::new();
+    let mut feed = |part: &str| {
+        hasher.update(&(part.len() as u64).to_le_bytes());
+        hasher.update(part.as_bytes());
+    };
+    feed(&method.to_uppercase());
+    feed(path);
+    feed(&query_pairs.len().to_string());
+    for (key, value) in &query_pairs {
+        feed(key);
+        feed(value);
+    }
+    for (name, value) in &headers {
+        feed(name);
+        feed(value);
+    }
+
+    hasher.finalize().to_hex().to_string()
+}
+
 pub fn normalize_header_name(name: &str) -> String {
     name.to_lowercase()
 }
@@ -221,6 +381,18 @@ mod tests {
         assert_eq!(params.get("special"), Some(&vec!["!@#".to_string()]));
     }
 
+    #[test]
+    fn test_parse_query_string_plus_is_space() {
+        let params = parse_query_string("q=a+b&literal=a%2Bb&mixed=c+%2B+d&a+key=1");
+
+        assert_eq!(params.get("q"), Some(&vec!["a b".to_string()]));
+        assert_eq!(params.get("literal"), Some(&vec!["a+b".to_string()]));
+        assert_eq!(params.get("mixed"), Some(&vec!["c + d".to_string()]));
+        assert_eq!(params.get("a key"), Some(&vec!["1".to_string()]));
+        // Path segments keep `+` as is
+        assert_eq!(percent_decode("a+b"), "a+b");
+    }
+
     #[test]
     fn test_parse_content_type() {
         let (media_type, params) = parse_content_type("application/json; charset=utf-8");
@@ -264,4 +436,145 @@ mod tests {
         assert_eq!(json["name"], "John");
         assert_eq!(json["age"], 30);
     }
+
+    #[test]
+    fn test_validate_framing() {
+        let mut headers = HashMap::new();
+        headers.insert("content-type".to_string(), "application/json".to_string());
+        headers.insert("content-length".to_string(), "42".to_string());
+        assert!(validate_framing(&headers).is_ok());
+
+        // Content-Length together with Transfer-Encoding
+        let mut conflicting = headers.clone();
+        conflicting.insert("Transfer-Encoding".to_string(), "chunked".to_string());
+        assert!(matches!(
+            validate_framing(&conflicting),
+            Err(RequestError::AmbiguousFraming(_))
+        ));
+
+        // Repeated Content-Length, folded into one value or under differently-cased names
+        let mut duplicate = headers.clone();
+        duplicate.insert("content-length".to_string(), "42, 43".to_string());
+        assert!(matches!(
+            validate_framing(&duplicate),
+            Err(RequestError::AmbiguousFraming(_))
+        ));
+
+        let mut duplicate_names = headers.clone();
+        duplicate_names.insert("Content-Length".to_string(), "42".to_string());
+        assert!(matches!(
+            validate_framing(&duplicate_names),
+            Err(RequestError::AmbiguousFraming(_))
+        ));
+
+        let mut non_numeric = headers.clone();
+        non_numeric.insert("content-length".to_string(), "+42".to_string());
+        assert!(matches!(
+            validate_framing(&non_numeric),
+            Err(RequestError::InvalidHeaderValue(_))
+        ));
+    }
+
+    #[test]
+    fn test_body_accumulator_limit() {
+        let mut body = BodyAccumulator::new(10);
+        assert!(body.is_empty());
+        body.push(b"hello").unwrap();
+        body.push(b"").unwrap();
+        body.push(b"world").unwrap();
+        assert_eq!(body.len(), 10);
+
+        let err = body.push(b"!").unwrap_err();
+        assert!(matches!(err, RequestError::BodyTooLarge { limit: 10 }));
+        // The rejected chunk is not buffered
+        assert_eq!(body.finish(), b"helloworld".to_vec());
+
+        let mut body = BodyAccumulator::new(4);
+        assert!(body.push(b"too long").is_err());
+        assert!(body.finish().is_empty());
+    }
+
+    #[test]
+    fn test_cache_key_is_order_independent() {
+        let query = |pairs: &[(&str, &[&str])]| -> HashMap<String, Vec<String>> {
+            pairs
+                .iter()
+                .map(|(k, vs)| (k.to_string(), vs.iter().map(|v| v.to_string()).collect()))
+                .collect()
+        };
+        let vary = vec![("Accept".to_string(), "application/json".to_string())];
+
+        let a = cache_key(
+            "GET",
+            "/items",
+            &query(&[("a", &["1"]), ("b", &["2", "3"])]),
+            &vary,
+        );
+        let b = cache_key(
+            "get",
+            "/items",
+            &query(&[("b", &["3", "2"]), ("a", &["1"])]),
+            &vary,
+        );
+        assert_eq!(a, b);
+        assert_eq!(a.len(), 64);
+
+        let reversed_vary = vec![
+            ("accept-language".to_string(), "en".to_string()),
+            ("accept".to_string(), "application/json".to_string()),
+        ];
+        let mut both_vary = vary.clone();
+        both_vary.push(("Accept-Language".to_string(), "en".to_string()));
+        assert_eq!(
+            cache_key("GET", "/items", &HashMap::new(), &both_vary),
+            cache_key("GET", "/items", &HashMap::new(), &reversed_vary)
+        );
+
+        let different = cache_key(
+            "GET",
+            "/items",
+            &query(&[("a", &["1"]), ("b", &["2", "4"])]),
+            &vary,
+        );
+        assert_ne!(a, different);
+        // Moving a character between key and value must not collide
+        assert_ne!(
+            cache_key("GET", "/", &query(&[("ab", &["c"])]), &[]),
+            cache_key("GET", "/", &query(&[("a", &["bc"])]), &[])
+        );
+        assert_ne!(
+            a,
+            cache_key(
+                "GET",
+                "/items",
+                &query(&[("a", &["1"]), ("b", &["2", "3"])]),
+                &[]
+            )
+        );
+    }
+
+    #[test]
+    fn test_resolve_effective_method() {
+        let allowed = vec!["PUT".to_string(), "PATCH".to_string(), "DELETE".to_string()];
+        let mut headers = HashMap::new();
+        headers.insert("X-HTTP-Method-Override".to_string(), " delete ".to_string());
+
+        assert_eq!(
+            resolve_effective_method("POST", &headers, &allowed),
+            "DELETE"
+        );
+
+        // Targets outside the allowlist are ignored
+        headers.insert("X-HTTP-Method-Override".to_string(), "TRACE".to_string());
+        assert_eq!(resolve_effective_method("POST", &headers, &allowed), "POST");
+
+        // Only POST may be overridden
+        headers.insert("X-HTTP-Method-Override".to_string(), "DELETE".to_string());
+        assert_eq!(resolve_effective_method("GET", &headers, &allowed), "GET");
+
+        assert_eq!(
+            resolve_effective_method("POST", &HashMap::new(), &allowed),
+            "POST"
+        );
+    }
 }
@@ -1,28 +1,117 @@
 use regex::Regex;
-use std::collections::HashMap;
-use std::sync::Arc;
-use dashmap::DashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, RwLock};
 use once_cell::sync::Lazy;
 use smallvec::SmallVec;
 use ahash::AHashMap;
 use thiserror::Error;
+use crate::core::cors::{evaluate_preflight, CorsDecision};
+use crate::core::request::parse_query_string;
+use crate::types::models::{CorsConfigModel, ResponseModel};
 
 #[derive(Error, Debug)]
 pub enum RoutingError {
-    #[error("Invalid path pattern: {0}")]
-    InvalidPath(String),
+    #[error("Invalid path pattern at byte {position}: {message}")]
+    InvalidPath { message: String, position: usize },
     #[error("Regex compilation failed: {0}")]
     RegexError(#[from] regex::Error),
     #[error("Route not found")]
     RouteNotFound,
+    #[error("Route '{path}' is not under prefix '{prefix}'")]
+    PrefixMismatch { path: String, prefix: String },
+    #[error("Unsupported media type: {0}")]
+    UnsupportedMediaType(String),
+    #[error("Invalid converter '{name}': {message}")]
+    InvalidConverter { name: String, message: String },
 }
 
 pub type Result<T> = std::result::Result<T, RoutingError>;
 
-static REGEX_CACHE: Lazy<DashMap<String, Arc<Regex>>> = Lazy::new(DashMap::new);
+/// Route index, path parameters and query parameters from `RouteTree::match_url`
+pub type UrlMatch = (usize, HashMap<String, String>, HashMap<String, Vec<String>>);
+
+pub const DEFAULT_REGEX_CACHE_CAPACITY: usize = 1024;
+
+static REGEX_CACHE: Lazy<Mutex<RegexCache>> =
+    Lazy::new(|| Mutex::new(RegexCache::new(DEFAULT_REGEX_CACHE_CAPACITY)));
 static PATH_PARAM_REGEX: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r"\{([^}:]+)(?::([^}]+))?\}").unwrap()
 });
+static FLOAT_SEGMENT_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^[0-9]*\.?[0-9]+$").unwrap()
+});
+
+const CONVERTERS: &[&str] = &["str", "int", "float", "uuid", "path"];
+
+/// Converters added with `register_converter`, name -> capturing regex fragment
+static CUSTOM_CONVERTERS: Lazy<RwLock<AHashMap<String, String>>> =
+    Lazy::new(|| RwLock::new(AHashMap::new()));
+
+/// Make `{param:name}` usable in paths compiled from now on. `regex_fragment`
+/// must be exactly one capturing group, e.g. `([a-z0-9-]+)`.
+pub fn register_converter(name: &str, regex_fragment: &str) -> Result<()> {
+    let invalid = |message: &str| RoutingError::InvalidConverter {
+        name: name.to_string(),
+        message: message.to_string(),
+    };
+    
+    let valid_name = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid_name {
+        return Err(invalid("name must be an identifier"));
+    }
+    if CONVERTERS.contains(&name) {
+        return Err(invalid("built-in converters cannot be replaced"));
+    }
+    
+    let regex = Regex::new(&format!("^{}$", regex_fragment))
+        .map_err(|e| invalid(&format!("invalid regex: {}", e)))?;
+    if regex.captures_len() != 2 || !is_single_group(regex_fragment) {
+        return Err(invalid("fragment must be a single capturing group"));
+    }
+    
+    CUSTOM_CONVERTERS
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(name.to_string(), regex_fragment.to_string());
+    Ok(())
+}
+
+fn custom_converter(name: &str) -> Option<String> {
+    CUSTOM_CONVERTERS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(name)
+        .cloned()
+}
+
+/// Whether `fragment` is one `( ... )` group spanning the whole string
+fn is_single_group(fragment: &str) -> bool {
+    if !fragment.starts_with('(') || fragment.starts_with("(?") {
+        return false;
+    }
+    
+    let mut depth = 0usize;
+    let mut in_class = false;
+    let mut escaped = false;
+    for (i, c) in fragment.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '[' => in_class = true,
+            ']' => in_class = false,
+            '(' if !in_class => depth += 1,
+            ')' if !in_class => {
+                depth -= 1;
+                if depth == 0 {
+                    return i == fragment.len() - 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    false
+}
 
 #[derive(Debug, Clone)]
 pub struct Route {
@@ -32,11 +121,35 @@ pub struct Route {
     pub regex: Arc<Regex>,
     pub param_names: SmallVec<[String; 4]>,
     pub path_format: String,
+    /// Bounds of `{name:int(min=..,max=..)}` params, parallel to `param_names`
+    pub int_bounds: SmallVec<[Option<IntBounds>; 4]>,
+    /// Request media types accepted by this route; empty accepts any
+    pub consumes: Vec<String>,
+}
+
+/// Inclusive range a bounded `int` path parameter must fall in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntBounds {
+    pub min: Option<i64>,
+    pub max: Option<i64>,
+}
+
+impl IntBounds {
+    /// `digits` is a captured `[0-9]+` segment; leading zeros are fine, overflow is out of range
+    pub fn contains(&self, digits: &str) -> bool {
+        match digits.parse::<i64>() {
+            Ok(value) => {
+                self.min.is_none_or(|min| value >= min)
+                    && self.max.is_none_or(|max| value <= max)
+            }
+            Err(_) => false,
+        }
+    }
 }
 
 impl Route {
     pub fn new(path: &str, methods: Vec<String>, name: Option<String>) -> Result<Self> {
-        let (regex_pattern, param_names, path_format) = compile_path_pattern(path)?;
+        let (regex_pattern, param_names, path_format, int_bounds) = compile_path_pattern(path)?;
         let regex = get_or_compile_regex(&regex_pattern)?;
         
         Ok(Route {
@@ -46,8 +159,62 @@ impl Route {
             regex,
             param_names,
             path_format,
+            int_bounds,
+            consumes: Vec::new(),
         })
     }
+    
+    /// Captured parameters if `path` matches and satisfies every `int` bound
+    pub fn match_path(&self, path: &str) -> Option<HashMap<String, String>> {
+        let captures = self.regex.captures(path)?;
+        let mut params = HashMap::with_capacity(self.param_names.len());
+        
+        for (i, param_name) in self.param_names.iter().enumerate() {
+            if let Some(capture) = captures.get(i + 1) {
+                if let Some(Some(bounds)) = self.int_bounds.get(i) {
+                    if !bounds.contains(capture.as_str()) {
+                        return None;
+                    }
+                }
+                params.insert(param_name.clone(), capture.as_str().to_string());
+            }
+        }
+        Some(params)
+    }
+    
+    /// Restrict request bodies to these media types (`type/*` and `*/*` allowed)
+    pub fn with_consumes(mut self, consumes: Vec<String>) -> Self {
+        self.consumes = consumes.iter().map(|ct| ct.trim().to_ascii_lowercase()).collect();
+        self
+    }
+    
+    /// Check a request's Content-Type against `consumes` before parsing its body
+    pub fn check_content_type(&self, content_type: Option<&str>) -> Result<()> {
+        if self.consumes.is_empty() {
+            return Ok(());
+        }
+        
+        let media_type = content_type
+            .and_then(|ct| ct.split(';').next())
+            .map(|mt| mt.trim().to_ascii_lowercase())
+            .unwrap_or_default();
+        let allowed = !media_type.is_empty()
+            && self.consumes.iter().any(|accepted| match accepted.strip_suffix("/*") {
+                Some("*") => true,
+                Some(main_type) => media_type
+                    .split_once('/')
+                    .is_some_and(|(ty, _)| ty == main_type),
+                None => *accepted == media_type,
+            });
+        
+        if allowed {
+            Ok(())
+        } else {
+            Err(RoutingError::UnsupportedMediaType(
+                content_type.unwrap_or("<missing>").to_string(),
+            ))
+        }
+    }
 }
 
 pub fn create_route(path: &str, methods: Vec<String>, name: Option<String>) -> Result<Route> {
@@ -60,37 +227,66 @@ pub fn match_route(
     routes: &[Route],
 ) -> Option<(usize, HashMap<String, String>)> {
     for (idx, route) in routes.iter().enumerate() {
-        if !route.methods.iter().any(|m| m == method) {
+        if !route.methods.iter().any(|m| m.eq_ignore_ascii_case(method)) {
             continue;
         }
         
-        if let Some(captures) = route.regex.captures(path) {
-            let mut params = HashMap::with_capacity(route.param_names.len());
-            
-            for (i, param_name) in route.param_names.iter().enumerate() {
-                if let Some(capture) = captures.get(i + 1) {
-                    params.insert(param_name.clone(), capture.as_str().to_string());
-                }
-            }
-            
+        if let Some(params) = route.match_path(path) {
             return Some((idx, params));
         }
     }
     None
 }
 
+/// Result of matching a request that keeps "no such path" and "wrong method" apart
+#[derive(Debug, Clone, PartialEq)]
+pub enum MatchStatus {
+    Matched(usize, HashMap<String, String>),
+    MethodNotAllowed(Vec<String>),
+    /// Only the path with one trailing slash added or removed matches
+    RedirectTo(String),
+    NotFound,
+}
+
+pub fn match_route_detailed(path: &str, method: &str, routes: &[Route]) -> MatchStatus {
+    if let Some((idx, params)) = match_route(path, method, routes) {
+        return MatchStatus::Matched(idx, params);
+    }
+    
+    let mut allowed: Vec<String> = Vec::new();
+    for route in routes.iter().filter(|r| r.match_path(path).is_some()) {
+        for m in &route.methods {
+            if !allowed.contains(m) {
+                allowed.push(m.clone());
+            }
+        }
+    }
+    
+    if allowed.is_empty() {
+        MatchStatus::NotFound
+    } else {
+        MatchStatus::MethodNotAllowed(allowed)
+    }
+}
+
 pub fn compile_path_regex(path: &str) -> Result<String> {
-    let (pattern, _, _) = compile_path_pattern(path)?;
+    let (pattern, _, _, _) = compile_path_pattern(path)?;
     Ok(pattern)
 }
 
-fn compile_path_pattern(path: &str) -> Result<(String, SmallVec<[String; 4]>, String)> {
+type CompiledPattern = (String, SmallVec<[String; 4]>, String, SmallVec<[Option<IntBounds>; 4]>);
+
+fn compile_path_pattern(path: &str) -> Result<CompiledPattern> {
     if !path.starts_with('/') {
-        return Err(RoutingError::InvalidPath("Path must start with '/'".to_string()));
+        return Err(RoutingError::InvalidPath {
+            message: "Path must start with '/'".to_string(),
+            position: 0,
+        });
     }
     
     let mut pattern = String::with_capacity(path.len() * 2);
     let mut param_names = SmallVec::new();
+    let mut int_bounds = SmallVec::new();
     let mut path_format = String::with_capacity(path.len());
     let mut last_end = 0;
     
@@ -99,44 +295,1399 @@ fn compile_path_pattern(path: &str) -> Result<(String, SmallVec<[String; 4]>, St
     for cap in PATH_PARAM_REGEX.captures_iter(path) {
         let full_match = cap.get(0).unwrap();
         let param_name = cap.get(1).unwrap().as_str();
+        if param_names.iter().any(|name: &String| name == param_name) {
+            return Err(RoutingError::InvalidPath {
+                message: format!("Duplicate parameter name '{}'", param_name),
+                position: cap.get(1).unwrap().start(),
+            });
+        }
         let param_type = cap.get(2).map(|m| m.as_str()).unwrap_or("str");
+        let converter_start = cap.get(2).map_or(full_match.start(), |m| m.start());
+        let (converter, length_range) = match param_type.split_once(':') {
+            Some((converter, range)) => (converter, Some(range)),
+            None => (param_type, None),
+        };
         
+        let mut bounds = None;
+        check_literal_segment(path, last_end, full_match.start())?;
         pattern.push_str(&regex::escape(&path[last_end..full_match.start()]));
         path_format.push_str(&path[last_end..full_match.start()]);
         
-        let regex_part = match param_type {
-            "int" => r"([0-9]+)",
-            "float" => r"([0-9]*\.?[0-9]+)",
-            "uuid" => r"([0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12})",
-            "path" => r"(.+)",
-            "str" | _ => r"([^/]+)",
-        };
-        
-        pattern.push_str(regex_part);
+        if let Some(range) = length_range {
+            if converter != "str" {
+                return Err(RoutingError::InvalidPath {
+                    message: format!(
+                        "Length constraint is only supported for str parameters: {{{}:{}}}",
+                        param_name, param_type
+                    ),
+                    position: converter_start,
+                });
+            }
+            
+            let range_start = converter_start + converter.len() + 1;
+            let (min, max) = parse_length_range(param_name, range, range_start)?;
+            let max = max.map(|m| m.to_string()).unwrap_or_default();
+            pattern.push_str(&format!("([^/]{{{},{}}})", min, max));
+        } else if let Some(args) = converter.strip_prefix("int(") {
+            let args_start = converter_start + "int(".len();
+            let args = args.strip_suffix(')').ok_or_else(|| RoutingError::InvalidPath {
+                message: format!("Unclosed bounds for parameter '{}'", param_name),
+                position: args_start,
+            })?;
+            bounds = Some(parse_int_bounds(param_name, args, args_start)?);
+            pattern.push_str(r"([0-9]+)");
+        } else if let Some(fragment) = custom_converter(converter) {
+            pattern.push_str(&fragment);
+        } else if converter == "path" && full_match.end() < path.len() {
+            // Stop at the first place the rest of the route can match
+            pattern.push_str(r"(.+?)");
+        } else {
+            let regex_part = match converter {
+                "int" => r"([0-9]+)",
+                "float" => r"([0-9]*\.?[0-9]+)",
+                "uuid" => r"([0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12})",
+                "path" => r"(.+)",
+                "str" => r"([^/]+)",
+                unknown => {
+                    return Err(RoutingError::InvalidPath {
+                        message: format!(
+                            "Unknown converter '{}' for parameter '{}', expected one of: {}",
+                            unknown,
+                            param_name,
+                            CONVERTERS.join(", ")
+                        ),
+                        position: converter_start,
+                    });
+                }
+            };
+            
+            pattern.push_str(regex_part);
+        }
         path_format.push('{');
         path_format.push_str(param_name);
         path_format.push('}');
         param_names.push(param_name.to_string());
+        int_bounds.push(bounds);
         
         last_end = full_match.end();
     }
     
+    check_literal_segment(path, last_end, path.len())?;
     pattern.push_str(&regex::escape(&path[last_end..]));
     path_format.push_str(&path[last_end..]);
     pattern.push('$');
     
-    Ok((pattern, param_names, path_format))
+    Ok((pattern, param_names, path_format, int_bounds))
+}
+
+/// Literal text between parameters must not contain braces; a leftover `{`
+/// means a parameter was never closed (or is empty)
+fn check_literal_segment(path: &str, start: usize, end: usize) -> Result<()> {
+    if let Some(offset) = path[start..end].find(['{', '}']) {
+        let position = start + offset;
+        let message = if path.as_bytes()[position] == b'{' {
+            "Unterminated or empty parameter '{'"
+        } else {
+            "Unmatched '}'"
+        };
+        return Err(RoutingError::InvalidPath {
+            message: message.to_string(),
+            position,
+        });
+    }
+    Ok(())
+}
+
+/// Parse `min=..,max=..` bounds of an `int(...)` converter; either may be omitted
+fn parse_int_bounds(param_name: &str, args: &str, position: usize) -> Result<IntBounds> {
+    let invalid = |reason: &str| RoutingError::InvalidPath {
+        message: format!(
+            "Invalid bounds '{}' for parameter '{}': {}",
+            args, param_name, reason
+        ),
+        position,
+    };
+    
+    let mut bounds = IntBounds { min: None, max: None };
+    for arg in args.split(',').map(str::trim).filter(|arg| !arg.is_empty()) {
+        let (key, value) = arg.split_once('=').ok_or_else(|| invalid("expected key=value"))?;
+        let value = value
+            .trim()
+            .parse::<i64>()
+            .map_err(|_| invalid("bound is not an integer"))?;
+        let slot = match key.trim() {
+            "min" => &mut bounds.min,
+            "max" => &mut bounds.max,
+            _ => return Err(invalid("only min and max are supported")),
+        };
+        if slot.replace(value).is_some() {
+            return Err(invalid("bound given twice"));
+        }
+    }
+    
+    match (bounds.min, bounds.max) {
+        (None, None) => Err(invalid("expected min and/or max")),
+        (Some(min), Some(max)) if min > max => Err(invalid("min is greater than max")),
+        _ => Ok(bounds),
+    }
+}
+
+/// Parse a `min..max` segment length bound; either end may be omitted
+fn parse_length_range(
+    param_name: &str,
+    range: &str,
+    position: usize,
+) -> Result<(usize, Option<usize>)> {
+    let invalid = || RoutingError::InvalidPath {
+        message: format!(
+            "Invalid length range '{}' for parameter '{}'",
+            range, param_name
+        ),
+        position,
+    };
+    
+    let (min, max) = range.split_once("..").ok_or_else(invalid)?;
+    if min.is_empty() && max.is_empty() {
+        return Err(invalid());
+    }
+    
+    let min = if min.is_empty() {
+        1
+    } else {
+        min.parse::<usize>().map_err(|_| invalid())?
+    };
+    let max = if max.is_empty() {
+        None
+    } else {
+        Some(max.parse::<usize>().map_err(|_| invalid())?)
+    };
+    
+    if min == 0 || max.is_some_and(|max| max < min) {
+        return Err(invalid());
+    }
+    
+    Ok((min, max))
+}
+
+/// Compiled patterns keyed by source, evicting the least recently used entry
+/// once `capacity` is reached. Evicted regexes stay alive for existing holders.
+struct RegexCache {
+    capacity: usize,
+    clock: u64,
+    entries: AHashMap<String, (Arc<Regex>, u64)>,
+    /// Last-use tick -> pattern, oldest first
+    recency: BTreeMap<u64, String>,
+}
+
+impl RegexCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            clock: 0,
+            entries: AHashMap::new(),
+            recency: BTreeMap::new(),
+        }
+    }
+    
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+    
+    fn get(&mut self, pattern: &str) -> Option<Arc<Regex>> {
+        self.clock += 1;
+        let (regex, tick) = self.entries.get_mut(pattern)?;
+        let key = self.recency.remove(tick).unwrap_or_else(|| pattern.to_string());
+        *tick = self.clock;
+        self.recency.insert(self.clock, key);
+        Some(regex.clone())
+    }
+    
+    fn insert(&mut self, pattern: &str, regex: Arc<Regex>) {
+        if let Some((_, tick)) = self.entries.remove(pattern) {
+            self.recency.remove(&tick);
+        }
+        self.clock += 1;
+        self.entries.insert(pattern.to_string(), (regex, self.clock));
+        self.recency.insert(self.clock, pattern.to_string());
+        self.evict();
+    }
+    
+    fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+        self.evict();
+    }
+    
+    fn evict(&mut self) {
+        while self.entries.len() > self.capacity {
+            match self.recency.pop_first() {
+                Some((_, oldest)) => self.entries.remove(&oldest),
+                None => break,
+            };
+        }
+    }
+}
+
+/// Bound the number of compiled path regexes kept around (at least one)
+pub fn set_regex_cache_capacity(capacity: usize) {
+    REGEX_CACHE.lock().unwrap().set_capacity(capacity);
 }
 
 fn get_or_compile_regex(pattern: &str) -> Result<Arc<Regex>> {
-    if let Some(cached) = REGEX_CACHE.get(pattern) {
-        return Ok(cached.clone());
+    if let Some(cached) = REGEX_CACHE.lock().unwrap().get(pattern) {
+        return Ok(cached);
     }
     
+    // Compile outside the lock; a concurrent miss just compiles twice
     let regex = Arc::new(Regex::new(pattern)?);
-    REGEX_CACHE.insert(pattern.to_string(), regex.clone());
+    REGEX_CACHE.lock().unwrap().insert(pattern, regex.clone());
     Ok(regex)
 }
 
-#[derive(Default)]
-pub struct RouteTree {
\ No newline at end of file
+/// Build a `RouteTree`, reporting dynamic routes that can never match because
+/// an earlier, more general route already accepts every path they would
+pub fn create_route_tree(routes: Vec<Route>) -> (RouteTree, Vec<String>) {
+    let mut diagnostics = Vec::new();
+    
+    for (i, specific) in routes.iter().enumerate() {
+        if specific.param_names.is_empty() {
+            continue;
+        }
+        for general in routes[..i].iter().filter(|r| !r.param_names.is_empty()) {
+            let shared: Vec<&str> = general
+                .methods
+                .iter()
+                .filter(|m| specific.methods.contains(m))
+                .map(|m| m.as_str())
+                .collect();
+            // A more specific route is tried first regardless of registration order
+            if !shared.is_empty()
+                && route_covers(&general.path, &specific.path)
+                && route_specificity(&general.path) >= route_specificity(&specific.path)
+            {
+                diagnostics.push(format!(
+                    "Route '{}' shadows '{}' for {}; register the more specific route first",
+                    general.path,
+                    specific.path,
+                    shared.join(", ")
+                ));
+            }
+        }
+    }
+    
+    let mut tree = RouteTree::new();
+    for route in routes {
+        let duplicated = tree.registered_static_methods(&route);
+        if !duplicated.is_empty() {
+            diagnostics.push(format!(
+                "Route '{}' is already registered for {}; the first registration wins",
+                route.path,
+                duplicated.join(", ")
+            ));
+        }
+        tree.add_route(route);
+    }
+    (tree, diagnostics)
+}
+
+/// Whether every path matched by the `specific` pattern is also matched by `general`
+fn route_covers(general: &str, specific: &str) -> bool {
+    let general: Vec<&str> = general.split('/').collect();
+    let specific: Vec<&str> = specific.split('/').collect();
+    
+    for (i, general_segment) in general.iter().enumerate() {
+        let Some(specific_segment) = specific.get(i) else {
+            return false;
+        };
+        if segment_converter(general_segment) == Some("path") {
+            return true;
+        }
+        if segment_converter(specific_segment) == Some("path")
+            || !segment_covers(general_segment, specific_segment)
+        {
+            return false;
+        }
+    }
+    general.len() == specific.len()
+}
+
+fn segment_covers(general: &str, specific: &str) -> bool {
+    if general == specific {
+        return true;
+    }
+    
+    let literal = !specific.contains('{');
+    match (segment_converter(general), segment_converter(specific)) {
+        (Some("str"), _) => true,
+        (Some("float"), Some("int")) => true,
+        (Some("int"), _) if literal => specific.bytes().all(|b| b.is_ascii_digit()),
+        (Some("float"), _) if literal => FLOAT_SEGMENT_REGEX.is_match(specific),
+        _ => false,
+    }
+}
+
+/// Per-segment ranks compared lexicographically, higher is more specific: literal
+/// segments outrank typed params (`int`, `uuid`, custom converters, ...), which
+/// outrank `str`, with `path` catch-alls last
+fn route_specificity(path: &str) -> SmallVec<[u8; 8]> {
+    path.split('/')
+        .map(|segment| match segment_converter(segment) {
+            None if !segment.contains('{') => 4,
+            // Literal text around a parameter, e.g. "report-{id}.csv"
+            None => 3,
+            Some("path") => 0,
+            Some(converter) if converter == "str" || converter.starts_with("str:") => 1,
+            Some(_) => 2,
+        })
+        .collect()
+}
+
+/// Converter of a segment consisting of a single parameter; length-constrained
+/// `str` parameters are reported as "str:<range>"
+fn segment_converter(segment: &str) -> Option<&str> {
+    let cap = PATH_PARAM_REGEX.captures(segment)?;
+    if cap.get(0).unwrap().as_str() != segment {
+        return None;
+    }
+    Some(cap.get(2).map(|m| m.as_str()).unwrap_or("str"))
+}
+
+pub struct RouteTree {
+    /// Removed routes leave a `None` behind so indices handed out stay valid
+    routes: Vec<Option<Route>>,
+    /// Keyed by `static_key(path, method)`, one entry per method
+    static_routes: AHashMap<String, usize>,
+    dynamic_routes: Vec<usize>,
+    redirect_slashes: bool,
+    /// Answer HEAD with the GET route when no HEAD route is registered
+    auto_head: bool,
+}
+
+impl Default for RouteTree {
+    fn default() -> Self {
+        Self {
+            routes: Vec::new(),
+            static_routes: AHashMap::new(),
+            dynamic_routes: Vec::new(),
+            redirect_slashes: false,
+            auto_head: true,
+        }
+    }
+}
+
+fn static_key(path: &str, method: &str) -> String {
+    format!("{}:{}", path, method.to_uppercase())
+}
+
+impl RouteTree {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Dynamic routes are kept in descending specificity, ties in registration order
+    pub fn add_route(&mut self, route: Route) -> usize {
+        let idx = self.routes.len();
+        if route.param_names.is_empty() {
+            for method in &route.methods {
+                self.static_routes.entry(static_key(&route.path, method)).or_insert(idx);
+            }
+        } else {
+            let specificity = route_specificity(&route.path);
+            let position = self.dynamic_routes.partition_point(|&other| {
+                route_specificity(&self.live_route(other).path) >= specificity
+            });
+            self.dynamic_routes.insert(position, idx);
+        }
+        self.routes.push(Some(route));
+        idx
+    }
+    
+    /// Stop serving `method` at `path`; the route itself is dropped once it has
+    /// no methods left. Returns whether anything was removed.
+    pub fn remove_route(&mut self, path: &str, method: &str) -> bool {
+        let Some(idx) = self.live_routes().find_map(|(idx, route)| {
+            let serves = route.methods.iter().any(|m| m.eq_ignore_ascii_case(method));
+            (route.path == path && serves).then_some(idx)
+        }) else {
+            return false;
+        };
+        
+        let route = self.routes[idx].as_mut().unwrap();
+        route.methods.retain(|m| !m.eq_ignore_ascii_case(method));
+        if route.methods.is_empty() {
+            self.routes[idx] = None;
+            self.dynamic_routes.retain(|&other| other != idx);
+        }
+        self.reindex_static(path, method);
+        true
+    }
+    
+    /// Swap in `route` for the first route registered at the same path, keeping its index
+    pub fn replace_route(&mut self, route: Route) -> Result<()> {
+        let idx = self
+            .live_routes()
+            .find_map(|(idx, existing)| (existing.path == route.path).then_some(idx))
+            .ok_or(RoutingError::RouteNotFound)?;
+        
+        let old = self.routes[idx].replace(route).unwrap();
+        let path = old.path.clone();
+        let new_methods = self.live_route(idx).methods.clone();
+        for method in old.methods.iter().chain(&new_methods) {
+            self.reindex_static(&path, method);
+        }
+        Ok(())
+    }
+    
+    /// Point the static entry for `path` and `method` at the earliest live route
+    /// serving it, or drop the entry if none does
+    fn reindex_static(&mut self, path: &str, method: &str) {
+        let key = static_key(path, method);
+        let serving = self.live_routes().find_map(|(idx, route)| {
+            let serves = route.param_names.is_empty()
+                && route.path == path
+                && route.methods.iter().any(|m| m.eq_ignore_ascii_case(method));
+            serves.then_some(idx)
+        });
+        match serving {
+            Some(idx) => self.static_routes.insert(key, idx),
+            None => self.static_routes.remove(&key),
+        };
+    }
+    
+    /// Route at an index held by the static or dynamic index, which only holds live routes
+    fn live_route(&self, idx: usize) -> &Route {
+        self.routes[idx].as_ref().expect("indexed route was removed")
+    }
+    
+    fn live_routes(&self) -> impl Iterator<Item = (usize, &Route)> {
+        self.routes
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, route)| route.as_ref().map(|route| (idx, route)))
+    }
+    
+    /// Methods of a static `route` that an earlier route already serves at the same path
+    fn registered_static_methods<'a>(&self, route: &'a Route) -> Vec<&'a str> {
+        if !route.param_names.is_empty() {
+            return Vec::new();
+        }
+        route
+            .methods
+            .iter()
+            .filter(|m| self.static_routes.contains_key(&static_key(&route.path, m)))
+            .map(|m| m.as_str())
+            .collect()
+    }
+    
+    /// Make `match_detailed` suggest the path with a trailing slash added or
+    /// removed when only that variant matches
+    pub fn set_redirect_slashes(&mut self, enabled: bool) {
+        self.redirect_slashes = enabled;
+    }
+    
+    pub fn set_auto_head(&mut self, enabled: bool) {
+        self.auto_head = enabled;
+    }
+    
+    /// Route at `idx`, or `None` if it was removed
+    pub fn route(&self, idx: usize) -> Option<&Route> {
+        self.routes.get(idx)?.as_ref()
+    }
+    
+    /// Routes that have not been removed, in registration order
+    pub fn routes(&self) -> impl Iterator<Item = &Route> {
+        self.live_routes().map(|(_, route)| route)
+    }
+    
+    pub fn len(&self) -> usize {
+        self.routes().count()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    
+    /// Match `path` and `method`; HEAD falls back to GET routes when auto HEAD is enabled
+    pub fn match_route(&self, path: &str, method: &str) -> Option<(usize, HashMap<String, String>)> {
+        self.match_method(path, method).or_else(|| {
+            if self.auto_head && method.eq_ignore_ascii_case("HEAD") {
+                self.match_method(path, "GET")
+            } else {
+                None
+            }
+        })
+    }
+    
+    /// Static paths are looked up directly; dynamic routes are tried most specific first
+    fn match_method(&self, path: &str, method: &str) -> Option<(usize, HashMap<String, String>)> {
+        if let Some(&idx) = self.static_routes.get(&static_key(path, method)) {
+            return Some((idx, HashMap::new()));
+        }
+        
+        for &idx in &self.dynamic_routes {
+            let route = self.live_route(idx);
+            if !route.methods.iter().any(|m| m.eq_ignore_ascii_case(method)) {
+                continue;
+            }
+            
+            if let Some(params) = route.match_path(path) {
+                return Some((idx, params));
+            }
+        }
+        None
+    }
+    
+    /// Like `match_route`, but tells 405 apart from 404; the allowed methods are
+    /// the union over every route whose path matches
+    pub fn match_with_status(&self, path: &str, method: &str) -> MatchStatus {
+        if let Some((idx, params)) = self.match_route(path, method) {
+            return MatchStatus::Matched(idx, params);
+        }
+        
+        let mut allowed: Vec<String> = Vec::new();
+        for route in self.routes().filter(|r| r.match_path(path).is_some()) {
+            for m in &route.methods {
+                if !allowed.contains(m) {
+                    allowed.push(m.clone());
+                }
+            }
+        }
+        if self.auto_head
+            && allowed.iter().any(|m| m == "GET")
+            && !allowed.iter().any(|m| m == "HEAD")
+        {
+            allowed.push("HEAD".to_string());
+        }
+        if allowed.is_empty() {
+            MatchStatus::NotFound
+        } else {
+            MatchStatus::MethodNotAllowed(allowed)
+        }
+    }
+    
+    /// `match_with_status`, plus trailing-slash redirects when enabled
+    pub fn match_detailed(&self, path: &str, method: &str) -> MatchStatus {
+        let status = self.match_with_status(path, method);
+        if status != MatchStatus::NotFound {
+            return status;
+        }
+        
+        if self.redirect_slashes {
+            let alternate = match path.strip_suffix('/') {
+                // Never redirect "/" to an empty path
+                Some("") => None,
+                Some(stripped) => Some(stripped.to_string()),
+                None => Some(format!("{}/", path)),
+            };
+            if let Some(alternate) = alternate {
+                if self.match_route(&alternate, method).is_some() {
+                    return MatchStatus::RedirectTo(alternate);
+                }
+            }
+        }
+        MatchStatus::NotFound
+    }
+    
+    /// Match a full request target, returning path params and parsed query params together
+    pub fn match_url(&self, url: &str, method: &str) -> Option<UrlMatch> {
+        let url = url.split('#').next().unwrap_or(url);
+        let (path, query) = url.split_once('?').unwrap_or((url, ""));
+        
+        let (idx, path_params) = self.match_route(path, method)?;
+        Some((idx, path_params, parse_query_string(query)))
+    }
+    
+    /// Answer a CORS preflight for `path`, or `None` if the route, origin,
+    /// method or any requested header is not allowed
+    pub fn handle_cors_preflight(
+        &self,
+        path: &str,
+        origin: &str,
+        request_method: &str,
+        request_headers: &[String],
+        cors: &CorsConfigModel,
+    ) -> Option<ResponseModel> {
+        self.match_route(path, request_method)?;
+        match evaluate_preflight(cors, origin, request_method, request_headers) {
+            CorsDecision::Allowed(headers) => {
+                let mut response = ResponseModel::new(204);
+                response.headers.extend(headers);
+                Some(response)
+            }
+            CorsDecision::Rejected(_) => None,
+        }
+    }
+    
+    /// Render the route table as a Graphviz DOT graph: path segments form a
+    /// prefix tree and each route hangs off its full path labelled with methods
+    pub fn to_dot(&self) -> String {
+        let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+        let mut out = String::from("digraph routes {\n    rankdir=LR;\n    node [shape=box];\n");
+        out.push_str("    \"/\";\n");
+        
+        let mut seen: Vec<String> = Vec::new();
+        for (idx, route) in self.live_routes() {
+            let mut parent = "/".to_string();
+            let mut prefix = String::new();
+            for segment in route.path.split('/').filter(|s| !s.is_empty()) {
+                prefix.push('/');
+                prefix.push_str(segment);
+                if !seen.contains(&prefix) {
+                    seen.push(prefix.clone());
+                    out.push_str(&format!("    {} [label={}];\n", quote(&prefix), quote(segment)));
+                    out.push_str(&format!("    {} -> {};\n", quote(&parent), quote(&prefix)));
+                }
+                parent = prefix.clone();
+            }
+            
+            let methods: Vec<&str> = route.methods.iter().map(|m| m.as_str()).collect();
+            let label = match &route.name {
+                Some(name) => format!("{} ({})", methods.join(", "), name),
+                None => methods.join(", "),
+            };
+            out.push_str(&format!(
+                "    route{} [shape=ellipse, label={}];\n    {} -> route{};\n",
+                idx,
+                quote(&label),
+                quote(&parent),
+                idx
+            ));
+        }
+        
+        out.push_str("}\n");
+        out
+    }
+    
+    /// Longest run of literal leading segments shared by every route, e.g. `/api/v1`
+    pub fn common_prefix(&self) -> String {
+        let mut routes = self.routes();
+        let Some(first) = routes.next() else {
+            return String::new();
+        };
+        
+        let literal = |path: &str| -> Vec<String> {
+            path.split('/')
+                .filter(|s| !s.is_empty())
+                .take_while(|s| !s.contains('{'))
+                .map(str::to_string)
+                .collect()
+        };
+        let mut shared = literal(&first.path);
+        for route in routes {
+            let segments = literal(&route.path);
+            let common = shared
+                .iter()
+                .zip(&segments)
+                .take_while(|(a, b)| a == b)
+                .count();
+            shared.truncate(common);
+        }
+        
+        shared.iter().map(|s| format!("/{}", s)).collect()
+    }
+    
+    /// Copy of this tree with `prefix` removed from every route path. Routes
+    /// keep their indices and the tree keeps its slash and HEAD settings.
+    pub fn strip_prefix(&self, prefix: &str) -> Result<RouteTree> {
+        let prefix = prefix.trim_end_matches('/');
+        let mut tree = RouteTree::new();
+        tree.redirect_slashes = self.redirect_slashes;
+        tree.auto_head = self.auto_head;
+        
+        for route in &self.routes {
+            let Some(route) = route else {
+                tree.routes.push(None);
+                continue;
+            };
+            let rest = route
+                .path
+                .strip_prefix(prefix)
+                .filter(|rest| rest.is_empty() || rest.starts_with('/'))
+                .ok_or_else(|| RoutingError::PrefixMismatch {
+                    path: route.path.clone(),
+                    prefix: prefix.to_string(),
+                })?;
+            let path = if rest.is_empty() { "/" } else { rest };
+            
+            tree.add_route(
+                Route::new(path, route.methods.to_vec(), route.name.clone())?
+                    .with_consumes(route.consumes.clone()),
+            );
+        }
+        Ok(tree)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn routes(specs: &[(&str, &[&str])]) -> Vec<Route> {
+        specs
+            .iter()
+            .map(|(path, methods)| {
+                Route::new(path, methods.iter().map(|m| m.to_string()).collect(), None).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_match_route_detailed() {
+        let routes = routes(&[
+            ("/users", &["GET", "POST"]),
+            ("/users/{id:int}", &["GET"]),
+            ("/users/{id:int}", &["PUT", "GET"]),
+        ]);
+
+        let mut expected = HashMap::new();
+        expected.insert("id".to_string(), "5".to_string());
+        assert_eq!(
+            match_route_detailed("/users/5", "PUT", &routes),
+            MatchStatus::Matched(2, expected)
+        );
+        assert_eq!(
+            match_route_detailed("/users/5", "DELETE", &routes),
+            MatchStatus::MethodNotAllowed(vec!["GET".to_string(), "PUT".to_string()])
+        );
+        assert_eq!(
+            match_route_detailed("/posts", "GET", &routes),
+            MatchStatus::NotFound
+        );
+    }
+
+    #[test]
+    fn test_str_length_constraint() {
+        assert_eq!(
+            compile_path_regex("/code/{c:str:3..8}").unwrap(),
+            "^/code/([^/]{3,8})$"
+        );
+
+        let route = Route::new("/code/{c:str:3..8}", vec!["GET".to_string()], None).unwrap();
+        assert!(route.regex.is_match("/code/abc"));
+        assert!(route.regex.is_match("/code/abcdefgh"));
+        assert!(!route.regex.is_match("/code/ab"));
+        assert!(!route.regex.is_match("/code/abcdefghi"));
+
+        let open_ended = Route::new("/code/{c:str:3..}", vec!["GET".to_string()], None).unwrap();
+        assert!(open_ended.regex.is_match(&format!("/code/{}", "x".repeat(100))));
+        assert!(!open_ended.regex.is_match("/code/ab"));
+    }
+
+    #[test]
+    fn test_malformed_length_constraint() {
+        for path in [
+            "/code/{c:str:3-8}",
+            "/code/{c:str:..}",
+            "/code/{c:str:8..3}",
+            "/code/{c:str:a..b}",
+            "/code/{c:str:0..4}",
+            "/code/{c:int:1..4}",
+        ] {
+            assert!(
+                matches!(compile_path_regex(path), Err(RoutingError::InvalidPath { .. })),
+                "{} should not compile",
+                path
+            );
+        }
+    }
+
+    fn error_position(path: &str) -> usize {
+        match compile_path_regex(path) {
+            Err(RoutingError::InvalidPath { position, .. }) => position,
+            other => panic!("expected InvalidPath for {}, got {:?}", path, other),
+        }
+    }
+
+    #[test]
+    fn test_compilation_error_position() {
+        assert_eq!(error_position("users"), 0);
+        // Unterminated brace points at the opening '{'
+        assert_eq!(error_position("/users/{id"), 7);
+        assert_eq!(error_position("/users/{id}/posts/{post_id"), 18);
+        assert_eq!(error_position("/users/{}"), 7);
+        assert_eq!(error_position("/users/id}"), 9);
+        // Converter problems point at the converter name
+        assert_eq!(error_position("/items/{id:int:1..4}"), 11);
+        assert_eq!(error_position("/code/{c:str:8..3}"), 13);
+
+        let message = compile_path_regex("/users/{id").unwrap_err().to_string();
+        assert!(message.contains("at byte 7"));
+    }
+
+    #[test]
+    fn test_unknown_converter_rejected() {
+        for converter in CONVERTERS {
+            let path = format!("/x/{{id:{}}}", converter);
+            assert!(compile_path_regex(&path).is_ok(), "{} should compile", path);
+        }
+
+        let err = compile_path_regex("/x/{id:inteter}").unwrap_err();
+        assert!(matches!(err, RoutingError::InvalidPath { position: 7, .. }));
+        let message = err.to_string();
+        assert!(message.contains("inteter"));
+        assert!(message.contains("str, int, float, uuid, path"));
+    }
+
+    #[test]
+    fn test_route_tree_match_url() {
+        let mut tree = RouteTree::new();
+        for route in routes(&[("/users", &["GET"]), ("/users/{id:int}", &["GET"])]) {
+            tree.add_route(route);
+        }
+        assert_eq!(tree.len(), 2);
+
+        let (idx, path_params, query_params) = tree.match_url("/users/5?active=true", "GET").unwrap();
+        assert_eq!(idx, 1);
+        assert_eq!(path_params.get("id"), Some(&"5".to_string()));
+        assert_eq!(query_params.get("active"), Some(&vec!["true".to_string()]));
+
+        let (idx, path_params, query_params) = tree.match_url("/users", "GET").unwrap();
+        assert_eq!(idx, 0);
+        assert!(path_params.is_empty());
+        assert!(query_params.is_empty());
+
+        let (_, _, query_params) = tree.match_url("/users?tag=a&tag=b#top", "GET").unwrap();
+        assert_eq!(query_params.get("tag"), Some(&vec!["a".to_string(), "b".to_string()]));
+
+        assert!(tree.match_url("/users/5?active=true", "POST").is_none());
+        assert!(tree.match_url("/posts?x=1", "GET").is_none());
+    }
+
+    #[test]
+    fn test_route_tree_cors_preflight() {
+        let mut tree = RouteTree::new();
+        for route in routes(&[("/items/{id}", &["GET", "PUT"])]) {
+            tree.add_route(route);
+        }
+        let cors = CorsConfigModel {
+            allow_origins: vec!["https://app.example".to_string()],
+            allow_methods: vec!["GET".to_string(), "PUT".to_string()],
+            allow_headers: vec!["Content-Type".to_string()],
+            allow_credentials: true,
+            max_age: Some(600),
+        };
+        let requested = vec!["content-type".to_string()];
+
+        let response = tree
+            .handle_cors_preflight("/items/3", "https://app.example", "PUT", &requested, &cors)
+            .unwrap();
+        assert_eq!(response.status_code, 204);
+        assert_eq!(response.headers["access-control-allow-origin"], "https://app.example");
+        assert_eq!(response.headers["access-control-allow-methods"], "PUT");
+        assert_eq!(response.headers["access-control-allow-headers"], "content-type");
+        assert_eq!(response.headers["access-control-allow-credentials"], "true");
+        assert_eq!(response.headers["access-control-max-age"], "600");
+        assert_eq!(response.headers["vary"], "Origin");
+
+        assert!(tree
+            .handle_cors_preflight("/missing", "https://app.example", "PUT", &requested, &cors)
+            .is_none());
+        assert!(tree
+            .handle_cors_preflight("/items/3", "https://evil.example", "PUT", &requested, &cors)
+            .is_none());
+        assert!(tree
+            .handle_cors_preflight("/items/3", "https://app.example", "DELETE", &[], &cors)
+            .is_none());
+        assert!(tree
+            .handle_cors_preflight(
+                "/items/3",
+                "https://app.example",
+                "GET",
+                &["x-secret".to_string()],
+                &cors
+            )
+            .is_none());
+
+        let open = CorsConfigModel {
+            allow_methods: vec!["*".to_string()],
+            ..CorsConfigModel::default()
+        };
+        let response = tree
+            .handle_cors_preflight("/items/3", "https://any.example", "GET", &[], &open)
+            .unwrap();
+        assert_eq!(response.headers["access-control-allow-origin"], "*");
+        assert!(!response.headers.contains_key("access-control-allow-headers"));
+    }
+
+    #[test]
+    fn test_create_route_tree_shadow_warnings() {
+        // float covers int at the same specificity, so registration order decides
+        let (tree, diagnostics) =
+            create_route_tree(routes(&[("/x/{v:float}", &["GET"]), ("/x/{id:int}", &["GET"])]));
+        assert_eq!(tree.len(), 2);
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].contains("'/x/{v:float}' shadows '/x/{id:int}'"));
+        // The shadowed route is still registered, it just never wins
+        assert_eq!(tree.match_route("/x/5", "GET").unwrap().0, 0);
+
+        // Outranked by specificity, disjoint converters, or no shared method: nothing to report
+        for specs in [
+            &[("/x/{name:str}", &["GET"][..]), ("/x/{id:int}", &["GET"][..])][..],
+            &[("/x/{id:int}", &["GET"][..]), ("/x/{name:str}", &["GET"][..])][..],
+            &[("/x/{id:int}", &["GET"][..]), ("/x/{id:uuid}", &["GET"][..])][..],
+            &[("/x/{name}", &["GET"][..]), ("/x/{id:int}", &["POST"][..])][..],
+            &[("/x/{name}", &["GET"][..]), ("/x/{id:int}/y", &["GET"][..])][..],
+            &[("/files/{rest:path}", &["GET"][..]), ("/files/{dir}/{name}", &["GET"][..])][..],
+        ] {
+            let (_, diagnostics) = create_route_tree(routes(specs));
+            assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        }
+    }
+
+    #[test]
+    fn test_route_tree_to_dot() {
+        let mut tree = RouteTree::new();
+        for route in routes(&[
+            ("/users", &["GET", "POST"]),
+            ("/users/{id:int}", &["GET"]),
+            ("/", &["GET"]),
+        ]) {
+            tree.add_route(route);
+        }
+        let dot = tree.to_dot();
+
+        assert!(dot.starts_with("digraph routes {"));
+        assert!(dot.trim_end().ends_with('}'));
+        assert_eq!(dot.matches('"').count() % 2, 0);
+
+        for idx in 0..tree.len() {
+            assert!(dot.contains(&format!("route{} [shape=ellipse", idx)));
+        }
+        assert!(dot.contains(r#""/users/{id:int}" [label="{id:int}"];"#));
+        assert!(dot.contains(r#""/users" -> "/users/{id:int}";"#));
+        assert!(dot.contains(r#"route0 [shape=ellipse, label="GET, POST"];"#));
+        assert!(dot.contains(r#""/" -> route2;"#));
+        // Shared prefixes are emitted once
+        assert_eq!(dot.matches(r#""/" -> "/users";"#).count(), 1);
+    }
+
+    #[test]
+    fn test_route_tree_common_prefix() {
+        let mut tree = RouteTree::new();
+        for route in routes(&[
+            ("/api/v1/users/{id:int}", &["GET"]),
+            ("/api/v1/items", &["GET"]),
+            ("/api/v1", &["GET"]),
+        ]) {
+            tree.add_route(route);
+        }
+        assert_eq!(tree.common_prefix(), "/api/v1");
+
+        let stripped = tree.strip_prefix("/api/v1/").unwrap();
+        let paths: Vec<&str> = stripped.routes().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths, vec!["/users/{id:int}", "/items", "/"]);
+        let (_, params) = stripped.match_route("/users/3", "GET").unwrap();
+        assert_eq!(params.get("id"), Some(&"3".to_string()));
+
+        // "/api/v10" shares the characters but not the segment
+        tree.add_route(Route::new("/api/v10/health", vec!["GET".to_string()], None).unwrap());
+        assert_eq!(tree.common_prefix(), "/api");
+        assert!(matches!(
+            tree.strip_prefix("/api/v1"),
+            Err(RoutingError::PrefixMismatch { ref path, .. }) if path == "/api/v10/health"
+        ));
+
+        assert_eq!(RouteTree::new().common_prefix(), "");
+    }
+
+    #[test]
+    fn test_strip_prefix_keeps_indices_and_settings() {
+        let mut tree = RouteTree::new();
+        for route in routes(&[
+            ("/api/v1/users/{id:int}", &["GET"]),
+            ("/api/v1/items", &["GET"]),
+            ("/api/v1", &["GET"]),
+        ]) {
+            tree.add_route(route);
+        }
+        tree.set_auto_head(true);
+        assert!(tree.remove_route("/api/v1/items", "GET"));
+
+        let stripped = tree.strip_prefix("/api/v1").unwrap();
+        assert!(stripped.route(1).is_none());
+        assert_eq!(stripped.match_route("/users/3", "GET").map(|(idx, _)| idx), Some(0));
+        assert_eq!(stripped.match_route("/", "HEAD").map(|(idx, _)| idx), Some(2));
+    }
+
+    #[test]
+    fn test_route_consumes_allowlist() {
+        let route = Route::new("/upload", vec!["POST".to_string()], None)
+            .unwrap()
+            .with_consumes(vec!["application/json".to_string(), "Image/*".to_string()]);
+
+        assert!(route.check_content_type(Some("application/json")).is_ok());
+        assert!(route.check_content_type(Some("Application/JSON; charset=utf-8")).is_ok());
+        assert!(route.check_content_type(Some("image/png")).is_ok());
+
+        let err = route.check_content_type(Some("text/plain")).unwrap_err();
+        assert!(matches!(err, RoutingError::UnsupportedMediaType(ref ct) if ct == "text/plain"));
+        assert_eq!(crate::core::ToHttpStatus::status_code(&err), 415);
+        assert!(route.check_content_type(None).is_err());
+
+        // No allowlist accepts anything
+        let open = Route::new("/upload", vec!["POST".to_string()], None).unwrap();
+        assert!(open.check_content_type(Some("text/plain")).is_ok());
+        assert!(open.check_content_type(None).is_ok());
+    }
+
+    #[test]
+    fn test_route_tree_specificity_order() {
+        let mut tree = RouteTree::new();
+        for route in routes(&[
+            ("/items/{id}", &["GET"]),
+            ("/items/special", &["GET"]),
+            ("/files/{p:path}", &["GET"]),
+            ("/files/config", &["GET"]),
+            ("/users/{name}/posts", &["GET"]),
+            ("/users/{id:int}/posts", &["GET"]),
+            ("/users/me/{tab}", &["GET"]),
+            ("/docs/{a}", &["GET"]),
+            ("/docs/{b}", &["GET"]),
+        ]) {
+            tree.add_route(route);
+        }
+
+        let matched = |path: &str| tree.match_route(path, "GET").map(|(idx, _)| idx);
+        assert_eq!(matched("/items/special"), Some(1));
+        assert_eq!(matched("/items/42"), Some(0));
+        assert_eq!(matched("/files/config"), Some(3));
+        assert_eq!(matched("/files/a/b.txt"), Some(2));
+
+        // Among dynamic routes: literal > int > str, whatever the registration order
+        assert_eq!(matched("/users/7/posts"), Some(5));
+        assert_eq!(matched("/users/bob/posts"), Some(4));
+        assert_eq!(matched("/users/me/posts"), Some(6));
+
+        // Equal specificity falls back to registration order
+        assert_eq!(matched("/docs/x"), Some(7));
+    }
+
+    #[test]
+    fn test_route_tree_static_index() {
+        let mut tree = RouteTree::new();
+        for i in 0..1000 {
+            let route = Route::new(
+                &format!("/static/{}", i),
+                vec!["GET".to_string(), "POST".to_string()],
+                None,
+            )
+            .unwrap();
+            tree.add_route(route);
+        }
+        // One hash entry per (path, method), so lookups never scan other routes
+        assert_eq!(tree.static_routes.len(), 2000);
+        for i in 0..1000 {
+            let path = format!("/static/{}", i);
+            assert_eq!(tree.match_route(&path, "GET").unwrap().0, i);
+            assert_eq!(tree.match_route(&path, "POST").unwrap().0, i);
+            assert!(tree.match_route(&path, "DELETE").is_none());
+        }
+
+        // Duplicates are reported per method and the first registration wins
+        let (tree, diagnostics) = create_route_tree(routes(&[
+            ("/health", &["GET"]),
+            ("/health", &["POST"]),
+            ("/health", &["GET", "HEAD"]),
+        ]));
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].contains("'/health' is already registered for GET"));
+        assert_eq!(tree.match_route("/health", "GET").unwrap().0, 0);
+        assert_eq!(tree.match_route("/health", "POST").unwrap().0, 1);
+        assert_eq!(tree.match_route("/health", "HEAD").unwrap().0, 2);
+    }
+
+    #[test]
+    fn test_bounded_int_param() {
+        let route = Route::new("/items/{id:int(min=1,max=9999)}", vec!["GET".to_string()], None)
+            .unwrap();
+        assert_eq!(route.int_bounds[0], Some(IntBounds { min: Some(1), max: Some(9999) }));
+        assert_eq!(route.match_path("/items/42").unwrap()["id"], "42");
+        assert_eq!(route.match_path("/items/007").unwrap()["id"], "007");
+        assert_eq!(route.match_path("/items/9999").unwrap()["id"], "9999");
+        assert!(route.match_path("/items/0").is_none());
+        assert!(route.match_path("/items/10000").is_none());
+        assert!(route.match_path("/items/99999999999999999999").is_none());
+
+        let routes = vec![route];
+        assert!(match_route("/items/0", "GET", &routes).is_none());
+        assert_eq!(match_route_detailed("/items/0", "POST", &routes), MatchStatus::NotFound);
+
+        // One-sided bounds are allowed
+        let at_least = Route::new("/p/{n:int(min=10)}", vec!["GET".to_string()], None).unwrap();
+        assert!(at_least.match_path("/p/9").is_none());
+        assert!(at_least.match_path("/p/123456").is_some());
+        let at_most = Route::new("/p/{n:int(max=5)}", vec!["GET".to_string()], None).unwrap();
+        assert!(at_most.match_path("/p/05").is_some());
+        assert!(at_most.match_path("/p/6").is_none());
+
+        for path in [
+            "/p/{n:int(min=5,max=1)}",
+            "/p/{n:int()}",
+            "/p/{n:int(min=a)}",
+            "/p/{n:int(step=2)}",
+            "/p/{n:int(min=1,min=2)}",
+            "/p/{n:int(min=1}",
+        ] {
+            let err = compile_path_regex(path).unwrap_err();
+            assert!(matches!(err, RoutingError::InvalidPath { .. }), "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_route_tree_redirect_slashes() {
+        let mut tree = RouteTree::new();
+        for route in routes(&[
+            ("/users", &["GET"]),
+            ("/docs/", &["GET"]),
+            ("/users/{id:int}", &["GET"]),
+            ("/", &["GET"]),
+        ]) {
+            tree.add_route(route);
+        }
+
+        // Disabled by default
+        assert_eq!(tree.match_detailed("/users/", "GET"), MatchStatus::NotFound);
+
+        tree.set_redirect_slashes(true);
+        assert_eq!(
+            tree.match_detailed("/users/", "GET"),
+            MatchStatus::RedirectTo("/users".to_string())
+        );
+        assert_eq!(
+            tree.match_detailed("/docs", "GET"),
+            MatchStatus::RedirectTo("/docs/".to_string())
+        );
+        assert_eq!(
+            tree.match_detailed("/users/5/", "GET"),
+            MatchStatus::RedirectTo("/users/5".to_string())
+        );
+
+        // Exact matches and 405s win over redirects
+        assert!(matches!(tree.match_detailed("/users", "GET"), MatchStatus::Matched(0, _)));
+        assert!(matches!(tree.match_detailed("/", "GET"), MatchStatus::Matched(3, _)));
+        assert_eq!(
+            tree.match_detailed("/users", "POST"),
+            MatchStatus::MethodNotAllowed(vec!["GET".to_string(), "HEAD".to_string()])
+        );
+        // Only one slash is added or removed, and only for a matching method
+        assert_eq!(tree.match_detailed("/users//", "GET"), MatchStatus::NotFound);
+        assert_eq!(tree.match_detailed("/users/", "POST"), MatchStatus::NotFound);
+
+        let mut root_only = RouteTree::new();
+        root_only.set_redirect_slashes(true);
+        root_only.add_route(routes(&[("/x", &["GET"])]).remove(0));
+        assert_eq!(root_only.match_detailed("/", "GET"), MatchStatus::NotFound);
+    }
+
+    #[test]
+    fn test_register_custom_converter() {
+        register_converter("slug", "([a-z0-9-]+)").unwrap();
+        let route = Route::new("/posts/{slug:slug}", vec!["GET".to_string()], None).unwrap();
+        assert_eq!(route.match_path("/posts/hello-world").unwrap()["slug"], "hello-world");
+        assert!(route.match_path("/posts/Hello_World").is_none());
+
+        // Captures stay aligned with later parameters
+        register_converter("iso_date", r"(\d{4}-\d{2}-\d{2})").unwrap();
+        let route = Route::new("/log/{day:iso_date}/{id:int}", vec!["GET".to_string()], None)
+            .unwrap();
+        let params = route.match_path("/log/2024-02-29/7").unwrap();
+        assert_eq!(params["day"], "2024-02-29");
+        assert_eq!(params["id"], "7");
+
+        for fragment in ["[a-z]+", "(a)(b)", "(a)b(c)", "(a)|b", "(?:a)", "(?P<x>a)", "(a"] {
+            let err = register_converter("broken", fragment).unwrap_err();
+            assert!(matches!(err, RoutingError::InvalidConverter { .. }), "{}", fragment);
+        }
+        assert!(register_converter("not-a-name", "([a])").is_err());
+        assert!(register_converter("int", "([0-9]{3})").is_err());
+        assert!(register_converter("brackets", r"([()\]]+)").is_ok());
+        assert!(compile_path_regex("/x/{id:broken}").is_err());
+    }
+
+    #[test]
+    fn test_route_tree_auto_head() {
+        let mut tree = RouteTree::new();
+        for route in routes(&[
+            ("/ping", &["GET"]),
+            ("/users/{id:int}", &["GET"]),
+            ("/files", &["GET"]),
+            ("/files", &["HEAD"]),
+        ]) {
+            tree.add_route(route);
+        }
+
+        assert_eq!(tree.match_route("/ping", "HEAD"), Some((0, HashMap::new())));
+        let (idx, params) = tree.match_route("/users/7", "HEAD").unwrap();
+        assert_eq!(idx, 1);
+        assert_eq!(params.get("id"), Some(&"7".to_string()));
+        // An explicit HEAD route wins over the derived one
+        assert_eq!(tree.match_route("/files", "HEAD").map(|(i, _)| i), Some(3));
+        assert_eq!(
+            tree.match_detailed("/ping", "POST"),
+            MatchStatus::MethodNotAllowed(vec!["GET".to_string(), "HEAD".to_string()])
+        );
+
+        tree.set_auto_head(false);
+        assert!(tree.match_route("/ping", "HEAD").is_none());
+        assert_eq!(
+            tree.match_detailed("/ping", "POST"),
+            MatchStatus::MethodNotAllowed(vec!["GET".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_match_with_status_wrong_method() {
+        let mut tree = RouteTree::new();
+        for route in routes(&[
+            ("/items", &["GET"]),
+            ("/items/{id:int}", &["GET"]),
+            ("/items/{name}", &["DELETE"]),
+        ]) {
+            tree.add_route(route);
+        }
+        tree.set_auto_head(false);
+
+        assert_eq!(
+            tree.match_with_status("/items", "POST"),
+            MatchStatus::MethodNotAllowed(vec!["GET".to_string()])
+        );
+        // Methods are collected from every route whose path matches
+        assert_eq!(
+            tree.match_with_status("/items/3", "POST"),
+            MatchStatus::MethodNotAllowed(vec!["GET".to_string(), "DELETE".to_string()])
+        );
+        assert!(matches!(tree.match_with_status("/items", "GET"), MatchStatus::Matched(0, _)));
+        assert_eq!(tree.match_with_status("/other", "GET"), MatchStatus::NotFound);
+
+        // Redirects are left to `match_detailed`
+        tree.set_redirect_slashes(true);
+        assert_eq!(tree.match_with_status("/items/", "GET"), MatchStatus::NotFound);
+    }
+
+    #[test]
+    fn test_method_match_ignores_case() {
+        let mut tree = RouteTree::new();
+        for route in routes(&[("/health", &["GET"]), ("/users/{id}", &["GET"])]) {
+            tree.add_route(route);
+        }
+
+        assert!(matches!(tree.match_with_status("/health", "get"), MatchStatus::Matched(0, _)));
+        match tree.match_with_status("/users/7", "get") {
+            MatchStatus::Matched(1, params) => assert_eq!(params["id"], "7"),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(
+            match_route("/users/7", "Get", &routes(&[("/users/{id}", &["GET"])])).map(|m| m.0),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn test_duplicate_param_name_rejected() {
+        match compile_path_regex("/users/{id}/x/{id}") {
+            Err(RoutingError::InvalidPath { message, position }) => {
+                assert!(message.contains("'id'"), "{}", message);
+                assert_eq!(position, 15);
+            }
+            other => panic!("expected InvalidPath, got {:?}", other),
+        }
+        assert!(compile_path_regex("/users/{id}/x/{id:int}").is_err());
+        assert!(compile_path_regex("/users/{id}/x/{other_id}").is_ok());
+    }
+
+    #[test]
+    fn test_regex_cache_evicts_least_recently_used() {
+        let compile = |p: &str| Arc::new(Regex::new(p).unwrap());
+        let mut cache = RegexCache::new(3);
+        for p in ["^/a$", "^/b$", "^/c$"] {
+            cache.insert(p, compile(p));
+        }
+        // Least recently used is now "/b", then "/a", then "/c"
+        let held = cache.get("^/b$").unwrap();
+        assert!(cache.get("^/a$").is_some());
+        assert!(cache.get("^/c$").is_some());
+        cache.insert("^/d$", compile("^/d$"));
+
+        assert_eq!(cache.len(), 3);
+        assert!(cache.get("^/b$").is_none());
+        assert!(cache.get("^/a$").is_some());
+        // Evicted regexes stay usable for whoever still holds them
+        assert!(held.is_match("/b"));
+
+        cache.set_capacity(1);
+        assert_eq!(cache.len(), 1);
+        assert!(cache.get("^/a$").is_some());
+    }
+
+    #[test]
+    fn test_route_tree_remove_route() {
+        let mut tree = RouteTree::new();
+        for route in routes(&[
+            ("/users", &["GET"]),
+            ("/users/{id:int}", &["GET", "DELETE"]),
+            ("/items/{name}", &["GET"]),
+        ]) {
+            tree.add_route(route);
+        }
+
+        assert!(tree.remove_route("/users", "GET"));
+        assert!(!tree.remove_route("/users", "GET"));
+        assert_eq!(tree.len(), 2);
+        assert!(tree.route(0).is_none());
+        assert!(tree.match_route("/users", "GET").is_none());
+        // The remaining routes keep their indices
+        assert_eq!(tree.match_route("/users/1", "GET").map(|(idx, _)| idx), Some(1));
+        assert_eq!(tree.match_route("/items/pen", "GET").map(|(idx, _)| idx), Some(2));
+
+        // Removing one method leaves the others in place
+        assert!(tree.remove_route("/users/{id:int}", "DELETE"));
+        assert!(tree.match_route("/users/1", "DELETE").is_none());
+        assert!(tree.match_route("/users/1", "GET").is_some());
+        assert!(tree.remove_route("/users/{id:int}", "GET"));
+        assert!(tree.match_route("/users/1", "GET").is_none());
+        assert_eq!(tree.routes().map(|r| r.path.as_str()).collect::<Vec<_>>(), ["/items/{name}"]);
+    }
+
+    #[test]
+    fn test_route_tree_replace_route() {
+        let mut tree = RouteTree::new();
+        for route in routes(&[
+            ("/ping", &["GET"]),
+            ("/ping", &["POST"]),
+            ("/users/{id}", &["GET"]),
+        ]) {
+            tree.add_route(route);
+        }
+
+        let replacement = routes(&[("/ping", &["PUT"])]).remove(0);
+        tree.replace_route(replacement).unwrap();
+        assert!(tree.match_route("/ping", "GET").is_none());
+        assert_eq!(tree.match_route("/ping", "PUT").map(|(idx, _)| idx), Some(0));
+        assert_eq!(tree.match_route("/ping", "POST").map(|(idx, _)| idx), Some(1));
+
+        let replacement = routes(&[("/users/{id}", &["PATCH"])]).remove(0);
+        tree.replace_route(replacement).unwrap();
+        assert_eq!(tree.match_route("/users/4", "PATCH").map(|(idx, _)| idx), Some(2));
+
+        let missing = routes(&[("/nope", &["GET"])]).remove(0);
+        assert!(matches!(tree.replace_route(missing), Err(RoutingError::RouteNotFound)));
+    }
+
+    #[test]
+    fn test_catch_all_path_param() {
+        let trailing = Route::new("/files/{p:path}", vec!["GET".to_string()], None).unwrap();
+        let params = trailing.match_path("/files/a/b/download").unwrap();
+        assert_eq!(params.get("p"), Some(&"a/b/download".to_string()));
+
+        let mid = Route::new("/files/{p:path}/download", vec!["GET".to_string()], None).unwrap();
+        let params = mid.match_path("/files/a/b/download").unwrap();
+        assert_eq!(params.get("p"), Some(&"a/b".to_string()));
+        assert!(mid.match_path("/files/download").is_none());
+
+        // The earlier catch-all stops at the first literal that lets the rest match
+        let two = Route::new("/mirror/{src:path}/to/{dst:path}", vec!["GET".to_string()], None)
+            .unwrap();
+        let params = two.match_path("/mirror/a/to/b/to/c").unwrap();
+        assert_eq!(params.get("src"), Some(&"a".to_string()));
+        assert_eq!(params.get("dst"), Some(&"b/to/c".to_string()));
+    }
+}
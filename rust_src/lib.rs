@@ -22,32 +22,54 @@ fn _fastapi_rust(m: &Bound<'_, PyModule>) -> PyResult<()> {
     // Core routing functions
     m.add_function(wrap_pyfunction!(create_api_route, m)?)?;
     m.add_function(wrap_pyfunction!(match_route, m)?)?;
+    m.add_function(wrap_pyfunction!(match_route_detailed, m)?)?;
+    m.add_function(wrap_pyfunction!(match_route_with_status, m)?)?;
+    m.add_function(wrap_pyfunction!(create_route_tree, m)?)?;
     m.add_function(wrap_pyfunction!(compile_path_regex, m)?)?;
+    m.add_function(wrap_pyfunction!(register_path_converter, m)?)?;
+    m.add_function(wrap_pyfunction!(set_regex_cache_capacity, m)?)?;
+    m.add_function(wrap_pyfunction!(resolve_effective_method, m)?)?;
+    m.add_function(wrap_pyfunction!(evaluate_cors_preflight, m)?)?;
 
     // Parameter validation functions
     m.add_function(wrap_pyfunction!(validate_path_params, m)?)?;
     m.add_function(wrap_pyfunction!(validate_query_params, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_query_arrays, m)?)?;
     m.add_function(wrap_pyfunction!(validate_header_params, m)?)?;
     m.add_function(wrap_pyfunction!(validate_body_params, m)?)?;
 
     // Serialization functions
     m.add_function(wrap_pyfunction!(jsonable_encoder, m)?)?;
+    m.add_function(wrap_pyfunction!(encode_to_writer, m)?)?;
     m.add_function(wrap_pyfunction!(serialize_response, m)?)?;
     m.add_function(wrap_pyfunction!(deserialize_request, m)?)?;
+    m.add_function(wrap_pyfunction!(json_diff, m)?)?;
 
     // Security functions
     m.add_function(wrap_pyfunction!(constant_time_compare, m)?)?;
     m.add_function(wrap_pyfunction!(verify_api_key, m)?)?;
     m.add_function(wrap_pyfunction!(hash_password, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_authorization, m)?)?;
+    m.add_function(wrap_pyfunction!(weighted_choice, m)?)?;
 
     // Utility functions
     m.add_function(wrap_pyfunction!(generate_unique_id, m)?)?;
     m.add_function(wrap_pyfunction!(parse_content_type, m)?)?;
+    m.add_function(wrap_pyfunction!(negotiate_charset, m)?)?;
+    m.add_function(wrap_pyfunction!(negotiate, m)?)?;
+    m.add_function(wrap_pyfunction!(compute_etag, m)?)?;
+    m.add_function(wrap_pyfunction!(evaluate_preconditions, m)?)?;
     m.add_function(wrap_pyfunction!(convert_python_type, m)?)?;
 
     // Type system
     m.add_class::<types::FastApiRoute>()?;
+    m.add_class::<types::RouteMatchResult>()?;
+    m.add_class::<types::RouteTreeWrapper>()?;
     m.add_class::<types::ValidationResult>()?;
+    m.add_class::<types::CompiledSchema>()?;
+    m.add_class::<types::SessionStore>()?;
+    m.add_class::<types::RateLimiterWrapper>()?;
+    m.add_class::<types::ResponseCacheWrapper>()?;
     m.add_class::<types::RequestData>()?;
 
     Ok(())
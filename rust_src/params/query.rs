@@ -0,0 +1,168 @@
+use crate::core::request::form_urldecode;
+use crate::params::validation::{Result, ValidationError};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+/// Largest `name[index]` accepted, so one parameter cannot allocate a huge array
+pub const MAX_QUERY_ARRAY_INDEX: usize = 1000;
+
+/// What to do about gaps such as `items[0]` and `items[2]` without `items[1]`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SparseIndices {
+    /// Fill missing positions with `null`
+    #[default]
+    FillNull,
+    Reject,
+}
+
+#[derive(Default)]
+struct IndexedValues {
+    indexed: BTreeMap<usize, String>,
+    appended: Vec<String>,
+}
+
+/// Decoded `key=value` pairs in the order they appear in `query`
+pub fn query_pairs(query: &str) -> Vec<(String, String)> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (form_urldecode(key), form_urldecode(value))
+        })
+        .collect()
+}
+
+/// `name[index]` as `(name, Some(index))` and `name[]` as `(name, None)`
+fn split_bracket_key(key: &str) -> Option<(&str, Option<&str>)> {
+    let inner_and_base = key.strip_suffix(']')?;
+    let open = inner_and_base.rfind('[')?;
+    let (base, inner) = (&inner_and_base[..open], &inner_and_base[open + 1..]);
+    if base.is_empty() || base.contains(['[', ']']) {
+        return None;
+    }
+    Some((base, (!inner.is_empty()).then_some(inner)))
+}
+
+/// Collapse `name[0]`, `name[1]` and `name[]` pairs into one array per name.
+///
+/// Numeric indices are ordered by value, with `name[]` values appended after
+/// them in encounter order. Other keys, including non-numeric brackets such as
+/// `filter[status]`, keep their first value as a string.
+pub fn group_query_arrays(
+    pairs: &[(String, String)],
+    sparse: SparseIndices,
+) -> Result<HashMap<String, Value>> {
+    let mut scalars: HashMap<String, Value> = HashMap::new();
+    let mut arrays: HashMap<String, IndexedValues> = HashMap::new();
+
+    for (key, value) in pairs {
+        let bracket = split_bracket_key(key)
+            .map(|(base, inner)| (base, inner.map(|i| i.parse::<usize>().map_err(|_| i))));
+        match bracket {
+            Some((base, Some(Ok(index)))) => {
+                if index > MAX_QUERY_ARRAY_INDEX {
+                    return Err(ValidationError::OutOfRange {
+                        param: base.to_string(),
+                        value: format!("index {} exceeds {}", index, MAX_QUERY_ARRAY_INDEX),
+                    });
+                }
+                let values = arrays.entry(base.to_string()).or_default();
+                if values.indexed.insert(index, value.clone()).is_some() {
+                    return Err(ValidationError::InvalidFormat {
+                        param: base.to_string(),
+                        value: format!("index {} given more than once", index),
+                    });
+                }
+            }
+            Some((base, None)) => {
+                let values = arrays.entry(base.to_string()).or_default();
+                values.appended.push(value.clone());
+            }
+            _ => {
+                scalars
+                    .entry(key.clone())
+                    .or_insert_with(|| Value::String(value.clone()));
+            }
+        }
+    }
+
+    for (name, values) in arrays {
+        if scalars.contains_key(&name) {
+            return Err(ValidationError::InvalidFormat {
+                param: name,
+                value: "given both as a plain and as an indexed parameter".to_string(),
+            });
+        }
+
+        let len = values.indexed.keys().next_back().map_or(0, |last| last + 1);
+        let mut items = vec![Value::Null; len];
+        for (index, value) in values.indexed {
+            items[index] = Value::String(value);
+        }
+        if sparse == SparseIndices::Reject {
+            if let Some(gap) = items.iter().position(Value::is_null) {
+                return Err(ValidationError::InvalidFormat {
+                    param: name,
+                    value: format!("missing index {}", gap),
+                });
+            }
+        }
+        items.extend(values.appended.into_iter().map(Value::String));
+        scalars.insert(name, Value::Array(items));
+    }
+
+    Ok(scalars)
+}
+
+/// `group_query_arrays` over a raw query string
+pub fn parse_query_arrays(query: &str, sparse: SparseIndices) -> Result<HashMap<String, Value>> {
+    group_query_arrays(&query_pairs(query), sparse)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_indexed_values_are_ordered() {
+        let params =
+            parse_query_arrays("items[1]=b&items[0]=a&items[2]=c", SparseIndices::Reject).unwrap();
+        assert_eq!(params["items"], json!(["a", "b", "c"]));
+
+        // `name[]` appends after the indexed values, in encounter order
+        let params =
+            parse_query_arrays("tags[]=y&tags[0]=x&tags[]=z", SparseIndices::Reject).unwrap();
+        assert_eq!(params["tags"], json!(["x", "y", "z"]));
+    }
+
+    #[test]
+    fn test_sparse_indices() {
+        let query = "items[0]=a&items[2]=c";
+        let params = parse_query_arrays(query, SparseIndices::FillNull).unwrap();
+        assert_eq!(params["items"], json!(["a", null, "c"]));
+
+        assert!(matches!(
+            parse_query_arrays(query, SparseIndices::Reject),
+            Err(ValidationError::InvalidFormat { param, .. }) if param == "items"
+        ));
+        assert!(parse_query_arrays("items[5000]=a", SparseIndices::FillNull).is_err());
+        assert!(parse_query_arrays("items[0]=a&items[0]=b", SparseIndices::FillNull).is_err());
+    }
+
+    #[test]
+    fn test_mixed_scalar_and_bracket_params() {
+        let params = parse_query_arrays(
+            "q=rust%20web&items[]=a&page=2&items[]=b&filter[status]=open&q=ignored",
+            SparseIndices::default(),
+        )
+        .unwrap();
+        assert_eq!(params["q"], json!("rust web"));
+        assert_eq!(params["page"], json!("2"));
+        assert_eq!(params["items"], json!(["a", "b"]));
+        assert_eq!(params["filter[status]"], json!("open"));
+
+        assert!(parse_query_arrays("items=a&items[0]=b", SparseIndices::default()).is_err());
+    }
+}
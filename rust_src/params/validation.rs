@@ -1,8 +1,13 @@
+use indexmap::IndexMap;
 use once_cell::sync::Lazy;
 use regex::Regex;
-use serde_json::Value;
+use serde_json::{Map, Value};
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::sync::Arc;
 use thiserror::Error;
+use unicase::UniCase;
+use unicode_normalization::UnicodeNormalization;
 
 #[derive(Error, Debug)]
 pub enum ValidationError {
@@ -32,6 +37,10 @@ pub enum ValidationError {
         len: usize,
         min: usize,
     },
+    #[error("Header {0} contains CR, LF or NUL characters")]
+    InvalidHeaderValue(String),
+    #[error("Body violates schema at '{path}': {message}")]
+    SchemaViolation { path: String, message: String },
 }
 
 pub type Result<T> = std::result::Result<T, ValidationError>;
@@ -40,11 +49,12 @@ pub type Result<T> = std::result::Result<T, ValidationError>;
 pub struct ValidationResult {
     pub valid: bool,
     pub errors: Vec<ValidationError>,
-    pub validated_data: HashMap<String, Value>,
+    /// Keyed in schema declaration order, so output is reproducible
+    pub validated_data: IndexMap<String, Value>,
 }
 
 impl ValidationResult {
-    pub fn success(data: HashMap<String, Value>) -> Self {
+    pub fn success(data: IndexMap<String, Value>) -> Self {
         Self {
             valid: true,
             errors: Vec::new(),
@@ -56,7 +66,7 @@ impl ValidationResult {
         Self {
             valid: false,
             errors,
-            validated_data: HashMap::new(),
+            validated_data: IndexMap::new(),
         }
     }
 
@@ -76,8 +86,25 @@ pub struct ParameterSchema {
     pub max_length: Option<usize>,
     pub minimum: Option<f64>,
     pub maximum: Option<f64>,
+    pub exclusive_minimum: Option<f64>,
+    pub exclusive_maximum: Option<f64>,
+    /// Step numbers must be a whole multiple of; non-positive values are ignored
+    pub multiple_of: Option<f64>,
     pub pattern: Option<String>,
     pub enum_values: Option<Vec<String>>,
+    pub enum_json: Option<Vec<Value>>,
+    pub flag_mask: Option<u64>,
+    pub allow_multiple: bool,
+    /// Element type of an `array` parameter; defaults to `string`
+    pub items_type: Option<String>,
+    pub min_items: Option<usize>,
+    pub max_items: Option<usize>,
+    pub case_sensitive: bool,
+    /// Trim surrounding whitespace before any other check
+    pub strip_whitespace: bool,
+    pub normalize_unicode: bool,
+    pub coerce_integral_floats: bool,
+    pub compiled_pattern: Option<Arc<Regex>>,
 }
 
 impl ParameterSchema {
@@ -91,8 +118,22 @@ impl ParameterSchema {
             max_length: None,
             minimum: None,
             maximum: None,
+            exclusive_minimum: None,
+            exclusive_maximum: None,
+            multiple_of: None,
             pattern: None,
             enum_values: None,
+            enum_json: None,
+            flag_mask: None,
+            allow_multiple: false,
+            items_type: None,
+            min_items: None,
+            max_items: None,
+            case_sensitive: true,
+            strip_whitespace: true,
+            normalize_unicode: false,
+            coerce_integral_floats: false,
+            compiled_pattern: None,
         }
     }
 
@@ -118,15 +159,234 @@ impl ParameterSchema {
         self
     }
 
+    /// Like `with_range`, but the bounds themselves are rejected when `exclusive`
+    pub fn with_bounds(mut self, min: Option<f64>, max: Option<f64>, exclusive: bool) -> Self {
+        if exclusive {
+            self.exclusive_minimum = min;
+            self.exclusive_maximum = max;
+        } else {
+            self.minimum = min;
+            self.maximum = max;
+        }
+        self
+    }
+
+    /// Values must be greater than `bound`
+    pub fn gt(mut self, bound: f64) -> Self {
+        self.exclusive_minimum = Some(bound);
+        self
+    }
+
+    /// Values must be greater than or equal to `bound`
+    pub fn ge(mut self, bound: f64) -> Self {
+        self.minimum = Some(bound);
+        self
+    }
+
+    /// Values must be less than `bound`
+    pub fn lt(mut self, bound: f64) -> Self {
+        self.exclusive_maximum = Some(bound);
+        self
+    }
+
+    /// Values must be less than or equal to `bound`
+    pub fn le(mut self, bound: f64) -> Self {
+        self.maximum = Some(bound);
+        self
+    }
+
+    pub fn multiple_of(mut self, step: f64) -> Self {
+        self.multiple_of = Some(step);
+        self
+    }
+
     pub fn with_pattern(mut self, pattern: String) -> Self {
         self.pattern = Some(pattern);
+        self.compiled_pattern = None;
         self
     }
 
+    /// Compile `pattern` up front so validation does not rebuild the regex per value
+    pub fn compile_pattern(&mut self) -> Result<()> {
+        if let Some(pattern) = &self.pattern {
+            let regex = Regex::new(pattern).map_err(|_| ValidationError::InvalidFormat {
+                param: self.name.clone(),
+                value: format!("Invalid regex pattern: {}", pattern),
+            })?;
+            self.compiled_pattern = Some(Arc::new(regex));
+        }
+        Ok(())
+    }
+
     pub fn with_enum(mut self, values: Vec<String>) -> Self {
         self.enum_values = Some(values);
         self
     }
+
+    /// Enum members as raw JSON values, used by `int-enum`
+    pub fn with_json_enum(mut self, values: Vec<Value>) -> Self {
+        self.enum_json = Some(values);
+        self
+    }
+
+    /// Bits a `bitflags` value may set
+    pub fn with_flag_mask(mut self, mask: u64) -> Self {
+        self.flag_mask = Some(mask);
+        self
+    }
+
+    /// Match enum members using Unicode case folding
+    pub fn case_insensitive(mut self) -> Self {
+        self.case_sensitive = false;
+        self
+    }
+
+    /// Validate values exactly as received instead of trimming them
+    pub fn preserve_whitespace(mut self) -> Self {
+        self.strip_whitespace = false;
+        self
+    }
+
+    /// NFC-normalize input (and enum members) before validating
+    pub fn with_unicode_normalization(mut self) -> Self {
+        self.normalize_unicode = true;
+        self
+    }
+
+    /// Accept whole-number floats such as `"42.0"` for integer parameters
+    pub fn coerce_integral_floats(mut self) -> Self {
+        self.coerce_integral_floats = true;
+        self
+    }
+
+    /// Validate every supplied value into an array instead of keeping only the first
+    pub fn allow_multiple(mut self) -> Self {
+        self.allow_multiple = true;
+        self
+    }
+
+    /// Element type for an `array` parameter; the scalar constraints apply per element
+    pub fn with_items_type(mut self, items_type: String) -> Self {
+        self.items_type = Some(items_type);
+        self
+    }
+
+    pub fn with_items_range(mut self, min: Option<usize>, max: Option<usize>) -> Self {
+        self.min_items = min;
+        self.max_items = max;
+        self
+    }
+
+    fn is_array(&self) -> bool {
+        self.param_type == "array"
+    }
+
+    /// Type each supplied value is validated as
+    fn element_type(&self) -> &str {
+        if self.is_array() {
+            self.items_type.as_deref().unwrap_or("string")
+        } else {
+            &self.param_type
+        }
+    }
+
+    /// Describe this schema as an OpenAPI/JSON Schema fragment matching what
+    /// `validate_single_parameter` enforces
+    pub fn to_json_schema(&self) -> Value {
+        let element_type = self.element_type();
+        let (json_type, format) = match element_type {
+            "integer" | "int" | "int-enum" | "bitflags" => ("integer", None),
+            "number" | "float" => ("number", None),
+            "boolean" | "bool" => ("boolean", None),
+            "email" => ("string", Some("email")),
+            "uuid" => ("string", Some("uuid")),
+            "datetime" => ("string", Some("date-time")),
+            "date" => ("string", Some("date")),
+            "time" => ("string", Some("time")),
+            "ipv4" => ("string", Some("ipv4")),
+            "ipv6" => ("string", Some("ipv6")),
+            "url" => ("string", Some("uri")),
+            "json-pointer" => ("string", Some("json-pointer")),
+            _ => ("string", None),
+        };
+        let is_integer = json_type == "integer";
+
+        let mut schema = Map::new();
+        schema.insert("type".to_string(), Value::String(json_type.to_string()));
+        if let Some(format) = format {
+            schema.insert("format".to_string(), Value::String(format.to_string()));
+        }
+        if let Some(min_length) = self.min_length {
+            schema.insert("minLength".to_string(), Value::from(min_length));
+        }
+        if let Some(max_length) = self.max_length {
+            schema.insert("maxLength".to_string(), Value::from(max_length));
+        }
+        if let Some(minimum) = self.minimum {
+            schema.insert("minimum".to_string(), json_number(minimum, is_integer));
+        }
+        if let Some(maximum) = self.maximum {
+            schema.insert("maximum".to_string(), json_number(maximum, is_integer));
+        }
+        if let Some(minimum) = self.exclusive_minimum {
+            schema.insert(
+                "exclusiveMinimum".to_string(),
+                json_number(minimum, is_integer),
+            );
+        }
+        if let Some(maximum) = self.exclusive_maximum {
+            schema.insert(
+                "exclusiveMaximum".to_string(),
+                json_number(maximum, is_integer),
+            );
+        }
+        if let Some(step) = self.multiple_of {
+            schema.insert("multipleOf".to_string(), json_number(step, is_integer));
+        }
+        if let Some(pattern) = &self.pattern {
+            schema.insert("pattern".to_string(), Value::String(pattern.clone()));
+        }
+        if element_type == "int-enum" {
+            if let Some(members) = &self.enum_json {
+                schema.insert("enum".to_string(), Value::Array(members.clone()));
+            }
+        } else if let Some(enum_values) = &self.enum_values {
+            schema.insert(
+                "enum".to_string(),
+                Value::Array(enum_values.iter().cloned().map(Value::String).collect()),
+            );
+        }
+
+        let mut schema = if self.allow_multiple || self.is_array() {
+            let mut array = Map::new();
+            array.insert("type".to_string(), Value::String("array".to_string()));
+            array.insert("items".to_string(), Value::Object(schema));
+            if let Some(min_items) = self.min_items {
+                array.insert("minItems".to_string(), Value::from(min_items));
+            }
+            if let Some(max_items) = self.max_items {
+                array.insert("maxItems".to_string(), Value::from(max_items));
+            }
+            array
+        } else {
+            schema
+        };
+
+        if let Some(default) = &self.default {
+            schema.insert("default".to_string(), default.clone());
+        }
+
+        Value::Object(schema)
+    }
+}
+
+/// Emit whole-number bounds of integer schemas as JSON integers
+fn json_number(value: f64, is_integer: bool) -> Value {
+    if is_integer && value.fract() == 0.0 && value.abs() < i64::MAX as f64 {
+        Value::from(value as i64)
+    } else {
+        Value::from(value)
+    }
 }
 
 static EMAIL_REGEX: Lazy<Regex> =
@@ -136,84 +396,308 @@ static UUID_REGEX: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r"^[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}$").unwrap()
 });
 
+// RFC 6901: "~" is only valid as the escape sequences "~0" and "~1"
+static JSON_POINTER_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(/([^~/]|~[01])*)*$").unwrap());
+
+// ISO 3166-1 alpha-2, sorted for binary search
+const COUNTRY_CODES: &[&str] = &[
+    "AD", "AE", "AF", "AG", "AI", "AL", "AM", "AO", "AQ", "AR", "AS", "AT", "AU", "AW", "AX", "AZ",
+    "BA", "BB", "BD", "BE", "BF", "BG", "BH", "BI", "BJ", "BL", "BM", "BN", "BO", "BQ", "BR", "BS",
+    "BT", "BV", "BW", "BY", "BZ", "CA", "CC", "CD", "CF", "CG", "CH", "CI", "CK", "CL", "CM", "CN",
+    "CO", "CR", "CU", "CV", "CW", "CX", "CY", "CZ", "DE", "DJ", "DK", "DM", "DO", "DZ", "EC", "EE",
+    "EG", "EH", "ER", "ES", "ET", "FI", "FJ", "FK", "FM", "FO", "FR", "GA", "GB", "GD", "GE", "GF",
+    "GG", "GH", "GI", "GL", "GM", "GN", "GP", "GQ", "GR", "GS", "GT", "GU", "GW", "GY", "HK", "HM",
+    "HN", "HR", "HT", "HU", "ID", "IE", "IL", "IM", "IN", "IO", "IQ", "IR", "IS", "IT", "JE", "JM",
+    "JO", "JP", "KE", "KG", "KH", "KI", "KM", "KN", "KP", "KR", "KW", "KY", "KZ", "LA", "LB", "LC",
+    "LI", "LK", "LR", "LS", "LT", "LU", "LV", "LY", "MA", "MC", "MD", "ME", "MF", "MG", "MH", "MK",
+    "ML", "MM", "MN", "MO", "MP", "MQ", "MR", "MS", "MT", "MU", "MV", "MW", "MX", "MY", "MZ", "NA",
+    "NC", "NE", "NF", "NG", "NI", "NL", "NO", "NP", "NR", "NU", "NZ", "OM", "PA", "PE", "PF", "PG",
+    "PH", "PK", "PL", "PM", "PN", "PR", "PS", "PT", "PW", "PY", "QA", "RE", "RO", "RS", "RU", "RW",
+    "SA", "SB", "SC", "SD", "SE", "SG", "SH", "SI", "SJ", "SK", "SL", "SM", "SN", "SO", "SR", "SS",
+    "ST", "SV", "SX", "SY", "SZ", "TC", "TD", "TF", "TG", "TH", "TJ", "TK", "TL", "TM", "TN", "TO",
+    "TR", "TT", "TV", "TW", "TZ", "UA", "UG", "UM", "US", "UY", "UZ", "VA", "VC", "VE", "VG", "VI",
+    "VN", "VU", "WF", "WS", "YE", "YT", "ZA", "ZM", "ZW",
+];
+
+// ISO 4217 alpha-3, sorted for binary search
+const CURRENCY_CODES: &[&str] = &[
+    "AED", "AFN", "ALL", "AMD", "AOA", "ARS", "AUD", "AWG", "AZN", "BAM", "BBD", "BDT", "BGN",
+    "BHD", "BIF", "BMD", "BND", "BOB", "BOV", "BRL", "BSD", "BTN", "BWP", "BYN", "BZD", "CAD",
+    "CDF", "CHE", "CHF", "CHW", "CLF", "CLP", "CNY", "COP", "COU", "CRC", "CUP", "CVE", "CZK",
+    "DJF", "DKK", "DOP", "DZD", "EGP", "ERN", "ETB", "EUR", "FJD", "FKP", "GBP", "GEL", "GHS",
+    "GIP", "GMD", "GNF", "GTQ", "GYD", "HKD", "HNL", "HTG", "HUF", "IDR", "ILS", "INR", "IQD",
+    "IRR", "ISK", "JMD", "JOD", "JPY", "KES", "KGS", "KHR", "KMF", "KPW", "KRW", "KWD", "KYD",
+    "KZT", "LAK", "LBP", "LKR", "LRD", "LSL", "LYD", "MAD", "MDL", "MGA", "MKD", "MMK", "MNT",
+    "MOP", "MRU", "MUR", "MVR", "MWK", "MXN", "MXV", "MYR", "MZN", "NAD", "NGN", "NIO", "NOK",
+    "NPR", "NZD", "OMR", "PAB", "PEN", "PGK", "PHP", "PKR", "PLN", "PYG", "QAR", "RON", "RSD",
+    "RUB", "RWF", "SAR", "SBD", "SCR", "SDG", "SEK", "SGD", "SHP", "SLE", "SOS", "SRD", "SSP",
+    "STN", "SVC", "SYP", "SZL", "THB", "TJS", "TMT", "TND", "TOP", "TRY", "TTD", "TWD", "TZS",
+    "UAH", "UGX", "USD", "USN", "UYI", "UYU", "UYW", "UZS", "VED", "VES", "VND", "VUV", "WST",
+    "XAF", "XAG", "XAU", "XBA", "XBB", "XBC", "XBD", "XCD", "XCG", "XDR", "XOF", "XPD", "XPF",
+    "XPT", "XSU", "XTS", "XUA", "XXX", "YER", "ZAR", "ZMW", "ZWG",
+];
+
+/// A schema map parsed once, with patterns pre-compiled, for reuse across requests
+#[derive(Debug, Clone)]
+pub struct CompiledSchema {
+    schemas: Vec<ParameterSchema>,
+}
+
+impl CompiledSchema {
+    pub fn new(schema: IndexMap<String, Value>) -> Result<Self> {
+        let mut schemas = parse_schema_map(schema)?;
+        for param_schema in &mut schemas {
+            param_schema.compile_pattern()?;
+        }
+        Ok(Self { schemas })
+    }
+
+    pub fn schemas(&self) -> &[ParameterSchema] {
+        &self.schemas
+    }
+
+    pub fn validate(&self, params: HashMap<String, String>) -> Result<ValidationResult> {
+        self.validate_multi(params.into_iter().map(|(k, v)| (k, vec![v])).collect())
+    }
+
+    /// Validate parameters that may repeat, such as those from
+    /// `core::request::parse_query_string`; `array` schemas see every value
+    pub fn validate_multi(&self, params: HashMap<String, Vec<String>>) -> Result<ValidationResult> {
+        validate_parameters(&params, &self.schemas)
+    }
+}
+
+/// Validated `limit`/`offset` window for list endpoints
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pagination {
+    pub limit: u64,
+    pub offset: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PaginationDefaults {
+    pub limit: u64,
+    pub max_limit: u64,
+}
+
+impl Default for PaginationDefaults {
+    fn default() -> Self {
+        Self {
+            limit: 50,
+            max_limit: 500,
+        }
+    }
+}
+
+/// Read `limit`/`offset`, or 1-based `page` with `page_size`, from query params.
+///
+/// An explicit `offset` takes precedence over `page`. The page size is
+/// clamped to `max_limit`; negative or non-integer values are errors.
+pub fn parse_pagination(
+    params: &HashMap<String, String>,
+    defaults: PaginationDefaults,
+) -> Result<Pagination> {
+    let read = |name: &str| -> Result<Option<u64>> {
+        let Some(raw) = params.get(name) else {
+            return Ok(None);
+        };
+        let value = raw
+            .trim()
+            .parse::<i64>()
+            .map_err(|_| ValidationError::InvalidType {
+                param: name.to_string(),
+                expected: "integer".to_string(),
+                actual: raw.clone(),
+            })?;
+        if value < 0 {
+            return Err(ValidationError::OutOfRange {
+                param: name.to_string(),
+                value: raw.clone(),
+            });
+        }
+        Ok(Some(value as u64))
+    };
+
+    let limit = read("page_size")?
+        .or(read("limit")?)
+        .unwrap_or(defaults.limit)
+        .min(defaults.max_limit);
+
+    let offset = match (read("offset")?, read("page")?) {
+        (Some(offset), _) => offset,
+        (None, Some(0)) => {
+            return Err(ValidationError::OutOfRange {
+                param: "page".to_string(),
+                value: "0".to_string(),
+            })
+        }
+        (None, Some(page)) => (page - 1).saturating_mul(limit),
+        (None, None) => 0,
+    };
+
+    Ok(Pagination { limit, offset })
+}
+
 pub fn validate_path_params(
     params: HashMap<String, String>,
-    schema: HashMap<String, Value>,
+    schema: IndexMap<String, Value>,
 ) -> Result<ValidationResult> {
+    if schema.is_empty() {
+        return Ok(ValidationResult::success(IndexMap::new()));
+    }
     let schemas = parse_schema_map(schema)?;
-    validate_parameters(
-        params.into_iter().map(|(k, v)| (k, vec![v])).collect(),
-        schemas,
-    )
+    let params = params.into_iter().map(|(k, v)| (k, vec![v])).collect();
+    validate_parameters(&params, &schemas)
 }
 
+/// Validate query parameters, as grouped by `core::request::parse_query_string`,
+/// against a schema map.
+///
+/// Every value of a repeated key reaches `array` and `allowMultiple` schemas.
+/// When `treat_empty_as_missing` is set, `?q=` is handled as if `q` were
+/// absent, so defaults apply and required parameters report as missing.
+/// Only schema-declared parameters are returned, so an empty schema yields a
+/// valid result with empty `validated_data`.
 pub fn validate_query_params(
-    params: HashMap<String, String>,
-    schema: HashMap<String, Value>,
+    mut params: HashMap<String, Vec<String>>,
+    schema: IndexMap<String, Value>,
+    treat_empty_as_missing: bool,
 ) -> Result<ValidationResult> {
+    if schema.is_empty() {
+        return Ok(ValidationResult::success(IndexMap::new()));
+    }
     let schemas = parse_schema_map(schema)?;
-    validate_parameters(
-        params.into_iter().map(|(k, v)| (k, vec![v])).collect(),
-        schemas,
-    )
+    if treat_empty_as_missing {
+        for values in params.values_mut() {
+            values.retain(|v| !v.is_empty());
+        }
+    }
+    validate_parameters(&params, &schemas)
 }
 
 pub fn validate_header_params(
     headers: HashMap<String, String>,
-    schema: HashMap<String, Value>,
+    schema: IndexMap<String, Value>,
 ) -> Result<ValidationResult> {
+    if schema.is_empty() {
+        return Ok(ValidationResult::success(IndexMap::new()));
+    }
     let schemas = parse_schema_map(schema)?;
     let normalized_headers: HashMap<String, Vec<String>> = headers
         .into_iter()
         .map(|(k, v)| (k.to_lowercase(), vec![v]))
         .collect();
-    validate_parameters(normalized_headers, schemas)
+
+    let mut result = ValidationResult::success(IndexMap::new());
+    for schema in &schemas {
+        // Values that could split a reflected header are rejected outright
+        let injected = normalized_headers
+            .get(&schema.name)
+            .is_some_and(|values| values.iter().any(|v| v.contains(['\r', '\n', '\0'])));
+        if injected {
+            result.add_error(ValidationError::InvalidHeaderValue(schema.name.clone()));
+            continue;
+        }
+
+        let checked = validate_parameters(&normalized_headers, std::slice::from_ref(schema))?;
+        result.validated_data.extend(checked.validated_data);
+        for error in checked.errors {
+            result.add_error(error);
+        }
+    }
+    Ok(result)
 }
 
 pub fn validate_body_params(
     body: Vec<u8>,
-    schema: HashMap<String, Value>,
+    schema: IndexMap<String, Value>,
 ) -> Result<ValidationResult> {
-    let body_str = std::str::from_utf8(&body).map_err(|_| ValidationError::InvalidFormat {
+    let json_value = parse_body_json(&body)?;
+    validate_json_against_schema(json_value, schema)
+}
+
+/// Validate a JSON body against a full draft-07 JSON Schema.
+///
+/// Each violation becomes a `SchemaViolation` carrying its instance path, so
+/// `allOf`/`oneOf`/`if` constraints are reported like any other error.
+pub fn validate_body_json_schema(body: Vec<u8>, json_schema: &Value) -> Result<ValidationResult> {
+    let validator =
+        jsonschema::draft7::new(json_schema).map_err(|e| ValidationError::InvalidFormat {
+            param: "schema".to_string(),
+            value: e.to_string(),
+        })?;
+    let json_value = parse_body_json(&body)?;
+
+    let errors: Vec<ValidationError> = validator
+        .iter_errors(&json_value)
+        .map(|error| ValidationError::SchemaViolation {
+            path: error.instance_path.to_string(),
+            message: error.to_string(),
+        })
+        .collect();
+
+    if errors.is_empty() {
+        let mut validated_data = IndexMap::new();
+        validated_data.insert("body".to_string(), json_value);
+        Ok(ValidationResult::success(validated_data))
+    } else {
+        Ok(ValidationResult::failure(errors))
+    }
+}
+
+fn parse_body_json(body: &[u8]) -> Result<Value> {
+    let body_str = std::str::from_utf8(body).map_err(|_| ValidationError::InvalidFormat {
         param: "body".to_string(),
         value: "Invalid UTF-8".to_string(),
     })?;
 
-    let json_value: Value =
-        serde_json::from_str(body_str).map_err(|_| ValidationError::InvalidFormat {
-            param: "body".to_string(),
-            value: "Invalid JSON".to_string(),
-        })?;
+    let invalid_json = || ValidationError::InvalidFormat {
+        param: "body".to_string(),
+        value: "Invalid JSON".to_string(),
+    };
+    let value: Value = serde_json::from_str(body_str).map_err(|_| invalid_json())?;
+    if has_non_finite_number(&value) {
+        return Err(invalid_json());
+    }
+    Ok(value)
+}
 
-    validate_json_against_schema(json_value, schema)
+/// Arbitrary-precision parsing keeps literals such as `1e400` that have no
+/// finite `f64` value; they are still rejected as they were before
+fn has_non_finite_number(value: &Value) -> bool {
+    match value {
+        Value::Number(n) => n.as_f64().is_none(),
+        Value::Array(items) => items.iter().any(has_non_finite_number),
+        Value::Object(map) => map.values().any(has_non_finite_number),
+        _ => false,
+    }
 }
 
 fn validate_parameters(
-    params: HashMap<String, Vec<String>>,
-    schemas: Vec<ParameterSchema>,
+    params: &HashMap<String, Vec<String>>,
+    schemas: &[ParameterSchema],
 ) -> Result<ValidationResult> {
-    let mut result = ValidationResult::success(HashMap::new());
+    let mut result = ValidationResult::success(IndexMap::new());
 
     for schema in schemas {
         match params.get(&schema.name) {
-            Some(values) if !values.is_empty() => {
-                let value = &values[0]; // Take first value for now
-                match validate_single_parameter(value, &schema) {
-                    Ok(validated_value) => {
-                        result
-                            .validated_data
-                            .insert(schema.name.clone(), validated_value);
-                    }
-                    Err(error) => {
-                        result.add_error(error);
-                    }
+            Some(values) if !values.is_empty() => match validate_parameter_values(values, schema) {
+                Ok(validated_value) => {
+                    result
+                        .validated_data
+                        .insert(schema.name.clone(), validated_value);
                 }
-            }
+                Err(error) => {
+                    result.add_error(error);
+                }
+            },
             _ => {
                 if schema.required {
                     result.add_error(ValidationError::MissingRequired(schema.name.clone()));
-                } else if let Some(default) = schema.default {
-                    result.validated_data.insert(schema.name.clone(), default);
+                } else if let Some(default) = &schema.default {
+                    result
+                        .validated_data
+                        .insert(schema.name.clone(), default.clone());
                 }
             }
         }
@@ -222,61 +706,237 @@ fn validate_parameters(
     Ok(result)
 }
 
+/// Validate every value a parameter was supplied with: `array` parameters check
+/// their item count and each element, `allow_multiple` ones each element, and
+/// anything else only the first value
+fn validate_parameter_values(values: &[String], schema: &ParameterSchema) -> Result<Value> {
+    if schema.is_array() {
+        if let Some(min) = schema.min_items.filter(|min| values.len() < *min) {
+            return Err(ValidationError::TooShort {
+                param: schema.name.clone(),
+                len: values.len(),
+                min,
+            });
+        }
+        if let Some(max) = schema.max_items.filter(|max| values.len() > *max) {
+            return Err(ValidationError::TooLong {
+                param: schema.name.clone(),
+                len: values.len(),
+                max,
+            });
+        }
+
+        // Errors name the element, e.g. `tags[2]`
+        let mut element = schema.clone();
+        element.param_type = schema.element_type().to_string();
+        return values
+            .iter()
+            .enumerate()
+            .map(|(index, value)| {
+                element.name = format!("{}[{}]", schema.name, index);
+                validate_single_parameter(value, &element)
+            })
+            .collect::<Result<Vec<_>>>()
+            .map(Value::Array);
+    }
+
+    if schema.allow_multiple {
+        values
+            .iter()
+            .map(|value| validate_single_parameter(value, schema))
+            .collect::<Result<Vec<_>>>()
+            .map(Value::Array)
+    } else {
+        validate_single_parameter(&values[0], schema)
+    }
+}
+
 fn validate_single_parameter(value: &str, schema: &ParameterSchema) -> Result<Value> {
+    let value = if schema.strip_whitespace {
+        value.trim()
+    } else {
+        value
+    };
+    let normalized;
+    let value = if schema.normalize_unicode {
+        normalized = value.nfc().collect::<String>();
+        normalized.as_str()
+    } else {
+        value
+    };
+
     // Type validation and conversion
-    let converted_value =
-        match schema.param_type.as_str() {
-            "string" | "str" => Value::String(value.to_string()),
-            "integer" | "int" => value.parse::<i64>().map(Value::Number).map_err(|_| {
-                ValidationError::InvalidType {
-                    param: schema.name.clone(),
-                    expected: "integer".to_string(),
-                    actual: value.to_string(),
-                }
+    let mut converted_value = match schema.param_type.as_str() {
+        "string" | "str" => Value::String(value.to_string()),
+        "integer" | "int" => value
+            .parse::<i64>()
+            .ok()
+            .or_else(|| {
+                schema
+                    .coerce_integral_floats
+                    .then(|| integral_float(value))
+                    .flatten()
+            })
+            .map(Value::from)
+            .ok_or_else(|| ValidationError::InvalidType {
+                param: schema.name.clone(),
+                expected: "integer".to_string(),
+                actual: value.to_string(),
             })?,
-            "number" | "float" => value
+        "number" | "float" => {
+            let f = value
                 .parse::<f64>()
-                .map(|f| Value::Number(serde_json::Number::from_f64(f).unwrap()))
                 .map_err(|_| ValidationError::InvalidType {
                     param: schema.name.clone(),
                     expected: "number".to_string(),
                     actual: value.to_string(),
-                })?,
-            "boolean" | "bool" => match value.to_lowercase().as_str() {
-                "true" | "1" | "yes" | "on" => Value::Bool(true),
-                "false" | "0" | "no" | "off" => Value::Bool(false),
-                _ => {
-                    return Err(ValidationError::InvalidType {
-                        param: schema.name.clone(),
-                        expected: "boolean".to_string(),
-                        actual: value.to_string(),
-                    })
-                }
-            },
-            "email" => {
-                if EMAIL_REGEX.is_match(value) {
-                    Value::String(value.to_string())
-                } else {
-                    return Err(ValidationError::InvalidFormat {
-                        param: schema.name.clone(),
-                        value: value.to_string(),
-                    });
-                }
+                })?;
+            // NaN and infinities (including overflow like "1e400") have no JSON form
+            serde_json::Number::from_f64(f)
+                .map(Value::Number)
+                .ok_or_else(|| ValidationError::InvalidFormat {
+                    param: schema.name.clone(),
+                    value: value.to_string(),
+                })?
+        }
+        "boolean" | "bool" => match value.to_lowercase().as_str() {
+            "true" | "1" | "yes" | "on" => Value::Bool(true),
+            "false" | "0" | "no" | "off" => Value::Bool(false),
+            _ => {
+                return Err(ValidationError::InvalidType {
+                    param: schema.name.clone(),
+                    expected: "boolean".to_string(),
+                    actual: value.to_string(),
+                })
             }
-            "uuid" => {
-                if UUID_REGEX.is_match(value) {
-                    Value::String(value.to_string())
-                } else {
-                    return Err(ValidationError::InvalidFormat {
-                        param: schema.name.clone(),
-                        value: value.to_string(),
-                    });
-                }
+        },
+        "email" => {
+            if EMAIL_REGEX.is_match(value) {
+                Value::String(value.to_string())
+            } else {
+                return Err(ValidationError::InvalidFormat {
+                    param: schema.name.clone(),
+                    value: value.to_string(),
+                });
             }
-            _ => Value::String(value.to_string()),
-        };
+        }
+        "uuid" => {
+            let lowercase = value.to_ascii_lowercase();
+            if UUID_REGEX.is_match(&lowercase) {
+                Value::String(lowercase)
+            } else {
+                return Err(ValidationError::InvalidFormat {
+                    param: schema.name.clone(),
+                    value: value.to_string(),
+                });
+            }
+        }
+        "datetime" | "date" | "time" | "ipv4" | "ipv6" | "url" => {
+            if matches_format(&schema.param_type, value) {
+                Value::String(value.to_string())
+            } else {
+                return Err(ValidationError::InvalidFormat {
+                    param: schema.name.clone(),
+                    value: value.to_string(),
+                });
+            }
+        }
+        "json-pointer" => {
+            if JSON_POINTER_REGEX.is_match(value) {
+                Value::String(value.to_string())
+            } else {
+                return Err(ValidationError::InvalidFormat {
+                    param: schema.name.clone(),
+                    value: value.to_string(),
+                });
+            }
+        }
+        "path-safe" => {
+            if is_safe_relative_path(value) {
+                Value::String(value.to_string())
+            } else {
+                return Err(ValidationError::InvalidFormat {
+                    param: schema.name.clone(),
+                    value: value.to_string(),
+                });
+            }
+        }
+        "country" | "currency" => {
+            let code = value.to_ascii_uppercase();
+            let known = if schema.param_type == "country" {
+                COUNTRY_CODES
+            } else {
+                CURRENCY_CODES
+            };
+            if known.binary_search(&code.as_str()).is_ok() {
+                Value::String(code)
+            } else {
+                return Err(ValidationError::InvalidFormat {
+                    param: schema.name.clone(),
+                    value: value.to_string(),
+                });
+            }
+        }
+        "bitflags" => {
+            let flags = parse_flags(value).ok_or_else(|| ValidationError::InvalidType {
+                param: schema.name.clone(),
+                expected: "integer".to_string(),
+                actual: value.to_string(),
+            })?;
+            if schema.flag_mask.is_some_and(|mask| flags & !mask != 0) {
+                return Err(ValidationError::InvalidFormat {
+                    param: schema.name.clone(),
+                    value: format!("Unknown flag bits set in {}", value),
+                });
+            }
+            Value::from(flags)
+        }
+        "int-enum" => {
+            let n = value
+                .trim()
+                .parse::<i64>()
+                .map_err(|_| ValidationError::InvalidType {
+                    param: schema.name.clone(),
+                    expected: "integer".to_string(),
+                    actual: value.to_string(),
+                })?;
+            let members = schema.enum_json.as_deref().unwrap_or(&[]);
+            if !members.iter().any(|m| m.as_i64() == Some(n)) {
+                return Err(ValidationError::InvalidFormat {
+                    param: schema.name.clone(),
+                    value: value.to_string(),
+                });
+            }
+            Value::from(n)
+        }
+        "mac" => match normalize_mac(value) {
+            Some(mac) => Value::String(mac),
+            None => {
+                return Err(ValidationError::InvalidFormat {
+                    param: schema.name.clone(),
+                    value: value.to_string(),
+                })
+            }
+        },
+        "host:port" => match split_host_port(value) {
+            Some((host, port)) => {
+                let mut pair = Map::new();
+                pair.insert("host".to_string(), Value::String(host.to_string()));
+                pair.insert("port".to_string(), Value::from(port));
+                Value::Object(pair)
+            }
+            None => {
+                return Err(ValidationError::InvalidFormat {
+                    param: schema.name.clone(),
+                    value: value.to_string(),
+                })
+            }
+        },
+        _ => Value::String(value.to_string()),
+    };
 
     // Length validation for strings
+    let mut enum_member = None;
     if let Value::String(s) = &converted_value {
         if let Some(min_len) = schema.min_length {
             if s.len() < min_len {
@@ -300,10 +960,16 @@ fn validate_single_parameter(value: &str, schema: &ParameterSchema) -> Result<Va
 
         // Pattern validation
         if let Some(pattern) = &schema.pattern {
-            let regex = Regex::new(pattern).map_err(|_| ValidationError::InvalidFormat {
-                param: schema.name.clone(),
-                value: format!("Invalid regex pattern: {}", pattern),
-            })?;
+            let regex =
+                match &schema.compiled_pattern {
+                    Some(regex) => Cow::Borrowed(regex.as_ref()),
+                    None => Cow::Owned(Regex::new(pattern).map_err(|_| {
+                        ValidationError::InvalidFormat {
+                            param: schema.name.clone(),
+                            value: format!("Invalid regex pattern: {}", pattern),
+                        }
+                    })?),
+                };
 
             if !regex.is_match(s) {
                 return Err(ValidationError::PatternMismatch {
@@ -315,15 +981,23 @@ fn validate_single_parameter(value: &str, schema: &ParameterSchema) -> Result<Va
 
         // Enum validation
         if let Some(enum_values) = &schema.enum_values {
-            if !enum_values.contains(s) {
-                return Err(ValidationError::InvalidFormat {
-                    param: schema.name.clone(),
-                    value: format!("Value must be one of: {}", enum_values.join(", ")),
-                });
+            match find_enum_member(s, enum_values, schema) {
+                Some(member) => enum_member = Some(member.clone()),
+                None => {
+                    return Err(ValidationError::InvalidFormat {
+                        param: schema.name.clone(),
+                        value: format!("Value must be one of: {}", enum_values.join(", ")),
+                    });
+                }
             }
         }
     }
 
+    // Case-insensitive matches resolve to the member as declared in the schema
+    if let Some(member) = enum_member {
+        converted_value = Value::String(member);
+    }
+
     // Range validation for numbers
     if let Value::Number(n) = &converted_value {
         let num_value = n.as_f64().unwrap_or(0.0);
@@ -345,23 +1019,359 @@ fn validate_single_parameter(value: &str, schema: &ParameterSchema) -> Result<Va
                 });
             }
         }
+
+        let below = schema.exclusive_minimum.is_some_and(|min| num_value <= min);
+        let above = schema.exclusive_maximum.is_some_and(|max| num_value >= max);
+        if below || above {
+            return Err(ValidationError::OutOfRange {
+                param: schema.name.clone(),
+                value: num_value.to_string(),
+            });
+        }
+
+        if let Some(step) = schema.multiple_of.filter(|step| *step > 0.0) {
+            if !is_multiple_of(n, step) {
+                return Err(ValidationError::InvalidFormat {
+                    param: schema.name.clone(),
+                    value: format!("{} is not a multiple of {}", n, step),
+                });
+            }
+        }
     }
 
     Ok(converted_value)
 }
 
-fn validate_json_against_schema(
-    value: Value,
-    _schema: HashMap<String, Value>,
-) -> Result<ValidationResult> {
-    // Simplified JSON schema validation
-    // In a full implementation, this would use a proper JSON schema validator
-    let mut validated_data = HashMap::new();
-    validated_data.insert("body".to_string(), value);
-    Ok(ValidationResult::success(validated_data))
-}
-
-fn parse_schema_map(schema: HashMap<String, Value>) -> Result<Vec<ParameterSchema>> {
+/// Integers are checked exactly against whole steps; anything else allows for
+/// float rounding, so `0.3` counts as a multiple of `0.1`
+fn is_multiple_of(n: &serde_json::Number, step: f64) -> bool {
+    if let Some(i) = n.as_i64() {
+        if step.fract() == 0.0 && step < i64::MAX as f64 {
+            return i % (step as i64) == 0;
+        }
+    }
+
+    let quotient = n.as_f64().unwrap_or(0.0) / step;
+    (quotient - quotient.round()).abs() <= 1e-9 * quotient.abs().max(1.0)
+}
+
+fn find_enum_member<'a>(
+    value: &str,
+    members: &'a [String],
+    schema: &ParameterSchema,
+) -> Option<&'a String> {
+    members.iter().find(|member| {
+        let member: Cow<str> = if schema.normalize_unicode {
+            Cow::Owned(member.nfc().collect())
+        } else {
+            Cow::Borrowed(member.as_str())
+        };
+
+        if schema.case_sensitive {
+            member == value
+        } else {
+            UniCase::unicode(member.as_ref()) == UniCase::unicode(value)
+        }
+    })
+}
+
+/// Decimal or `0x`-prefixed hexadecimal flag set
+fn parse_flags(value: &str) -> Option<u64> {
+    let value = value.trim();
+    match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => value.parse::<u64>().ok(),
+    }
+}
+
+/// A float with no fractional part that fits in an i64
+fn integral_float(value: &str) -> Option<i64> {
+    let f = value.parse::<f64>().ok()?;
+    let in_range = f >= i64::MIN as f64 && f < i64::MAX as f64;
+    (f.is_finite() && f.fract() == 0.0 && in_range).then_some(f as i64)
+}
+
+/// `AA:BB:CC:DD:EE:FF` or `AA-BB-CC-DD-EE-FF` as lowercase colon-separated hex
+fn normalize_mac(value: &str) -> Option<String> {
+    let separator = if value.contains('-') { '-' } else { ':' };
+    let octets: Vec<&str> = value.split(separator).collect();
+    let valid = octets.len() == 6
+        && octets
+            .iter()
+            .all(|o| o.len() == 2 && o.bytes().all(|b| b.is_ascii_hexdigit()));
+
+    valid.then(|| octets.join(":").to_ascii_lowercase())
+}
+
+/// RFC 1123 host name (IPv4 literals also qualify)
+fn is_valid_hostname(host: &str) -> bool {
+    let host = host.strip_suffix('.').unwrap_or(host);
+    !host.is_empty()
+        && host.len() <= 253
+        && host.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'-')
+        })
+}
+
+/// Check a string `format`: RFC 3339 `datetime`, `YYYY-MM-DD` `date`,
+/// `HH:MM[:SS]` `time`, IP addresses, and absolute http(s) `url`s
+fn matches_format(format: &str, value: &str) -> bool {
+    use chrono::{DateTime, NaiveDate, NaiveTime};
+
+    match format {
+        "datetime" => DateTime::parse_from_rfc3339(value).is_ok(),
+        "date" => value.len() == 10 && NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok(),
+        "time" => match value.len() {
+            5 => NaiveTime::parse_from_str(value, "%H:%M").is_ok(),
+            8 => NaiveTime::parse_from_str(value, "%H:%M:%S").is_ok(),
+            _ => false,
+        },
+        "ipv4" => value.parse::<std::net::Ipv4Addr>().is_ok(),
+        "ipv6" => value.parse::<std::net::Ipv6Addr>().is_ok(),
+        "url" => is_http_url(value),
+        _ => false,
+    }
+}
+
+/// Absolute `http`/`https` URL with a valid host and optional port
+fn is_http_url(value: &str) -> bool {
+    if value.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return false;
+    }
+    let Some((scheme, rest)) = value.split_once("://") else {
+        return false;
+    };
+    if !scheme.eq_ignore_ascii_case("http") && !scheme.eq_ignore_ascii_case("https") {
+        return false;
+    }
+
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or("");
+    let host_port = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host_port)| host_port);
+    if let Some(bracketed) = host_port.strip_prefix('[') {
+        let Some((addr, after)) = bracketed.split_once(']') else {
+            return false;
+        };
+        return addr.parse::<std::net::Ipv6Addr>().is_ok()
+            && (after.is_empty() || after.strip_prefix(':').and_then(parse_port).is_some());
+    }
+    match host_port.rsplit_once(':') {
+        Some((host, port)) => is_valid_hostname(host) && parse_port(port).is_some(),
+        None => is_valid_hostname(host_port),
+    }
+}
+
+/// TCP/UDP port in `1..=65535`
+fn parse_port(port: &str) -> Option<u16> {
+    if port.is_empty() || !port.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    port.parse::<u16>().ok().filter(|&p| p != 0)
+}
+
+/// `host:port`, with IPv6 hosts written as `[addr]:port`
+fn split_host_port(value: &str) -> Option<(&str, u16)> {
+    let (host, port) = value.rsplit_once(':')?;
+    let port = parse_port(port)?;
+
+    if let Some(addr) = host.strip_prefix('[').and_then(|h| h.strip_suffix(']')) {
+        return addr
+            .parse::<std::net::Ipv6Addr>()
+            .ok()
+            .map(|_| (addr, port));
+    }
+    is_valid_hostname(host).then_some((host, port))
+}
+
+/// A relative path that cannot escape its base directory
+fn is_safe_relative_path(value: &str) -> bool {
+    if value.is_empty() || value.contains('\0') {
+        return false;
+    }
+
+    // Absolute POSIX/UNC paths and Windows drive prefixes like "C:"
+    if value.starts_with('/') || value.starts_with('\\') {
+        return false;
+    }
+    let bytes = value.as_bytes();
+    if bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' {
+        return false;
+    }
+
+    !value.split(['/', '\\']).any(|segment| segment == "..")
+}
+
+/// Check a JSON body against a field schema map: each entry describes one
+/// top-level field like a parameter schema, and `object` fields nest their own
+/// `properties`. Errors name fields by dotted path, e.g. `address.zip`.
+fn validate_json_against_schema(
+    value: Value,
+    schema: IndexMap<String, Value>,
+) -> Result<ValidationResult> {
+    let mut errors = Vec::new();
+    if !schema.is_empty() {
+        match &value {
+            Value::Object(body) => {
+                for (name, spec) in &schema {
+                    validate_json_field(body, name, spec, "", false, &mut errors);
+                }
+            }
+            other => errors.push(ValidationError::InvalidType {
+                param: "body".to_string(),
+                expected: "object".to_string(),
+                actual: json_type_name(other).to_string(),
+            }),
+        }
+    }
+
+    if errors.is_empty() {
+        let mut validated_data = IndexMap::new();
+        validated_data.insert("body".to_string(), value);
+        Ok(ValidationResult::success(validated_data))
+    } else {
+        Ok(ValidationResult::failure(errors))
+    }
+}
+
+/// Validate `object[name]`; a field is required by `"required": true` in its
+/// own spec or by `listed_required` from the parent's `required` array
+fn validate_json_field(
+    object: &Map<String, Value>,
+    name: &str,
+    spec: &Value,
+    prefix: &str,
+    listed_required: bool,
+    errors: &mut Vec<ValidationError>,
+) {
+    let path = format!("{}{}", prefix, name);
+    match object.get(name) {
+        None | Some(Value::Null) => {
+            let required = listed_required || spec.get("required") == Some(&Value::Bool(true));
+            if required {
+                errors.push(ValidationError::MissingRequired(path));
+            }
+        }
+        Some(value) => validate_json_value(value, spec, &path, errors),
+    }
+}
+
+fn validate_json_value(value: &Value, spec: &Value, path: &str, errors: &mut Vec<ValidationError>) {
+    let Some(spec_obj) = spec.as_object() else {
+        return;
+    };
+    let json_type = spec_obj
+        .get("type")
+        .and_then(|v| v.as_str())
+        .or_else(|| spec_obj.contains_key("properties").then_some("object"));
+    let Some(json_type) = json_type else {
+        return;
+    };
+    let invalid_type = |expected: &str| ValidationError::InvalidType {
+        param: path.to_string(),
+        expected: expected.to_string(),
+        actual: json_type_name(value).to_string(),
+    };
+
+    match json_type {
+        "object" => {
+            let Value::Object(object) = value else {
+                errors.push(invalid_type("object"));
+                return;
+            };
+            let required: Vec<&str> = match spec_obj.get("required") {
+                Some(Value::Array(names)) => names.iter().filter_map(|n| n.as_str()).collect(),
+                _ => Vec::new(),
+            };
+            if let Some(Value::Object(properties)) = spec_obj.get("properties") {
+                let prefix = format!("{}.", path);
+                for (name, property) in properties {
+                    let listed = required.contains(&name.as_str());
+                    validate_json_field(object, name, property, &prefix, listed, errors);
+                }
+            }
+        }
+        "array" => {
+            let Value::Array(items) = value else {
+                errors.push(invalid_type("array"));
+                return;
+            };
+            let count = |key: &str| {
+                spec_obj
+                    .get(key)
+                    .and_then(|v| v.as_u64())
+                    .map(|n| n as usize)
+            };
+            if let Some(min) = count("minItems").filter(|min| items.len() < *min) {
+                errors.push(ValidationError::TooShort {
+                    param: path.to_string(),
+                    len: items.len(),
+                    min,
+                });
+            } else if let Some(max) = count("maxItems").filter(|max| items.len() > *max) {
+                errors.push(ValidationError::TooLong {
+                    param: path.to_string(),
+                    len: items.len(),
+                    max,
+                });
+            }
+            if let Some(item_spec) = spec_obj.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    validate_json_value(item, item_spec, &format!("{}[{}]", path, index), errors);
+                }
+            }
+        }
+        leaf_type => {
+            // Leaves must already have the JSON type; constraints reuse the parameter checks
+            let expected = match leaf_type {
+                "integer" | "int" | "int-enum" | "bitflags" => "integer",
+                "number" | "float" => "number",
+                "boolean" | "bool" => "boolean",
+                _ => "string",
+            };
+            let text = match (expected, value) {
+                ("integer", Value::Number(n)) if !n.is_f64() => n.to_string(),
+                ("number", Value::Number(n)) => n.to_string(),
+                ("boolean", Value::Bool(b)) => b.to_string(),
+                ("string", Value::String(s)) => s.clone(),
+                _ => {
+                    errors.push(invalid_type(expected));
+                    return;
+                }
+            };
+            // The body is returned as sent, so it is also checked as sent
+            let mut leaf =
+                ParameterSchema::new(path.to_string(), leaf_type.to_string()).preserve_whitespace();
+            apply_constraints(&mut leaf, spec_obj);
+            if let Err(error) = validate_single_parameter(&text, &leaf) {
+                errors.push(error);
+            }
+        }
+    }
+}
+
+fn json_type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn parse_schema_map(schema: IndexMap<String, Value>) -> Result<Vec<ParameterSchema>> {
     let mut schemas = Vec::new();
 
     for (name, spec) in schema {
@@ -382,34 +1392,24 @@ fn parse_schema_map(schema: HashMap<String, Value>) -> Result<Vec<ParameterSchem
                 param_schema.default = Some(default.clone());
             }
 
-            if let Some(Value::Number(min_len)) = spec_obj.get("minLength") {
-                param_schema.min_length = min_len.as_u64().map(|n| n as usize);
-            }
-
-            if let Some(Value::Number(max_len)) = spec_obj.get("maxLength") {
-                param_schema.max_length = max_len.as_u64().map(|n| n as usize);
-            }
+            apply_constraints(&mut param_schema, &spec_obj);
 
-            if let Some(Value::Number(min)) = spec_obj.get("minimum") {
-                param_schema.minimum = min.as_f64();
-            }
-
-            if let Some(Value::Number(max)) = spec_obj.get("maximum") {
-                param_schema.maximum = max.as_f64();
-            }
+            if param_schema.is_array() {
+                if let Some(Value::Number(min_items)) = spec_obj.get("minItems") {
+                    param_schema.min_items = min_items.as_u64().map(|n| n as usize);
+                }
 
-            if let Some(Value::String(pattern)) = spec_obj.get("pattern") {
-                param_schema.pattern = Some(pattern.clone());
-            }
+                if let Some(Value::Number(max_items)) = spec_obj.get("maxItems") {
+                    param_schema.max_items = max_items.as_u64().map(|n| n as usize);
+                }
 
-            if let Some(Value::Array(enum_vals)) = spec_obj.get("enum") {
-                let enum_strings: Vec<String> = enum_vals
-                    .iter()
-                    .filter_map(|v| v.as_str())
-                    .map(|s| s.to_string())
-                    .collect();
-                if !enum_strings.is_empty() {
-                    param_schema.enum_values = Some(enum_strings);
+                // Item constraints apply to each element
+                if let Some(Value::Object(items)) = spec_obj.get("items") {
+                    param_schema.items_type = items
+                        .get("type")
+                        .and_then(|v| v.as_str())
+                        .map(str::to_string);
+                    apply_constraints(&mut param_schema, items);
                 }
             }
 
@@ -420,6 +1420,77 @@ fn parse_schema_map(schema: HashMap<String, Value>) -> Result<Vec<ParameterSchem
     Ok(schemas)
 }
 
+/// Read the value constraints of a JSON schema object into `schema`
+fn apply_constraints(schema: &mut ParameterSchema, spec_obj: &Map<String, Value>) {
+    if let Some(Value::Number(min_len)) = spec_obj.get("minLength") {
+        schema.min_length = min_len.as_u64().map(|n| n as usize);
+    }
+
+    if let Some(Value::Number(max_len)) = spec_obj.get("maxLength") {
+        schema.max_length = max_len.as_u64().map(|n| n as usize);
+    }
+
+    if let Some(Value::Number(min)) = spec_obj.get("minimum") {
+        schema.minimum = min.as_f64();
+    }
+
+    if let Some(Value::Number(max)) = spec_obj.get("maximum") {
+        schema.maximum = max.as_f64();
+    }
+
+    if let Some(Value::Number(min)) = spec_obj.get("exclusiveMinimum") {
+        schema.exclusive_minimum = min.as_f64();
+    }
+
+    if let Some(Value::Number(max)) = spec_obj.get("exclusiveMaximum") {
+        schema.exclusive_maximum = max.as_f64();
+    }
+
+    if let Some(Value::Number(step)) = spec_obj.get("multipleOf") {
+        schema.multiple_of = step.as_f64();
+    }
+
+    if let Some(Value::String(pattern)) = spec_obj.get("pattern") {
+        schema.pattern = Some(pattern.clone());
+    }
+
+    if let Some(Value::Bool(allow_multiple)) = spec_obj.get("allowMultiple") {
+        schema.allow_multiple = *allow_multiple;
+    }
+
+    if let Some(Value::Bool(case_sensitive)) = spec_obj.get("caseSensitive") {
+        schema.case_sensitive = *case_sensitive;
+    }
+
+    if let Some(Value::Bool(strip)) = spec_obj.get("stripWhitespace") {
+        schema.strip_whitespace = *strip;
+    }
+
+    if let Some(Value::Bool(normalize)) = spec_obj.get("normalizeUnicode") {
+        schema.normalize_unicode = *normalize;
+    }
+
+    if let Some(Value::Bool(coerce)) = spec_obj.get("coerceIntegralFloats") {
+        schema.coerce_integral_floats = *coerce;
+    }
+
+    if let Some(Value::Number(mask)) = spec_obj.get("mask") {
+        schema.flag_mask = mask.as_u64();
+    }
+
+    if let Some(Value::Array(enum_vals)) = spec_obj.get("enum") {
+        schema.enum_json = Some(enum_vals.clone());
+        let enum_strings: Vec<String> = enum_vals
+            .iter()
+            .filter_map(|v| v.as_str())
+            .map(|s| s.to_string())
+            .collect();
+        if !enum_strings.is_empty() {
+            schema.enum_values = Some(enum_strings);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -447,6 +1518,74 @@ mod tests {
         assert!(validate_single_parameter("abc", &schema).is_err()); // Invalid type
     }
 
+    #[test]
+    fn test_exclusive_bounds_and_multiple_of() {
+        let positive = ParameterSchema::new("n".to_string(), "number".to_string()).gt(0.0);
+        assert!(validate_single_parameter("0", &positive).is_err());
+        assert!(validate_single_parameter("-0.0", &positive).is_err());
+        assert!(validate_single_parameter("0.001", &positive).is_ok());
+
+        let schema = ParameterSchema::new("n".to_string(), "integer".to_string())
+            .with_bounds(Some(0.0), Some(10.0), true)
+            .multiple_of(3.0);
+        assert!(validate_single_parameter("3", &schema).is_ok());
+        assert!(validate_single_parameter("9", &schema).is_ok());
+        assert!(validate_single_parameter("4", &schema).is_err());
+        assert!(validate_single_parameter("0", &schema).is_err());
+        assert!(validate_single_parameter("12", &schema).is_err());
+
+        let inclusive = ParameterSchema::new("n".to_string(), "integer".to_string())
+            .ge(1.0)
+            .lt(5.0);
+        assert!(validate_single_parameter("1", &inclusive).is_ok());
+        assert!(validate_single_parameter("5", &inclusive).is_err());
+        assert!(validate_single_parameter("4", &inclusive.clone().le(3.0)).is_err());
+
+        // Float steps tolerate rounding error
+        let price =
+            ParameterSchema::new("price".to_string(), "number".to_string()).multiple_of(0.1);
+        assert!(validate_single_parameter("0.3", &price).is_ok());
+        assert!(validate_single_parameter("2.7", &price).is_ok());
+        assert!(validate_single_parameter("0.35", &price).is_err());
+
+        let exported = schema.to_json_schema();
+        assert_eq!(exported["exclusiveMinimum"], json!(0));
+        assert_eq!(exported["exclusiveMaximum"], json!(10));
+        assert_eq!(exported["multipleOf"], json!(3));
+    }
+
+    #[test]
+    fn test_non_finite_numbers_rejected() {
+        let schema = ParameterSchema::new("ratio".to_string(), "float".to_string());
+        for value in ["nan", "NaN", "inf", "-inf", "infinity", "1e400"] {
+            assert!(
+                matches!(
+                    validate_single_parameter(value, &schema),
+                    Err(ValidationError::InvalidFormat { .. })
+                ),
+                "{} should be rejected",
+                value
+            );
+        }
+        assert_eq!(
+            validate_single_parameter("1e300", &schema).unwrap(),
+            json!(1e300)
+        );
+
+        let int = ParameterSchema::new("n".to_string(), "integer".to_string());
+        assert!(matches!(
+            validate_single_parameter("9223372036854775808", &int),
+            Err(ValidationError::InvalidType { .. })
+        ));
+
+        // JSON bodies cannot smuggle them in either
+        let mut body_schema = IndexMap::new();
+        body_schema.insert("ratio".to_string(), json!({"type": "number"}));
+        for body in [&br#"{"ratio": 1e400}"#[..], br#"{"ratio": NaN}"#] {
+            assert!(validate_body_params(body.to_vec(), body_schema.clone()).is_err());
+        }
+    }
+
     #[test]
     fn test_validate_email_parameter() {
         let schema = ParameterSchema::new("email".to_string(), "email".to_string());
@@ -497,7 +1636,7 @@ mod tests {
         ];
 
         let params = HashMap::new(); // Empty params
-        let result = validate_parameters(params, schema).unwrap();
+        let result = validate_parameters(&params, &schema).unwrap();
 
         assert!(!result.valid);
         assert_eq!(result.errors.len(), 1);
@@ -516,7 +1655,7 @@ mod tests {
         ];
 
         let params = HashMap::new(); // Empty params
-        let result = validate_parameters(params, schema).unwrap();
+        let result = validate_parameters(&params, &schema).unwrap();
 
         assert!(result.valid);
         assert_eq!(result.validated_data.get("page"), Some(&json!(1)));
@@ -532,5 +1671,891 @@ mod tests {
 
         assert!(validate_single_parameter(valid_uuid, &schema).is_ok());
         assert!(validate_single_parameter(invalid_uuid, &schema).is_err());
+        assert_eq!(
+            validate_single_parameter("550E8400-E29B-41D4-A716-446655440000", &schema).unwrap(),
+            json!(valid_uuid)
+        );
+    }
+
+    #[test]
+    fn test_datetime_validation() {
+        let datetime = ParameterSchema::new("at".to_string(), "datetime".to_string());
+        assert!(validate_single_parameter("2024-02-29T12:30:00Z", &datetime).is_ok());
+        assert!(validate_single_parameter("2024-02-29T12:30:00.5+02:00", &datetime).is_ok());
+        assert!(validate_single_parameter("2024-02-29 12:30", &datetime).is_err());
+
+        let date = ParameterSchema::new("on".to_string(), "date".to_string());
+        assert!(validate_single_parameter("2024-02-29", &date).is_ok());
+        assert!(validate_single_parameter("2023-02-29", &date).is_err());
+        assert!(validate_single_parameter("2024-2-9", &date).is_err());
+
+        let time = ParameterSchema::new("at".to_string(), "time".to_string());
+        assert!(validate_single_parameter("09:05", &time).is_ok());
+        assert!(validate_single_parameter("23:59:59", &time).is_ok());
+        assert!(validate_single_parameter("24:00", &time).is_err());
+        assert!(validate_single_parameter("9:05", &time).is_err());
+    }
+
+    #[test]
+    fn test_ip_validation() {
+        let ipv4 = ParameterSchema::new("ip".to_string(), "ipv4".to_string());
+        assert!(validate_single_parameter("192.168.0.1", &ipv4).is_ok());
+        assert!(validate_single_parameter("256.1.1.1", &ipv4).is_err());
+        assert!(validate_single_parameter("::1", &ipv4).is_err());
+
+        let ipv6 = ParameterSchema::new("ip".to_string(), "ipv6".to_string());
+        assert!(validate_single_parameter("2001:db8::1", &ipv6).is_ok());
+        assert!(matches!(
+            validate_single_parameter("2001:db8::g", &ipv6),
+            Err(ValidationError::InvalidFormat { .. })
+        ));
+    }
+
+    #[test]
+    fn test_url_validation() {
+        let schema = ParameterSchema::new("next".to_string(), "url".to_string());
+        for valid in [
+            "https://example.com",
+            "http://user@example.com:8080/a?b=c#d",
+            "HTTPS://[2001:db8::1]:443/",
+        ] {
+            assert!(
+                validate_single_parameter(valid, &schema).is_ok(),
+                "{}",
+                valid
+            );
+        }
+        for invalid in [
+            "ftp://example.com",
+            "javascript:alert(1)",
+            "https://",
+            "https://exa mple.com",
+            "https://example.com:0",
+            "/relative/path",
+        ] {
+            assert!(
+                validate_single_parameter(invalid, &schema).is_err(),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_empty_query_value_handling() {
+        let mut schema = IndexMap::new();
+        schema.insert("q".to_string(), json!({"type": "string", "required": true}));
+        schema.insert("page".to_string(), json!({"type": "integer", "default": 1}));
+
+        let mut params = HashMap::new();
+        params.insert("q".to_string(), vec![String::new()]);
+        params.insert("page".to_string(), vec![String::new()]);
+
+        // Empty values are validated as present by default
+        let result = validate_query_params(params.clone(), schema.clone(), false).unwrap();
+        assert!(!result.valid);
+        assert_eq!(result.errors.len(), 1);
+        assert!(matches!(
+            result.errors[0],
+            ValidationError::InvalidType { ref param, .. } if param == "page"
+        ));
+        assert_eq!(result.validated_data.get("q"), Some(&json!("")));
+
+        // Empty values go through the missing-parameter path
+        let result = validate_query_params(params, schema, true).unwrap();
+        assert!(!result.valid);
+        assert_eq!(result.errors.len(), 1);
+        assert!(matches!(
+            result.errors[0],
+            ValidationError::MissingRequired(ref name) if name == "q"
+        ));
+        assert_eq!(result.validated_data.get("page"), Some(&json!(1)));
+    }
+
+    #[test]
+    fn test_multiple_values() {
+        let schema = vec![
+            ParameterSchema::new("ids".to_string(), "integer".to_string()).allow_multiple(),
+            ParameterSchema::new("sort".to_string(), "string".to_string()),
+        ];
+
+        let mut params = HashMap::new();
+        params.insert(
+            "ids".to_string(),
+            vec!["1".to_string(), "2".to_string(), "3".to_string()],
+        );
+        params.insert(
+            "sort".to_string(),
+            vec!["asc".to_string(), "desc".to_string(), "none".to_string()],
+        );
+
+        let result = validate_parameters(&params, &schema).unwrap();
+
+        assert!(result.valid);
+        assert_eq!(result.validated_data.get("ids"), Some(&json!([1, 2, 3])));
+        assert_eq!(result.validated_data.get("sort"), Some(&json!("asc")));
+    }
+
+    #[test]
+    fn test_multiple_values_invalid_element() {
+        let mut schema = IndexMap::new();
+        schema.insert(
+            "ids".to_string(),
+            json!({"type": "integer", "allowMultiple": true}),
+        );
+        let schemas = parse_schema_map(schema).unwrap();
+
+        let mut params = HashMap::new();
+        params.insert("ids".to_string(), vec!["1".to_string(), "x".to_string()]);
+
+        let result = validate_parameters(&params, &schemas).unwrap();
+        assert!(!result.valid);
+        assert!(matches!(
+            result.errors[0],
+            ValidationError::InvalidType { .. }
+        ));
+    }
+
+    #[test]
+    fn test_query_params_keep_repeated_values() {
+        let mut schema = IndexMap::new();
+        schema.insert(
+            "tags".to_string(),
+            json!({"type": "string", "allowMultiple": true}),
+        );
+        schema.insert("q".to_string(), json!({"type": "string"}));
+        let params = crate::core::request::parse_query_string("tags=a&tags=b&q=x&q=y");
+
+        let result = validate_query_params(params, schema, false).unwrap();
+        assert!(result.valid);
+        assert_eq!(result.validated_data.get("tags"), Some(&json!(["a", "b"])));
+        // A single-valued parameter still takes the first value
+        assert_eq!(result.validated_data.get("q"), Some(&json!("x")));
+    }
+
+    #[test]
+    fn test_array_query_parameter_end_to_end() {
+        let mut schema = IndexMap::new();
+        schema.insert(
+            "ids".to_string(),
+            json!({"type": "array", "items": {"type": "integer"}, "minItems": 1}),
+        );
+        let compiled = CompiledSchema::new(schema.clone()).unwrap();
+        let query = crate::core::request::parse_query_string;
+
+        for result in [
+            validate_query_params(query("ids=1&ids=2"), schema.clone(), false).unwrap(),
+            compiled.validate_multi(query("ids=1&ids=2")).unwrap(),
+        ] {
+            assert!(result.valid);
+            assert_eq!(result.validated_data.get("ids"), Some(&json!([1, 2])));
+        }
+
+        for result in [
+            validate_query_params(query("ids=1&ids=2&ids=x"), schema.clone(), false).unwrap(),
+            compiled.validate_multi(query("ids=1&ids=2&ids=x")).unwrap(),
+        ] {
+            assert!(!result.valid);
+            assert!(matches!(
+                &result.errors[0],
+                ValidationError::InvalidType { param, .. } if param == "ids[2]"
+            ));
+        }
+    }
+
+    #[test]
+    fn test_array_parameter() {
+        let mut schema = IndexMap::new();
+        schema.insert(
+            "tags".to_string(),
+            json!({"type": "array", "items": {"type": "integer", "minimum": 0}, "minItems": 1, "maxItems": 3}),
+        );
+        let schemas = parse_schema_map(schema).unwrap();
+        let values = |items: &[&str]| {
+            let mut params = HashMap::new();
+            params.insert(
+                "tags".to_string(),
+                items.iter().map(|s| s.to_string()).collect::<Vec<_>>(),
+            );
+            params
+        };
+
+        let result = validate_parameters(&values(&["1", "2"]), &schemas).unwrap();
+        assert!(result.valid);
+        assert_eq!(result.validated_data.get("tags"), Some(&json!([1, 2])));
+
+        let result = validate_parameters(&values(&["1", "x", "3"]), &schemas).unwrap();
+        assert!(matches!(
+            &result.errors[0],
+            ValidationError::InvalidType { param, .. } if param == "tags[1]"
+        ));
+        let result = validate_parameters(&values(&["1", "-4"]), &schemas).unwrap();
+        assert!(matches!(
+            &result.errors[0],
+            ValidationError::OutOfRange { param, .. } if param == "tags[1]"
+        ));
+        let result = validate_parameters(&values(&["1", "2", "3", "4"]), &schemas).unwrap();
+        assert!(matches!(
+            result.errors[0],
+            ValidationError::TooLong { len: 4, max: 3, .. }
+        ));
+        // No values at all means the optional parameter is absent
+        let result = validate_parameters(&values(&[]), &schemas).unwrap();
+        assert!(result.valid);
+
+        assert_eq!(
+            schemas[0].to_json_schema(),
+            json!({
+                "type": "array",
+                "items": {"type": "integer", "minimum": 0},
+                "minItems": 1,
+                "maxItems": 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_json_pointer_validation() {
+        let schema = ParameterSchema::new("pointer".to_string(), "json-pointer".to_string());
+
+        assert!(validate_single_parameter("", &schema).is_ok());
+        assert!(validate_single_parameter("/users/0/name", &schema).is_ok());
+        assert!(validate_single_parameter("/a~1b/m~0n", &schema).is_ok());
+        assert!(matches!(
+            validate_single_parameter("users/0", &schema),
+            Err(ValidationError::InvalidFormat { .. })
+        ));
+        assert!(matches!(
+            validate_single_parameter("/a~2b", &schema),
+            Err(ValidationError::InvalidFormat { .. })
+        ));
+    }
+
+    #[test]
+    fn test_path_safe_validation() {
+        let schema = ParameterSchema::new("file".to_string(), "path-safe".to_string());
+
+        assert!(validate_single_parameter("reports/2024/summary.csv", &schema).is_ok());
+        assert!(validate_single_parameter("notes..txt", &schema).is_ok());
+
+        for unsafe_path in [
+            "../etc/passwd",
+            "reports/../../secret",
+            "reports\\..\\secret",
+            "/etc/passwd",
+            "C:\\Windows",
+            "file\0.txt",
+        ] {
+            assert!(
+                matches!(
+                    validate_single_parameter(unsafe_path, &schema),
+                    Err(ValidationError::InvalidFormat { .. })
+                ),
+                "{:?} should be rejected",
+                unsafe_path
+            );
+        }
+    }
+
+    #[test]
+    fn test_to_json_schema_string() {
+        let schema = ParameterSchema::new("code".to_string(), "string".to_string())
+            .with_length_range(Some(3), Some(8))
+            .with_pattern(r"^[A-Z]+$".to_string())
+            .with_enum(vec!["ABC".to_string(), "DEFG".to_string()]);
+
+        assert_eq!(
+            schema.to_json_schema(),
+            json!({
+                "type": "string",
+                "minLength": 3,
+                "maxLength": 8,
+                "pattern": "^[A-Z]+$",
+                "enum": ["ABC", "DEFG"]
+            })
+        );
+
+        let email = ParameterSchema::new("email".to_string(), "email".to_string());
+        assert_eq!(
+            email.to_json_schema(),
+            json!({"type": "string", "format": "email"})
+        );
+    }
+
+    #[test]
+    fn test_to_json_schema_integer() {
+        let schema = ParameterSchema::new("limit".to_string(), "integer".to_string())
+            .with_range(Some(1.0), Some(100.0))
+            .with_default(json!(10));
+
+        assert_eq!(
+            schema.to_json_schema(),
+            json!({"type": "integer", "minimum": 1, "maximum": 100, "default": 10})
+        );
+
+        let ids = ParameterSchema::new("ids".to_string(), "int".to_string()).allow_multiple();
+        assert_eq!(
+            ids.to_json_schema(),
+            json!({"type": "array", "items": {"type": "integer"}})
+        );
+    }
+
+    #[test]
+    fn test_case_insensitive_unicode_enum() {
+        let schema = ParameterSchema::new("city".to_string(), "string".to_string())
+            .with_enum(vec![
+                "Zürich".to_string(),
+                "Straße".to_string(),
+                "Ísafjörður".to_string(),
+            ])
+            .case_insensitive();
+
+        // Returns the member as declared in the schema
+        assert_eq!(
+            validate_single_parameter("ZÜRICH", &schema).unwrap(),
+            json!("Zürich")
+        );
+        assert_eq!(
+            validate_single_parameter("ísafjörður", &schema).unwrap(),
+            json!("Ísafjörður")
+        );
+        // Full case folding: "ß" folds to "ss"
+        assert_eq!(
+            validate_single_parameter("STRASSE", &schema).unwrap(),
+            json!("Straße")
+        );
+        assert!(validate_single_parameter("Zurich", &schema).is_err());
+
+        // Case-sensitive schemas still require an exact match
+        let strict = ParameterSchema::new("city".to_string(), "string".to_string())
+            .with_enum(vec!["Zürich".to_string()]);
+        assert!(validate_single_parameter("zürich", &strict).is_err());
+    }
+
+    #[test]
+    fn test_whitespace_handling() {
+        let schema = ParameterSchema::new("code".to_string(), "string".to_string())
+            .with_length_range(Some(3), None);
+        // Stripping happens before the length check
+        assert!(validate_single_parameter("  ab  ", &schema).is_err());
+        assert_eq!(
+            validate_single_parameter(" abc\t", &schema).unwrap(),
+            json!("abc")
+        );
+        assert_eq!(
+            validate_single_parameter(
+                " 42 ",
+                &ParameterSchema::new("n".to_string(), "int".to_string())
+            )
+            .unwrap(),
+            json!(42)
+        );
+
+        let color = ParameterSchema::new("color".to_string(), "string".to_string())
+            .with_enum(vec!["Red".to_string(), "Green".to_string()])
+            .case_insensitive();
+        assert_eq!(
+            validate_single_parameter("  rED ", &color).unwrap(),
+            json!("Red")
+        );
+
+        let exact = schema.preserve_whitespace();
+        assert_eq!(
+            validate_single_parameter("  ab  ", &exact).unwrap(),
+            json!("  ab  ")
+        );
+    }
+
+    #[test]
+    fn test_unicode_normalized_enum() {
+        // "Zu\u{308}rich" uses a combining diaeresis instead of the precomposed "ü"
+        let decomposed = "Zu\u{308}rich";
+
+        let schema = ParameterSchema::new("city".to_string(), "string".to_string())
+            .with_enum(vec!["Zürich".to_string()])
+            .case_insensitive();
+        assert!(validate_single_parameter(decomposed, &schema).is_err());
+
+        let normalized = schema.with_unicode_normalization();
+        assert_eq!(
+            validate_single_parameter(&decomposed.to_uppercase(), &normalized).unwrap(),
+            json!("Zürich")
+        );
+    }
+
+    #[test]
+    fn test_country_and_currency_codes() {
+        let country = ParameterSchema::new("country".to_string(), "country".to_string());
+        assert_eq!(
+            validate_single_parameter("us", &country).unwrap(),
+            Value::String("US".to_string())
+        );
+        assert!(matches!(
+            validate_single_parameter("XZ", &country),
+            Err(ValidationError::InvalidFormat { .. })
+        ));
+        assert!(validate_single_parameter("USA", &country).is_err());
+
+        let currency = ParameterSchema::new("currency".to_string(), "currency".to_string());
+        assert_eq!(
+            validate_single_parameter("usd", &currency).unwrap(),
+            Value::String("USD".to_string())
+        );
+        assert!(matches!(
+            validate_single_parameter("ABC", &currency),
+            Err(ValidationError::InvalidFormat { .. })
+        ));
+
+        assert!(COUNTRY_CODES.windows(2).all(|w| w[0] < w[1]));
+        assert!(CURRENCY_CODES.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_compiled_schema_matches_one_shot_validation() {
+        let schema: IndexMap<String, Value> = [
+            (
+                "code".to_string(),
+                json!({"type": "string", "pattern": "^[A-Z]{3}$", "required": true}),
+            ),
+            (
+                "limit".to_string(),
+                json!({"type": "integer", "maximum": 100, "default": 10}),
+            ),
+        ]
+        .into_iter()
+        .collect();
+        let compiled = CompiledSchema::new(schema.clone()).unwrap();
+
+        let code_schema = compiled
+            .schemas()
+            .iter()
+            .find(|s| s.name == "code")
+            .unwrap();
+        let regex = code_schema.compiled_pattern.clone().unwrap();
+
+        let cases: Vec<HashMap<String, String>> = vec![
+            [("code".to_string(), "ABC".to_string())]
+                .into_iter()
+                .collect(),
+            [
+                ("code".to_string(), "abc".to_string()),
+                ("limit".to_string(), "500".to_string()),
+            ]
+            .into_iter()
+            .collect(),
+            HashMap::new(),
+        ];
+        for params in cases {
+            let expected = validate_path_params(params.clone(), schema.clone()).unwrap();
+            let actual = compiled.validate(params).unwrap();
+            assert_eq!(actual.valid, expected.valid);
+            assert_eq!(actual.validated_data, expected.validated_data);
+            let messages = |r: &ValidationResult| {
+                let mut m: Vec<String> = r.errors.iter().map(|e| e.to_string()).collect();
+                m.sort();
+                m
+            };
+            assert_eq!(messages(&actual), messages(&expected));
+        }
+
+        // The same compiled regex is shared rather than rebuilt
+        let again = compiled
+            .schemas()
+            .iter()
+            .find(|s| s.name == "code")
+            .unwrap();
+        assert!(Arc::ptr_eq(
+            &regex,
+            again.compiled_pattern.as_ref().unwrap()
+        ));
+
+        let bad: IndexMap<String, Value> = [("x".to_string(), json!({"pattern": "("}))]
+            .into_iter()
+            .collect();
+        assert!(matches!(
+            CompiledSchema::new(bad),
+            Err(ValidationError::InvalidFormat { .. })
+        ));
+    }
+
+    #[test]
+    fn test_header_injection_rejected() {
+        let schema: IndexMap<String, Value> = [(
+            "x-request-id".to_string(),
+            json!({"type": "string", "maxLength": 64}),
+        )]
+        .into_iter()
+        .collect();
+
+        let headers: HashMap<String, String> = [(
+            "X-Request-Id".to_string(),
+            "abc\r\nSet-Cookie: session=evil".to_string(),
+        )]
+        .into_iter()
+        .collect();
+        let result = validate_header_params(headers, schema.clone()).unwrap();
+        assert!(!result.valid);
+        assert_eq!(result.errors.len(), 1);
+        assert!(matches!(
+            &result.errors[0],
+            ValidationError::InvalidHeaderValue(name) if name == "x-request-id"
+        ));
+        assert!(result.validated_data.is_empty());
+
+        let headers: HashMap<String, String> =
+            [("X-Request-Id".to_string(), "abc-123".to_string())]
+                .into_iter()
+                .collect();
+        let result = validate_header_params(headers, schema.clone()).unwrap();
+        assert!(result.valid);
+        assert_eq!(result.validated_data["x-request-id"], json!("abc-123"));
+
+        // The rejection keeps its place among the other errors
+        let mut schema = schema;
+        schema.insert(
+            "x-tenant".to_string(),
+            json!({"type": "string", "required": true}),
+        );
+        schema.shift_insert(
+            0,
+            "x-trace".to_string(),
+            json!({"type": "string", "required": true}),
+        );
+        let headers: HashMap<String, String> = [("X-Request-Id".to_string(), "a\nb".to_string())]
+            .into_iter()
+            .collect();
+        let errors: Vec<String> = validate_header_params(headers, schema)
+            .unwrap()
+            .errors
+            .iter()
+            .map(|e| e.to_string())
+            .collect();
+        assert_eq!(errors.len(), 3);
+        assert!(errors[0].contains("x-trace"), "{:?}", errors);
+        assert!(errors[1].contains("x-request-id"), "{:?}", errors);
+        assert!(errors[2].contains("x-tenant"), "{:?}", errors);
+    }
+
+    #[test]
+    fn test_mac_and_host_port_types() {
+        let mac = ParameterSchema::new("mac".to_string(), "mac".to_string());
+        assert_eq!(
+            validate_single_parameter("AA:BB:CC:0D:EE:FF", &mac).unwrap(),
+            json!("aa:bb:cc:0d:ee:ff")
+        );
+        assert_eq!(
+            validate_single_parameter("AA-BB-CC-0D-EE-FF", &mac).unwrap(),
+            json!("aa:bb:cc:0d:ee:ff")
+        );
+        for invalid in [
+            "AA:BB:CC:DD:EE",
+            "AA:BB:CC:DD:EE:GG",
+            "AA-BB:CC-DD:EE-FF",
+            "AABBCCDDEEFF",
+        ] {
+            assert!(matches!(
+                validate_single_parameter(invalid, &mac),
+                Err(ValidationError::InvalidFormat { .. })
+            ));
+        }
+
+        let addr = ParameterSchema::new("upstream".to_string(), "host:port".to_string());
+        assert_eq!(
+            validate_single_parameter("db.internal:5432", &addr).unwrap(),
+            json!({"host": "db.internal", "port": 5432})
+        );
+        assert_eq!(
+            validate_single_parameter("[::1]:8080", &addr).unwrap(),
+            json!({"host": "::1", "port": 8080})
+        );
+        for invalid in [
+            "db.internal:70000",
+            "db.internal:0",
+            "db.internal",
+            "-bad-:80",
+            ":80",
+        ] {
+            assert!(matches!(
+                validate_single_parameter(invalid, &addr),
+                Err(ValidationError::InvalidFormat { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_validate_body_json_schema() {
+        let schema = json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "type": "object",
+            "required": ["name", "payment"],
+            "properties": {
+                "name": {"type": "string"},
+                "payment": {
+                    "oneOf": [
+                        {"type": "object", "required": ["card"], "properties": {"card": {"type": "string"}}},
+                        {"type": "object", "required": ["iban"], "properties": {"iban": {"type": "string"}}}
+                    ]
+                }
+            }
+        });
+
+        let body = br#"{"name": "Ada", "payment": {"card": "4111"}}"#.to_vec();
+        let result = validate_body_json_schema(body, &schema).unwrap();
+        assert!(result.valid);
+        assert_eq!(result.validated_data["body"]["name"], json!("Ada"));
+
+        let body = br#"{"payment": {"card": "4111", "iban": "DE00"}}"#.to_vec();
+        let result = validate_body_json_schema(body, &schema).unwrap();
+        assert!(!result.valid);
+        let paths: Vec<&str> = result
+            .errors
+            .iter()
+            .map(|e| match e {
+                ValidationError::SchemaViolation { path, .. } => path.as_str(),
+                other => panic!("unexpected error: {}", other),
+            })
+            .collect();
+        // Missing "name" is reported at the root, the ambiguous oneOf at its member
+        assert!(paths.contains(&""));
+        assert!(paths.contains(&"/payment"));
+
+        assert!(validate_body_json_schema(b"{".to_vec(), &schema).is_err());
+        assert!(validate_body_json_schema(b"{}".to_vec(), &json!({"type": 12})).is_err());
+    }
+
+    #[test]
+    fn test_validate_body_nested_fields() {
+        let mut schema = IndexMap::new();
+        schema.insert(
+            "name".to_string(),
+            json!({"type": "string", "required": true, "minLength": 1}),
+        );
+        schema.insert(
+            "address".to_string(),
+            json!({
+                "type": "object",
+                "required": ["zip"],
+                "properties": {
+                    "city": {"type": "string"},
+                    "zip": {"type": "string", "pattern": "^[0-9]{5}$"}
+                }
+            }),
+        );
+        schema.insert("age".to_string(), json!({"type": "integer", "minimum": 0}));
+
+        let body = br#"{"name": "Ann", "address": {"city": "Oslo", "zip": "01234"}, "age": 30}"#;
+        let result = validate_body_params(body.to_vec(), schema.clone()).unwrap();
+        assert!(result.valid);
+        assert_eq!(
+            result.validated_data["body"]["address"]["zip"],
+            json!("01234")
+        );
+
+        // Missing nested required field
+        let body = br#"{"name": "Ann", "address": {"city": "Oslo"}}"#;
+        let result = validate_body_params(body.to_vec(), schema.clone()).unwrap();
+        assert!(!result.valid);
+        assert!(matches!(
+            &result.errors[..],
+            [ValidationError::MissingRequired(path)] if path == "address.zip"
+        ));
+
+        // Wrong types are reported by path, alongside leaf constraint failures
+        let body = br#"{"name": "", "address": {"city": 7, "zip": "01234"}, "age": "30"}"#;
+        let result = validate_body_params(body.to_vec(), schema).unwrap();
+        let paths: Vec<&str> = result
+            .errors
+            .iter()
+            .map(|error| match error {
+                ValidationError::InvalidType { param, .. } => param.as_str(),
+                ValidationError::TooShort { param, .. } => param.as_str(),
+                other => panic!("unexpected error {:?}", other),
+            })
+            .collect();
+        // Top-level fields are reported in schema declaration order
+        assert_eq!(paths, vec!["name", "address.city", "age"]);
+    }
+
+    #[test]
+    fn test_validate_body_keeps_whitespace() {
+        let mut schema = IndexMap::new();
+        schema.insert(
+            "code".to_string(),
+            json!({"type": "string", "pattern": "^a$", "maxLength": 1}),
+        );
+
+        let result = validate_body_params(br#"{"code": "a"}"#.to_vec(), schema.clone()).unwrap();
+        assert!(result.valid);
+        let result = validate_body_params(br#"{"code": "  a "}"#.to_vec(), schema.clone()).unwrap();
+        assert!(!result.valid);
+
+        // Stripping is still available when the schema asks for it
+        schema["code"]["stripWhitespace"] = json!(true);
+        let result = validate_body_params(br#"{"code": "  a "}"#.to_vec(), schema).unwrap();
+        assert!(result.valid);
+    }
+
+    #[test]
+    fn test_empty_schema_fast_path() {
+        let params: HashMap<String, String> = [("q".to_string(), "rust".to_string())]
+            .into_iter()
+            .collect();
+
+        let query = params
+            .iter()
+            .map(|(k, v)| (k.clone(), vec![v.clone()]))
+            .collect();
+        let result = validate_query_params(query, IndexMap::new(), false).unwrap();
+        assert!(result.valid);
+        assert!(result.errors.is_empty());
+        // Undeclared parameters are never passed through, schema or not
+        assert!(result.validated_data.is_empty());
+
+        assert!(
+            validate_path_params(params.clone(), IndexMap::new())
+                .unwrap()
+                .valid
+        );
+        assert!(
+            validate_header_params(params, IndexMap::new())
+                .unwrap()
+                .valid
+        );
+    }
+
+    #[test]
+    fn test_parse_pagination() {
+        let params = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+        let defaults = PaginationDefaults::default();
+
+        assert_eq!(
+            parse_pagination(&params(&[("page", "3"), ("page_size", "20")]), defaults).unwrap(),
+            Pagination {
+                limit: 20,
+                offset: 40
+            }
+        );
+        assert_eq!(
+            parse_pagination(&params(&[("limit", "10"), ("offset", "30")]), defaults).unwrap(),
+            Pagination {
+                limit: 10,
+                offset: 30
+            }
+        );
+        assert_eq!(
+            parse_pagination(&params(&[]), defaults).unwrap(),
+            Pagination {
+                limit: 50,
+                offset: 0
+            }
+        );
+        assert_eq!(
+            parse_pagination(&params(&[("limit", "100000")]), defaults).unwrap(),
+            Pagination {
+                limit: 500,
+                offset: 0
+            }
+        );
+
+        assert!(matches!(
+            parse_pagination(&params(&[("offset", "-5")]), defaults),
+            Err(ValidationError::OutOfRange { .. })
+        ));
+        assert!(matches!(
+            parse_pagination(&params(&[("page", "0")]), defaults),
+            Err(ValidationError::OutOfRange { .. })
+        ));
+        assert!(matches!(
+            parse_pagination(&params(&[("limit", "ten")]), defaults),
+            Err(ValidationError::InvalidType { .. })
+        ));
+    }
+
+    #[test]
+    fn test_integral_float_coercion() {
+        let strict = ParameterSchema::new("count".to_string(), "integer".to_string());
+        assert!(matches!(
+            validate_single_parameter("42.0", &strict),
+            Err(ValidationError::InvalidType { .. })
+        ));
+        assert_eq!(validate_single_parameter("42", &strict).unwrap(), json!(42));
+
+        let lenient = strict.clone().coerce_integral_floats();
+        assert_eq!(
+            validate_single_parameter("42.0", &lenient).unwrap(),
+            json!(42)
+        );
+        assert_eq!(
+            validate_single_parameter("-7e0", &lenient).unwrap(),
+            json!(-7)
+        );
+        assert!(validate_single_parameter("42.5", &lenient).is_err());
+        assert!(validate_single_parameter("1e30", &lenient).is_err());
+        assert!(validate_single_parameter("NaN", &lenient).is_err());
+    }
+
+    #[test]
+    fn test_bitflags_and_int_enum() {
+        let flags =
+            ParameterSchema::new("perms".to_string(), "bitflags".to_string()).with_flag_mask(0b111);
+        assert_eq!(validate_single_parameter("5", &flags).unwrap(), json!(5));
+        assert_eq!(validate_single_parameter("0x3", &flags).unwrap(), json!(3));
+        assert!(matches!(
+            validate_single_parameter("8", &flags),
+            Err(ValidationError::InvalidFormat { .. })
+        ));
+        assert!(matches!(
+            validate_single_parameter("-1", &flags),
+            Err(ValidationError::InvalidType { .. })
+        ));
+
+        let schema: IndexMap<String, Value> = [(
+            "level".to_string(),
+            json!({"type": "int-enum", "enum": [1, 2, 4]}),
+        )]
+        .into_iter()
+        .collect();
+        let level = parse_schema_map(schema).unwrap().remove(0);
+        assert_eq!(validate_single_parameter("4", &level).unwrap(), json!(4));
+        assert!(matches!(
+            validate_single_parameter("3", &level),
+            Err(ValidationError::InvalidFormat { .. })
+        ));
+        assert_eq!(
+            level.to_json_schema(),
+            json!({"type": "integer", "enum": [1, 2, 4]})
+        );
+    }
+
+    #[test]
+    fn test_output_follows_schema_order() {
+        let schema: IndexMap<String, Value> = [
+            ("zeta", json!({"type": "integer"})),
+            ("alpha", json!({"type": "string", "default": "a"})),
+            ("mid", json!({"type": "boolean"})),
+            ("beta", json!({"type": "string", "required": true})),
+            ("able", json!({"type": "string", "required": true})),
+        ]
+        .into_iter()
+        .map(|(name, spec)| (name.to_string(), spec))
+        .collect();
+        let params = crate::core::request::parse_query_string("mid=true&zeta=3");
+
+        let result = validate_query_params(params, schema, false).unwrap();
+        let keys: Vec<&str> = result.validated_data.keys().map(String::as_str).collect();
+        assert_eq!(keys, ["zeta", "alpha", "mid"]);
+        let missing: Vec<String> = result.errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            missing,
+            [
+                "Missing required parameter: beta",
+                "Missing required parameter: able"
+            ]
+        );
     }
 }
@@ -10,13 +10,16 @@ pub fn init_rust_backend() -> PyResult<bool> {
 
 // Core routing functions
 #[pyfunction]
+#[pyo3(signature = (path, methods, name = None, consumes = None))]
 pub fn create_api_route(
     path: &str,
     methods: Vec<String>,
     name: Option<String>,
+    consumes: Option<Vec<String>>,
 ) -> PyResult<Py<types::FastApiRoute>> {
     Python::with_gil(|py| {
-        let route = core::routing::create_route(path, methods, name)?;
+        let route = core::routing::create_route(path, methods, name)?
+            .with_consumes(consumes.unwrap_or_default());
         Py::new(py, types::FastApiRoute::from(route))
     })
 }
@@ -38,12 +41,104 @@ pub fn match_route(
     })
 }
 
+/// Like `match_route`, but reports 404 and 405 (with allowed methods) separately
+#[pyfunction]
+pub fn match_route_detailed(
+    path: &str,
+    method: &str,
+    routes: Vec<Py<types::FastApiRoute>>,
+) -> PyResult<Py<types::RouteMatchResult>> {
+    Python::with_gil(|py| {
+        let rust_routes: Vec<_> = routes
+            .iter()
+            .map(|r| r.borrow(py).to_rust_route())
+            .collect();
+
+        let status = core::routing::match_route_detailed(path, method, &rust_routes);
+        Py::new(py, types::RouteMatchResult::from(status))
+    })
+}
+
+/// Match through a `RouteTree`, returning a dict tagged by `"status"`:
+/// "matched", "method_not_allowed" or "not_found"
+#[pyfunction]
+pub fn match_route_with_status(
+    path: &str,
+    method: &str,
+    routes: Vec<Py<types::FastApiRoute>>,
+) -> PyResult<Py<PyDict>> {
+    Python::with_gil(|py| {
+        let mut tree = types::RouteTreeWrapper::new();
+        for route in &routes {
+            tree.add_route(route.borrow(py));
+        }
+        tree.match_with_status(py, path, method)
+    })
+}
+
+/// Compile `routes` once into a tree that can be matched against repeatedly
+#[pyfunction]
+#[pyo3(signature = (routes = None))]
+pub fn create_route_tree(
+    py: Python,
+    routes: Option<Vec<Py<types::FastApiRoute>>>,
+) -> types::RouteTreeWrapper {
+    let routes = routes
+        .unwrap_or_default()
+        .iter()
+        .map(|r| r.borrow(py).to_rust_route())
+        .collect();
+    let (inner, warnings) = core::routing::create_route_tree(routes);
+    types::RouteTreeWrapper { inner, warnings }
+}
+
+/// Register `{param:name}` for routes created afterwards
+#[pyfunction]
+pub fn register_path_converter(name: &str, regex_fragment: &str) -> PyResult<()> {
+    core::routing::register_converter(name, regex_fragment)
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
+}
+
+/// Bound the number of compiled path regexes kept in the shared cache
+#[pyfunction]
+pub fn set_regex_cache_capacity(capacity: usize) {
+    core::routing::set_regex_cache_capacity(capacity)
+}
+
 #[pyfunction]
 pub fn compile_path_regex(path: &str) -> PyResult<String> {
     core::routing::compile_path_regex(path)
         .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
 }
 
+#[pyfunction]
+pub fn resolve_effective_method(
+    method: &str,
+    headers: HashMap<String, String>,
+    allowed_overrides: Vec<String>,
+) -> String {
+    core::request::resolve_effective_method(method, &headers, &allowed_overrides)
+}
+
+/// Response headers for an allowed CORS preflight, or `None` if it is refused.
+/// `config` keys match `CorsConfigModel`; missing ones take its defaults.
+#[pyfunction]
+pub fn evaluate_cors_preflight(
+    origin: &str,
+    method: &str,
+    requested_headers: Vec<String>,
+    config: &Bound<PyDict>,
+) -> PyResult<Option<Vec<(String, String)>>> {
+    let encoded = serialization::encoders::jsonable_encoder(config.as_any())
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+    let config: types::models::CorsConfigModel = serde_json::from_str(&encoded)
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+    match core::cors::evaluate_preflight(&config, origin, method, &requested_headers) {
+        core::cors::CorsDecision::Allowed(headers) => Ok(Some(headers)),
+        core::cors::CorsDecision::Rejected(_) => Ok(None),
+    }
+}
+
 // Parameter validation functions
 #[pyfunction]
 pub fn validate_path_params(
@@ -60,19 +155,42 @@ pub fn validate_path_params(
 }
 
 #[pyfunction]
+#[pyo3(signature = (params, schema, treat_empty_as_missing = false))]
 pub fn validate_query_params(
     params: &Bound<PyDict>,
     schema: &Bound<PyDict>,
+    treat_empty_as_missing: bool,
 ) -> PyResult<Py<types::ValidationResult>> {
     Python::with_gil(|py| {
-        let param_map = utils::py_dict_to_hashmap(params)?;
+        let param_map = utils::py_dict_to_multimap(params)?;
         let schema_map = utils::py_dict_to_hashmap(schema)?;
 
-        let result = params::validation::validate_query_params(param_map, schema_map)?;
+        let result = params::validation::validate_query_params(
+            param_map,
+            schema_map,
+            treat_empty_as_missing,
+        )?;
         Py::new(py, types::ValidationResult::from(result))
     })
 }
 
+/// Decode a raw query string, collecting `name[0]`/`name[]` keys into lists
+#[pyfunction]
+#[pyo3(signature = (query, reject_sparse = false))]
+pub fn parse_query_arrays(query: &str, reject_sparse: bool) -> PyResult<Py<PyAny>> {
+    let sparse = if reject_sparse {
+        params::query::SparseIndices::Reject
+    } else {
+        params::query::SparseIndices::FillNull
+    };
+    let grouped = params::query::parse_query_arrays(query, sparse)
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+    let json = serde_json::to_vec(&grouped)
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+    serialization::decoders::deserialize_request(&json, "application/json")
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
+}
+
 #[pyfunction]
 pub fn validate_header_params(
     headers: &Bound<PyDict>,
@@ -87,10 +205,13 @@ pub fn validate_header_params(
     })
 }
 
+/// With `json_schema=True`, `schema` is a full draft-07 JSON Schema for the body
 #[pyfunction]
+#[pyo3(signature = (body, schema, json_schema = false))]
 pub fn validate_body_params(
     body: &Bound<PyAny>,
     schema: &Bound<PyDict>,
+    json_schema: bool,
 ) -> PyResult<Py<types::ValidationResult>> {
     Python::with_gil(|py| {
         let body_data = if let Ok(bytes) = body.downcast::<PyBytes>() {
@@ -103,6 +224,16 @@ pub fn validate_body_params(
             ));
         };
 
+        if json_schema {
+            let encoded = serialization::encoders::jsonable_encoder(schema.as_any())
+                .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+            let schema_value: serde_json::Value = serde_json::from_str(&encoded)
+                .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+            let result = params::validation::validate_body_json_schema(body_data, &schema_value)
+                .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+            return Py::new(py, types::ValidationResult::from(result));
+        }
+
         let schema_map = utils::py_dict_to_hashmap(schema)?;
         let result = params::validation::validate_body_params(body_data, schema_map)?;
         Py::new(py, types::ValidationResult::from(result))
@@ -110,45 +241,151 @@ pub fn validate_body_params(
 }
 
 // Serialization functions
+/// `custom_encoder` maps types to callables, as in FastAPI
 #[pyfunction]
-pub fn jsonable_encoder(obj: &Bound<PyAny>) -> PyResult<String> {
-    serialization::encoders::jsonable_encoder(obj)
+#[pyo3(signature = (obj, custom_encoder=None))]
+pub fn jsonable_encoder<'py>(
+    obj: &Bound<'py, PyAny>,
+    custom_encoder: Option<Bound<'py, PyDict>>,
+) -> PyResult<String> {
+    let options = serialization::encoders::SerializeOptions {
+        custom_encoder,
+        ..Default::default()
+    };
+    serialization::encoders::jsonable_encoder_with_options(obj, &options)
         .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
 }
 
 #[pyfunction]
-pub fn serialize_response(data: &Bound<PyAny>, content_type: Option<&str>) -> PyResult<Vec<u8>> {
-    serialization::encoders::serialize_response(data, content_type)
+pub fn encode_to_writer(obj: &Bound<PyAny>, writer: &Bound<PyAny>) -> PyResult<()> {
+    serialization::encoders::encode_to_writer(obj, writer)
         .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
 }
 
+/// `allow_nan` is `"null"` (default), `"error"` or `"literal"`
 #[pyfunction]
-pub fn deserialize_request(body: &Bound<PyBytes>, content_type: &str) -> PyResult<Py<PyAny>> {
+#[pyo3(signature = (
+    data,
+    content_type = None,
+    allow_nan = "null",
+    indent = None,
+    sort_keys = false,
+    custom_encoder = None
+))]
+pub fn serialize_response<'py>(
+    data: &Bound<'py, PyAny>,
+    content_type: Option<&str>,
+    allow_nan: &str,
+    indent: Option<usize>,
+    sort_keys: bool,
+    custom_encoder: Option<Bound<'py, PyDict>>,
+) -> PyResult<Vec<u8>> {
+    let options = serialization::encoders::SerializeOptions {
+        allow_nan: allow_nan
+            .parse()
+            .map_err(|e: serialization::encoders::EncodingError| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string())
+            })?,
+        indent,
+        sort_keys,
+        custom_encoder,
+    };
+    serialization::encoders::serialize_response_with_options(data, content_type, &options)
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
+}
+
+#[pyfunction]
+#[pyo3(signature = (
+    body,
+    content_type,
+    max_depth = None,
+    max_length = None,
+    max_body_bytes = None,
+    max_form_fields = None,
+    max_part_bytes = None
+))]
+pub fn deserialize_request(
+    body: &Bound<PyBytes>,
+    content_type: &str,
+    max_depth: Option<usize>,
+    max_length: Option<usize>,
+    max_body_bytes: Option<usize>,
+    max_form_fields: Option<usize>,
+    max_part_bytes: Option<usize>,
+) -> PyResult<Py<PyAny>> {
+    let defaults = serialization::decoders::RequestLimits::default();
+    let limits = serialization::decoders::RequestLimits {
+        max_body_bytes: max_body_bytes.unwrap_or(defaults.max_body_bytes),
+        max_depth: max_depth.unwrap_or(defaults.max_depth),
+        max_length: max_length.unwrap_or(defaults.max_length),
+        max_form_fields: max_form_fields.unwrap_or(defaults.max_form_fields),
+        max_part_bytes: max_part_bytes.unwrap_or(defaults.max_part_bytes),
+    };
+
     Python::with_gil(|py| {
-        serialization::decoders::deserialize_request(body.as_bytes(), content_type)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
+        serialization::decoders::deserialize_request_with_limits(
+            body.as_bytes(),
+            content_type,
+            limits,
+        )
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
     })
 }
 
+#[pyfunction]
+pub fn json_diff(old: &Bound<PyAny>, new: &Bound<PyAny>) -> PyResult<Py<PyAny>> {
+    let to_value = |obj: &Bound<PyAny>| -> PyResult<serde_json::Value> {
+        let json = serialization::encoders::jsonable_encoder(obj)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+        serde_json::from_str(&json)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
+    };
+
+    let diff = serialization::diff::json_diff(&to_value(old)?, &to_value(new)?);
+    serialization::decoders::deserialize_request(diff.to_string().as_bytes(), "application/json")
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
+}
+
 // Security functions
 #[pyfunction]
 pub fn constant_time_compare(a: &str, b: &str) -> PyResult<bool> {
     Ok(security::utils::constant_time_compare(a, b))
 }
 
+/// bcrypt verification is deliberately slow, so it runs without the GIL
 #[pyfunction]
 pub fn verify_api_key(
+    py: Python,
     provided_key: &str,
     expected_key: &str,
     algorithm: Option<&str>,
 ) -> PyResult<bool> {
-    security::utils::verify_api_key(provided_key, expected_key, algorithm)
+    py.allow_threads(|| security::utils::verify_api_key(provided_key, expected_key, algorithm))
         .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
 }
 
 #[pyfunction]
-pub fn hash_password(password: &str, algorithm: Option<&str>) -> PyResult<String> {
-    security::utils::hash_password(password, algorithm)
+pub fn parse_authorization(header: &str) -> PyResult<(String, String)> {
+    security::utils::parse_authorization(header)
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
+}
+
+#[pyfunction]
+pub fn weighted_choice(items: Vec<(String, f64)>) -> PyResult<String> {
+    security::utils::weighted_choice(&items)
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
+}
+
+/// bcrypt hashing is deliberately slow, so it runs without the GIL
+#[pyfunction]
+#[pyo3(signature = (password, algorithm = None, cost = security::utils::DEFAULT_BCRYPT_COST))]
+pub fn hash_password(
+    py: Python,
+    password: &str,
+    algorithm: Option<&str>,
+    cost: u32,
+) -> PyResult<String> {
+    py.allow_threads(|| security::utils::hash_password_with_cost(password, algorithm, cost))
         .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
 }
 
@@ -166,8 +403,164 @@ pub fn parse_content_type(content_type: &str) -> PyResult<(String, HashMap<Strin
         .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
 }
 
+#[pyfunction]
+pub fn negotiate_charset(accept_charset: &str, available: Vec<String>) -> Option<String> {
+    utils::content_type::negotiate_charset(accept_charset, &available)
+}
+
+#[pyfunction]
+pub fn negotiate(accept_header: &str, available: Vec<String>) -> Option<String> {
+    let available: Vec<&str> = available.iter().map(String::as_str).collect();
+    utils::content_type::negotiate(accept_header, &available)
+}
+
+#[pyfunction]
+pub fn compute_etag(body: &[u8]) -> String {
+    utils::etag::compute_etag(body)
+}
+
+/// Whether the response should be `304 Not Modified`
+#[pyfunction]
+#[pyo3(signature = (if_none_match, etag))]
+pub fn evaluate_preconditions(if_none_match: Option<&str>, etag: &str) -> bool {
+    utils::etag::evaluate_preconditions(if_none_match, etag)
+}
+
 #[pyfunction]
 pub fn convert_python_type(py_obj: &Bound<PyAny>) -> PyResult<String> {
     utils::type_conv::convert_python_type(py_obj)
         .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn users_route(py: Python) -> Py<types::FastApiRoute> {
+        let route = types::FastApiRoute::new(
+            "/users/{id:int}".to_string(),
+            vec!["GET".to_string(), "PUT".to_string()],
+            None,
+            None,
+        )
+        .unwrap();
+        Py::new(py, route).unwrap()
+    }
+
+    #[test]
+    fn test_match_route_detailed_outcomes() {
+        Python::with_gil(|py| {
+            let found = match_route_detailed("/users/5", "GET", vec![users_route(py)]).unwrap();
+            let found = found.borrow(py);
+            assert_eq!(found.status_code, 200);
+            assert_eq!(found.route_index, Some(0));
+            assert_eq!(found.params.get("id"), Some(&"5".to_string()));
+
+            let not_allowed =
+                match_route_detailed("/users/5", "DELETE", vec![users_route(py)]).unwrap();
+            let not_allowed = not_allowed.borrow(py);
+            assert_eq!(not_allowed.status_code, 405);
+            assert_eq!(not_allowed.route_index, None);
+            assert_eq!(not_allowed.allow_header(), Some("GET, PUT".to_string()));
+
+            let not_found = match_route_detailed("/posts/5", "GET", vec![users_route(py)]).unwrap();
+            let not_found = not_found.borrow(py);
+            assert_eq!(not_found.status_code, 404);
+            assert_eq!(not_found.allow_header(), None);
+        });
+    }
+
+    #[test]
+    fn test_match_route_with_status_wrong_method() {
+        Python::with_gil(|py| {
+            let ping_routes = || {
+                let route = types::FastApiRoute::new(
+                    "/ping".to_string(),
+                    vec!["GET".to_string()],
+                    None,
+                    None,
+                )
+                .unwrap();
+                vec![Py::new(py, route).unwrap()]
+            };
+
+            let result = match_route_with_status("/ping", "POST", ping_routes()).unwrap();
+            let result = result.bind(py);
+            let status: String = result
+                .get_item("status")
+                .unwrap()
+                .unwrap()
+                .extract()
+                .unwrap();
+            assert_eq!(status, "method_not_allowed");
+            let allowed: Vec<String> = result
+                .get_item("allowed_methods")
+                .unwrap()
+                .unwrap()
+                .extract()
+                .unwrap();
+            assert_eq!(allowed, vec!["GET".to_string(), "HEAD".to_string()]);
+
+            let result = match_route_with_status("/ping", "GET", ping_routes()).unwrap();
+            let result = result.bind(py);
+            let index: usize = result
+                .get_item("route_index")
+                .unwrap()
+                .unwrap()
+                .extract()
+                .unwrap();
+            assert_eq!(index, 0);
+        });
+    }
+
+    #[test]
+    fn test_route_tree_wrapper_persists_across_calls() {
+        Python::with_gil(|py| {
+            let tree = create_route_tree(py, Some(vec![users_route(py)]));
+            let tree = Py::new(py, tree).unwrap();
+            assert!(tree.borrow(py).warnings.is_empty());
+
+            let ping =
+                types::FastApiRoute::new("/ping".to_string(), vec!["GET".to_string()], None, None)
+                    .unwrap();
+            let idx = tree
+                .borrow_mut(py)
+                .add_route(Py::new(py, ping).unwrap().borrow(py));
+            assert_eq!(idx, 1);
+            assert_eq!(tree.borrow(py).route_count(), 2);
+
+            // Driven from Python the same way the framework would
+            let locals = PyDict::new_bound(py);
+            locals.set_item("tree", &tree).unwrap();
+            let matched: (usize, HashMap<String, String>) = py
+                .eval_bound("tree.match_route('/users/5', 'PUT')", None, Some(&locals))
+                .unwrap()
+                .extract()
+                .unwrap();
+            assert_eq!(matched.0, 0);
+            assert_eq!(matched.1.get("id"), Some(&"5".to_string()));
+            let status: u16 = py
+                .eval_bound(
+                    "tree.match_detailed('/ping', 'POST').status_code",
+                    None,
+                    Some(&locals),
+                )
+                .unwrap()
+                .extract()
+                .unwrap();
+            assert_eq!(status, 405);
+        });
+    }
+
+    #[test]
+    fn test_created_route_keeps_compiled_pattern() {
+        Python::with_gil(|py| {
+            let route =
+                create_api_route("/users/{id:int}", vec!["GET".to_string()], None, None).unwrap();
+            let route = route.borrow(py);
+            assert!(!route.matches("/posts/abc", "GET"));
+            assert!(!route.matches("/users/abc", "GET"));
+            assert!(route.matches("/users/5", "GET"));
+        });
+    }
+}
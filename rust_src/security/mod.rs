@@ -1,5 +1,7 @@
 pub mod utils;
+pub mod session;
 pub mod oauth2;
 
 pub use utils::*;
+pub use session::*;
 pub use oauth2::*
\ No newline at end of file
@@ -0,0 +1,167 @@
+use crate::security::utils::{generate_session_token, Result, SecurityError};
+use crate::types::models::CacheEntryModel;
+use chrono::{Duration, Utc};
+use dashmap::DashMap;
+use serde_json::Value;
+
+/// In-memory server-side sessions; expired entries are dropped lazily on access
+#[derive(Debug, Default)]
+pub struct SessionStore {
+    sessions: DashMap<String, Session>,
+}
+
+#[derive(Debug)]
+struct Session {
+    entry: CacheEntryModel,
+    ttl: Duration,
+}
+
+impl SessionStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store `data` under a fresh random session id valid for `ttl_seconds`
+    pub fn create(&self, data: Value, ttl_seconds: i64) -> Result<String> {
+        let mut entry = CacheEntryModel::new(String::new(), data)
+            .try_with_ttl(ttl_seconds)
+            .ok_or(SecurityError::InvalidTtl(ttl_seconds))?;
+        let ttl = Duration::seconds(ttl_seconds);
+        loop {
+            let session_id = generate_session_token();
+            if let dashmap::mapref::entry::Entry::Vacant(slot) =
+                self.sessions.entry(session_id.clone())
+            {
+                entry.key = session_id.clone();
+                slot.insert(Session { entry, ttl });
+                return Ok(session_id);
+            }
+        }
+    }
+
+    pub fn get(&self, session_id: &str) -> Option<Value> {
+        let mut session = self.live_session(session_id)?;
+        session.entry.touch();
+        Some(session.entry.value.clone())
+    }
+
+    /// Restart the session's TTL from now; returns false if it no longer exists
+    pub fn touch(&self, session_id: &str) -> bool {
+        match self.live_session(session_id) {
+            Some(mut session) => {
+                // A TTL too long to represent from now on never runs out
+                session.entry.expires_at = Utc::now().checked_add_signed(session.ttl);
+                session.entry.touch();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn destroy(&self, session_id: &str) -> bool {
+        self.sessions.remove(session_id).is_some()
+    }
+
+    /// Drop every expired session, returning how many were removed
+    pub fn purge_expired(&self) -> usize {
+        // Counted as removed, since other threads may insert meanwhile
+        let mut removed = 0;
+        self.sessions.retain(|_, session| {
+            let expired = session.entry.is_expired();
+            removed += usize::from(expired);
+            !expired
+        });
+        removed
+    }
+
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    fn live_session(
+        &self,
+        session_id: &str,
+    ) -> Option<dashmap::mapref::one::RefMut<'_, String, Session>> {
+        let session = self.sessions.get_mut(session_id)?;
+        if session.entry.is_expired() {
+            drop(session);
+            self.sessions
+                .remove_if(session_id, |_, s| s.entry.is_expired());
+            return None;
+        }
+        Some(session)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn expire(store: &SessionStore, session_id: &str) {
+        let mut session = store.sessions.get_mut(session_id).unwrap();
+        session.entry.expires_at = Some(Utc::now() - Duration::seconds(1));
+    }
+
+    #[test]
+    fn test_create_get_destroy() {
+        let store = SessionStore::new();
+        let id = store.create(json!({"user_id": 7}), 60).unwrap();
+        let other = store.create(json!({"user_id": 8}), 60).unwrap();
+        assert_ne!(id, other);
+
+        assert_eq!(store.get(&id), Some(json!({"user_id": 7})));
+        assert!(store.destroy(&id));
+        assert_eq!(store.get(&id), None);
+        assert!(!store.destroy(&id));
+        assert_eq!(store.len(), 1);
+    }
+
+    #[test]
+    fn test_expired_sessions_are_removed() {
+        let store = SessionStore::new();
+        let id = store.create(json!("data"), 60).unwrap();
+        expire(&store, &id);
+
+        assert_eq!(store.get(&id), None);
+        assert!(store.is_empty());
+        assert!(!store.touch(&id));
+
+        let stale = store.create(json!(1), 60).unwrap();
+        store.create(json!(2), 60).unwrap();
+        expire(&store, &stale);
+        assert_eq!(store.purge_expired(), 1);
+        assert_eq!(store.len(), 1);
+    }
+
+    #[test]
+    fn test_out_of_range_ttl_is_rejected() {
+        let store = SessionStore::new();
+        for ttl in [i64::MAX, i64::MAX / 1000, -(i64::MAX / 1000)] {
+            assert!(matches!(
+                store.create(json!(1), ttl),
+                Err(SecurityError::InvalidTtl(t)) if t == ttl
+            ));
+        }
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn test_touch_extends_lifetime() {
+        let store = SessionStore::new();
+        let id = store.create(json!("data"), 60).unwrap();
+        {
+            let mut session = store.sessions.get_mut(&id).unwrap();
+            session.entry.expires_at = Some(Utc::now() + Duration::seconds(1));
+        }
+
+        assert!(store.touch(&id));
+        let expires_at = store.sessions.get(&id).unwrap().entry.expires_at.unwrap();
+        assert!(expires_at > Utc::now() + Duration::seconds(50));
+        assert_eq!(store.get(&id), Some(json!("data")));
+    }
+}
@@ -1,4 +1,5 @@
-use std::time::{SystemTime, UNIX_EPOCH};
+use crate::types::models::{Clock, SystemClock};
+use serde_json::{Map, Value};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -11,42 +12,65 @@ pub enum SecurityError {
     InvalidKeyFormat,
     #[error("Verification failed")]
     VerificationFailed,
+    #[error("Invalid Authorization header: {0}")]
+    InvalidAuthorizationHeader(String),
+    #[error("Invalid weights: {0}")]
+    InvalidWeights(String),
+    #[error("TTL of {0} seconds is out of range")]
+    InvalidTtl(i64),
 }
 
 pub type Result<T> = std::result::Result<T, SecurityError>;
 
 /// Constant-time string comparison to prevent timing attacks
 pub fn constant_time_compare(a: &str, b: &str) -> bool {
-    if a.len() != b.len() {
-        return false;
-    }
-
-    let a_bytes = a.as_bytes();
-    let b_bytes = b.as_bytes();
-
-    let mut result = 0u8;
-    for i in 0..a_bytes.len() {
-        result |= a_bytes[i] ^ b_bytes[i];
-    }
-
-    result == 0
+    constant_time_compare_bytes(a.as_bytes(), b.as_bytes())
 }
 
 /// Constant-time byte array comparison
 pub fn constant_time_compare_bytes(a: &[u8], b: &[u8]) -> bool {
-    if a.len() != b.len() {
-        return false;
+    // Walk the longer input in full and fold the length difference into the
+    // result, so timing reveals neither the mismatch position nor the length
+    let len = a.len().max(b.len());
+    let mut result = (a.len() ^ b.len()) as u64;
+    for i in 0..len {
+        let x = a.get(i).copied().unwrap_or(0);
+        let y = b.get(i).copied().unwrap_or(0);
+        result |= u64::from(x ^ y);
     }
 
-    let mut result = 0u8;
-    for i in 0..a.len() {
-        result |= a[i] ^ b[i];
+    result == 0
+}
+
+/// Split an Authorization header into its lowercased scheme and raw credentials
+pub fn parse_authorization(header: &str) -> Result<(String, String)> {
+    let (scheme, credentials) = header
+        .trim()
+        .split_once(|c: char| c.is_ascii_whitespace())
+        .ok_or_else(|| {
+            SecurityError::InvalidAuthorizationHeader(
+                "expected '<scheme> <credentials>'".to_string(),
+            )
+        })?;
+
+    let credentials = credentials.trim();
+    if credentials.is_empty() {
+        return Err(SecurityError::InvalidAuthorizationHeader(
+            "empty credentials".to_string(),
+        ));
     }
 
-    result == 0
+    Ok((scheme.to_ascii_lowercase(), credentials.to_string()))
 }
 
-/// Verify API key with optional algorithm
+/// bcrypt work factor used when none is given
+pub const DEFAULT_BCRYPT_COST: u32 = 12;
+
+/// Verified against when the stored hash is malformed, so rejecting it costs as
+/// much as a real mismatch
+const DUMMY_BCRYPT_HASH: &str = "$2b$12$b8WQivmsSuOU99A/T2xqbOm6NAtNn.fIVPeOpWPxyGtkjdUC.Ausu";
+
+/// Verify API key with optional algorithm; for `bcrypt`, `expected_key` is the stored hash
 pub fn verify_api_key(
     provided_key: &str,
     expected_key: &str,
@@ -59,55 +83,52 @@ pub fn verify_api_key(
             let expected_hash = hash_sha256(expected_key.as_bytes());
             Ok(constant_time_compare_bytes(&provided_hash, &expected_hash))
         }
-        Some("bcrypt") => {
-            // Note: In a real implementation, you'd use a proper bcrypt library
-            // For now, fallback to constant time comparison
-            Ok(constant_time_compare(provided_key, expected_key))
-        }
+        Some("bcrypt") => verify_bcrypt_with(provided_key, expected_key, |password, hash| {
+            bcrypt::verify(password, hash)
+        }),
         Some(alg) => Err(SecurityError::InvalidAlgorithm(alg.to_string())),
     }
 }
 
+/// bcrypt verification through `verify`, which tests replace to observe calls
+fn verify_bcrypt_with(
+    provided_key: &str,
+    stored_hash: &str,
+    verify: impl Fn(&str, &str) -> bcrypt::BcryptResult<bool>,
+) -> Result<bool> {
+    verify(provided_key, stored_hash).map_err(|e| {
+        let _ = verify(provided_key, DUMMY_BCRYPT_HASH);
+        SecurityError::HashError(e.to_string())
+    })
+}
+
 /// Hash password with specified algorithm
 pub fn hash_password(password: &str, algorithm: Option<&str>) -> Result<String> {
+    hash_password_with_cost(password, algorithm, DEFAULT_BCRYPT_COST)
+}
+
+/// Like `hash_password`, with the bcrypt work factor (4..=31) given explicitly
+pub fn hash_password_with_cost(
+    password: &str,
+    algorithm: Option<&str>,
+    cost: u32,
+) -> Result<String> {
     match algorithm {
         Some("sha256") | None => {
             let hash = hash_sha256(password.as_bytes());
             Ok(hex_encode(&hash))
         }
         Some("bcrypt") => {
-            // Note: In a real implementation, you'd use a proper bcrypt library
-            // For now, use SHA256 as fallback
-            let hash = hash_sha256(password.as_bytes());
-            Ok(format!("bcrypt:{}", hex_encode(&hash)))
+            bcrypt::hash(password, cost).map_err(|e| SecurityError::HashError(e.to_string()))
         }
         Some(alg) => Err(SecurityError::InvalidAlgorithm(alg.to_string())),
     }
 }
 
-/// Generate cryptographically secure random bytes
+/// Generate cryptographically secure random bytes from the OS generator
 pub fn generate_random_bytes(length: usize) -> Vec<u8> {
-    use std::collections::hash_map::DefaultHasher;
-    use std::hash::{Hash, Hasher};
-
-    let mut bytes = Vec::with_capacity(length);
-    let mut hasher = DefaultHasher::new();
-
-    // Use current time as seed
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_nanos();
-    now.hash(&mut hasher);
-
-    let mut seed = hasher.finish();
-
-    for _ in 0..length {
-        // Simple LCG for demonstration (use proper CSPRNG in production)
-        seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
-        bytes.push((seed >> 16) as u8);
-    }
-
+    let mut bytes = vec![0u8; length];
+    getrandom::getrandom(&mut bytes).expect("OS random number generator unavailable");
     bytes
 }
 
@@ -124,31 +145,15 @@ pub fn generate_session_token() -> String {
     hex_encode(&bytes)
 }
 
-/// Simple SHA-256 implementation (for demonstration)
-fn hash_sha256(data: &[u8]) -> [u8; 32] {
-    // Note: In production, use a proper SHA-256 implementation
-    // This is a simplified version for demonstration
-    let mut hasher = std::collections::hash_map::DefaultHasher::new();
-    use std::hash::{Hash, Hasher};
-
-    data.hash(&mut hasher);
-    let hash = hasher.finish();
-
-    let mut result = [0u8; 32];
-    for i in 0..4 {
-        let bytes = ((hash >> (i * 16)) as u64).to_le_bytes();
-        for j in 0..8 {
-            if i * 8 + j < 32 {
-                result[i * 8 + j] = bytes[j];
-            }
-        }
-    }
+/// SHA-256 digest of `data`
+pub(crate) fn hash_sha256(data: &[u8]) -> [u8; 32] {
+    use sha2::{Digest, Sha256};
 
-    result
+    Sha256::digest(data).into()
 }
 
 /// Hex encoding
-fn hex_encode(bytes: &[u8]) -> String {
+pub(crate) fn hex_encode(bytes: &[u8]) -> String {
     const HEX_CHARS: &[u8] = b"0123456789abcdef";
     let mut result = String::with_capacity(bytes.len() * 2);
 
@@ -182,6 +187,44 @@ pub fn generate_secure_random_string(length: usize, charset: Option<&str>) -> St
     result
 }
 
+/// Pick an item with probability proportional to its weight
+pub fn weighted_choice(items: &[(String, f64)]) -> Result<String> {
+    if items.is_empty() {
+        return Err(SecurityError::InvalidWeights(
+            "no items to choose from".to_string(),
+        ));
+    }
+    if items.iter().any(|(_, w)| !w.is_finite() || *w < 0.0) {
+        return Err(SecurityError::InvalidWeights(
+            "weights must be finite and non-negative".to_string(),
+        ));
+    }
+    let total: f64 = items.iter().map(|(_, w)| w).sum();
+    if total <= 0.0 {
+        return Err(SecurityError::InvalidWeights(
+            "all weights are zero".to_string(),
+        ));
+    }
+
+    // 53 random bits give a uniform float in [0, 1)
+    let mut raw = [0u8; 8];
+    raw.copy_from_slice(&generate_random_bytes(8));
+    let unit = (u64::from_le_bytes(raw) >> 11) as f64 / (1u64 << 53) as f64;
+    let target = unit * total;
+
+    let mut cumulative = 0.0;
+    for (item, weight) in items {
+        cumulative += weight;
+        if *weight > 0.0 && target < cumulative {
+            return Ok(item.clone());
+        }
+    }
+
+    // Rounding can leave target just past the last bucket
+    let (item, _) = items.iter().rev().find(|(_, w)| *w > 0.0).unwrap();
+    Ok(item.clone())
+}
+
 /// Timing-safe operation wrapper
 pub fn timing_safe_operation<F, T>(operation: F) -> T
 where
@@ -234,29 +277,62 @@ pub fn validate_password_strength(password: &str) -> (bool, Vec<String>) {
     (errors.is_empty(), errors)
 }
 
+/// Replace the values of sensitive keys with `"***"` so payloads can be logged
+pub fn mask_sensitive(value: &Value, keys: &[String]) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut masked = Map::with_capacity(map.len());
+            for (key, val) in map {
+                if keys.iter().any(|k| k.eq_ignore_ascii_case(key)) {
+                    masked.insert(key.clone(), Value::String("***".to_string()));
+                } else {
+                    masked.insert(key.clone(), mask_sensitive(val, keys));
+                }
+            }
+            Value::Object(masked)
+        }
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| mask_sensitive(item, keys))
+                .collect(),
+        ),
+        _ => value.clone(),
+    }
+}
+
 /// Rate limiting utilities
-pub struct RateLimiter {
+pub struct RateLimiter<C: Clock = SystemClock> {
     requests: std::collections::HashMap<String, Vec<u64>>,
     max_requests: usize,
     window_seconds: u64,
+    clock: C,
 }
 
 impl RateLimiter {
     pub fn new(max_requests: usize, window_seconds: u64) -> Self {
+        Self::new_with_clock(max_requests, window_seconds, SystemClock)
+    }
+}
+
+impl<C: Clock> RateLimiter<C> {
+    pub fn new_with_clock(max_requests: usize, window_seconds: u64, clock: C) -> Self {
         Self {
             requests: std::collections::HashMap::new(),
             max_requests,
             window_seconds,
+            clock,
         }
     }
 
+    fn now_secs(&self) -> u64 {
+        self.clock.now().timestamp().max(0) as u64
+    }
+
     pub fn is_allowed(&mut self, key: &str) -> bool {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let now = self.now_secs();
 
-        let window_start = now - self.window_seconds;
+        let window_start = now.saturating_sub(self.window_seconds);
 
         let timestamps = self
             .requests
@@ -275,12 +351,7 @@ impl RateLimiter {
     }
 
     pub fn cleanup_old_entries(&mut self) {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-
-        let window_start = now - self.window_seconds;
+        let window_start = self.now_secs().saturating_sub(self.window_seconds);
 
         self.requests.retain(|_, timestamps| {
             timestamps.retain(|&timestamp| timestamp > window_start);
@@ -292,6 +363,7 @@ impl RateLimiter {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::models::MockClock;
 
     #[test]
     fn test_constant_time_compare() {
@@ -311,6 +383,17 @@ mod tests {
         assert!(!constant_time_compare_bytes(a, c));
     }
 
+    #[test]
+    fn test_constant_time_compare_length_mismatch() {
+        // Equal length, different content
+        assert!(!constant_time_compare("secret-key", "secret-kez"));
+        // A zero-padded prefix must not compare equal to the longer input
+        assert!(!constant_time_compare_bytes(b"key", b"key\0"));
+        assert!(!constant_time_compare_bytes(b"key\0\0", b"key"));
+        assert!(!constant_time_compare("", "a"));
+        assert!(constant_time_compare("", ""));
+    }
+
     #[test]
     fn test_verify_api_key() {
         let key = "test-key-123";
@@ -325,6 +408,59 @@ mod tests {
         assert!(!verify_api_key(key, "wrong-key", Some("sha256")).unwrap());
     }
 
+    #[test]
+    fn test_hash_sha256_known_answer() {
+        assert_eq!(
+            hash_password("data", Some("sha256")).unwrap(),
+            "3a6eb0790f39ac87c94f3856b2dd2c5d110e6811602261a9a923d3bb23adc8b7"
+        );
+        assert_eq!(
+            hex_encode(&hash_sha256(b"")),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+
+    #[test]
+    fn test_bcrypt_roundtrip() {
+        let hash = hash_password_with_cost("hunter2", Some("bcrypt"), 4).unwrap();
+        assert!(hash.starts_with("$2b$04$"), "{}", hash);
+        assert!(verify_api_key("hunter2", &hash, Some("bcrypt")).unwrap());
+        assert!(!verify_api_key("hunter3", &hash, Some("bcrypt")).unwrap());
+
+        assert!(verify_api_key("hunter2", "not-a-hash", Some("bcrypt")).is_err());
+        assert!(hash_password_with_cost("hunter2", Some("bcrypt"), 3).is_err());
+    }
+
+    #[test]
+    fn test_bcrypt_mismatch_runs_full_verify() {
+        let hash = hash_password_with_cost("correct horse", Some("bcrypt"), 4).unwrap();
+        let calls = std::cell::RefCell::new(Vec::new());
+        let verify = |password: &str, hash: &str| {
+            calls.borrow_mut().push(hash.to_string());
+            bcrypt::verify(password, hash)
+        };
+
+        assert!(!verify_bcrypt_with("x", &hash, verify).unwrap());
+        assert_eq!(*calls.borrow(), vec![hash.clone()]);
+
+        // A malformed stored hash is still paid for with a verify at the default cost
+        calls.borrow_mut().clear();
+        let stub = |_: &str, hash: &str| {
+            calls.borrow_mut().push(hash.to_string());
+            if hash == DUMMY_BCRYPT_HASH {
+                Ok(false)
+            } else {
+                Err(bcrypt::BcryptError::InvalidHash(hash.to_string()))
+            }
+        };
+        assert!(verify_bcrypt_with("x", "not-a-hash", stub).is_err());
+        assert_eq!(
+            *calls.borrow(),
+            vec!["not-a-hash".to_string(), DUMMY_BCRYPT_HASH.to_string()]
+        );
+        assert!(DUMMY_BCRYPT_HASH.starts_with(&format!("$2b${}$", DEFAULT_BCRYPT_COST)));
+    }
+
     #[test]
     fn test_generate_api_key() {
         let key1 = generate_api_key(None);
@@ -333,6 +469,30 @@ mod tests {
         assert!(!key1.is_empty());
         assert!(!key2.is_empty());
         assert_ne!(key1, key2);
+
+        // Padded base64 turns every started 3-byte group into 4 characters
+        for len in [1, 16, 32, 33] {
+            assert_eq!(generate_api_key(Some(len)).len(), len.div_ceil(3) * 4);
+        }
+    }
+
+    #[test]
+    fn test_random_bytes_are_unpredictable() {
+        let a = generate_random_bytes(32);
+        let b = generate_random_bytes(32);
+        assert_eq!(a.len(), 32);
+        assert_ne!(a, b);
+
+        let sample = generate_random_bytes(4096);
+        let mut distinct = sample.clone();
+        distinct.sort_unstable();
+        distinct.dedup();
+        // 4096 uniform draws cover nearly all 256 values
+        assert!(
+            distinct.len() > 200,
+            "only {} distinct bytes",
+            distinct.len()
+        );
     }
 
     #[test]
@@ -358,6 +518,21 @@ mod tests {
         assert!(limiter.is_allowed("user2")); // Different user should be allowed
     }
 
+    #[test]
+    fn test_rate_limiter_window_expires() {
+        let clock = MockClock::default();
+        let mut limiter = RateLimiter::new_with_clock(2, 60, clock.clone());
+
+        assert!(limiter.is_allowed("user1"));
+        assert!(limiter.is_allowed("user1"));
+        assert!(!limiter.is_allowed("user1"));
+
+        clock.advance(chrono::Duration::seconds(61));
+        limiter.cleanup_old_entries();
+        assert!(limiter.requests.is_empty());
+        assert!(limiter.is_allowed("user1"));
+    }
+
     #[test]
     fn test_generate_secure_random_string() {
         let s1 = generate_secure_random_string(10, None);
@@ -370,4 +545,87 @@ mod tests {
         // Check that s2 only contains characters from the specified charset
         assert!(s2.chars().all(|c| "ABC123".contains(c)));
     }
+
+    #[test]
+    fn test_mask_sensitive() {
+        let keys = vec![
+            "password".to_string(),
+            "token".to_string(),
+            "authorization".to_string(),
+        ];
+        let payload = serde_json::json!({
+            "username": "john",
+            "Password": "hunter2",
+            "headers": {"Authorization": "Bearer abc", "accept": "*/*"},
+            "sessions": [{"token": "t1", "id": 1}, {"TOKEN": "t2", "id": 2}]
+        });
+
+        let masked = mask_sensitive(&payload, &keys);
+
+        assert_eq!(
+            masked,
+            serde_json::json!({
+                "username": "john",
+                "Password": "***",
+                "headers": {"Authorization": "***", "accept": "*/*"},
+                "sessions": [{"token": "***", "id": 1}, {"TOKEN": "***", "id": 2}]
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_authorization() {
+        assert_eq!(
+            parse_authorization("Bearer x").unwrap(),
+            ("bearer".to_string(), "x".to_string())
+        );
+        assert_eq!(
+            parse_authorization("  Basic   dXNlcjpwYXNz ").unwrap(),
+            ("basic".to_string(), "dXNlcjpwYXNz".to_string())
+        );
+
+        assert!(matches!(
+            parse_authorization("Bearer"),
+            Err(SecurityError::InvalidAuthorizationHeader(_))
+        ));
+        assert!(matches!(
+            parse_authorization("Bearer    "),
+            Err(SecurityError::InvalidAuthorizationHeader(_))
+        ));
+        assert!(matches!(
+            parse_authorization(""),
+            Err(SecurityError::InvalidAuthorizationHeader(_))
+        ));
+    }
+
+    #[test]
+    fn test_weighted_choice_distribution() {
+        let items = vec![
+            ("a".to_string(), 3.0),
+            ("b".to_string(), 1.0),
+            ("never".to_string(), 0.0),
+        ];
+
+        let draws = 4000;
+        let mut a_count = 0;
+        for _ in 0..draws {
+            match weighted_choice(&items).unwrap().as_str() {
+                "a" => a_count += 1,
+                "b" => {}
+                other => panic!("zero-weight item chosen: {}", other),
+            }
+        }
+        let ratio = a_count as f64 / draws as f64;
+        assert!((ratio - 0.75).abs() < 0.05, "ratio was {}", ratio);
+
+        assert!(matches!(
+            weighted_choice(&[]),
+            Err(SecurityError::InvalidWeights(_))
+        ));
+        assert!(matches!(
+            weighted_choice(&[("x".to_string(), 0.0)]),
+            Err(SecurityError::InvalidWeights(_))
+        ));
+        assert!(weighted_choice(&[("x".to_string(), -1.0)]).is_err());
+    }
 }
@@ -1,28 +1,100 @@
 use pyo3::prelude::*;
-use pyo3::types::{PyBool, PyDict, PyFloat, PyInt, PyList, PyNone, PyString};
+use pyo3::types::{PyBool, PyBytes, PyDict, PyFloat, PyInt, PyList, PyNone, PyString};
 use serde_json::Value;
 use thiserror::Error;
 
+use super::encoders::is_integer_literal;
+use super::guard::{RecursionError, RecursionGuard, DEFAULT_MAX_DEPTH};
+use crate::utils::content_type::{get_boundary, is_multipart_content_type, parse_content_type};
+
 #[derive(Error, Debug)]
 pub enum DecodingError {
     #[error("Invalid JSON: {0}")]
     InvalidJson(String),
+    #[error("Invalid CBOR: {0}")]
+    InvalidCbor(String),
     #[error("Unsupported content type: {0}")]
     UnsupportedContentType(String),
     #[error("Encoding error: {0}")]
     EncodingError(String),
     #[error("Parse error: {0}")]
     ParseError(String),
+    #[error("Limit exceeded: {0}")]
+    LimitExceeded(String),
+    #[error("Request body exceeds {limit} bytes")]
+    BodyTooLarge { limit: usize },
+    #[error("Form has more than {limit} fields")]
+    TooManyFields { limit: usize },
+    #[error("Multipart part exceeds {limit} bytes")]
+    PartTooLarge { limit: usize },
+}
+
+impl From<RecursionError> for DecodingError {
+    fn from(err: RecursionError) -> Self {
+        DecodingError::LimitExceeded(err.to_string())
+    }
+}
+
+impl From<PyErr> for DecodingError {
+    fn from(err: PyErr) -> Self {
+        DecodingError::ParseError(err.to_string())
+    }
 }
 
 pub type Result<T> = std::result::Result<T, DecodingError>;
 
+/// Limits applied to every request body, whatever its content type
+#[derive(Debug, Clone, Copy)]
+pub struct RequestLimits {
+    /// Maximum raw body size in bytes
+    pub max_body_bytes: usize,
+    /// Maximum nesting of JSON arrays and objects
+    pub max_depth: usize,
+    /// Maximum number of values in a JSON document
+    pub max_length: usize,
+    /// Maximum number of `key=value` pairs in a urlencoded form
+    pub max_form_fields: usize,
+    /// Maximum size in bytes of a single multipart part
+    pub max_part_bytes: usize,
+}
+
+impl Default for RequestLimits {
+    fn default() -> Self {
+        Self {
+            max_body_bytes: 10 * 1024 * 1024,
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_length: 1_000_000,
+            max_form_fields: 1_000,
+            max_part_bytes: 5 * 1024 * 1024,
+        }
+    }
+}
+
 /// Deserialize request body based on content type
 pub fn deserialize_request(body: &[u8], content_type: &str) -> Result<Py<PyAny>> {
+    deserialize_request_with_limits(body, content_type, RequestLimits::default())
+}
+
+/// Deserialize request body, enforcing `limits` before and during parsing
+pub fn deserialize_request_with_limits(
+    body: &[u8],
+    content_type: &str,
+    limits: RequestLimits,
+) -> Result<Py<PyAny>> {
+    if body.len() > limits.max_body_bytes {
+        return Err(DecodingError::BodyTooLarge {
+            limit: limits.max_body_bytes,
+        });
+    }
+
     Python::with_gil(|py| match content_type {
-        "application/json" => deserialize_json(body, py),
-        "application/x-www-form-urlencoded" => deserialize_form_data(body, py),
+        "application/json" => deserialize_json(body, py, limits),
+        "application/x-www-form-urlencoded" => deserialize_form_data(body, py, limits),
+        "application/cbor" => deserialize_cbor(body, py, limits),
         "text/plain" => deserialize_text(body, py),
+        _ if is_multipart_content_type(content_type) => {
+            deserialize_multipart(body, content_type, py, limits)
+        }
         _ => Err(DecodingError::UnsupportedContentType(
             content_type.to_string(),
         )),
@@ -30,56 +102,316 @@ pub fn deserialize_request(body: &[u8], content_type: &str) -> Result<Py<PyAny>>
 }
 
 /// Deserialize JSON body to Python object
-fn deserialize_json(body: &[u8], py: Python) -> Result<Py<PyAny>> {
+fn deserialize_json(body: &[u8], py: Python, limits: RequestLimits) -> Result<Py<PyAny>> {
     let body_str =
         std::str::from_utf8(body).map_err(|e| DecodingError::EncodingError(e.to_string()))?;
 
     let json_value: Value =
         serde_json::from_str(body_str).map_err(|e| DecodingError::InvalidJson(e.to_string()))?;
 
-    json_to_python(&json_value, py)
+    let mut nodes = 0;
+    let mut guard = RecursionGuard::new(limits.max_depth);
+    json_to_python(&json_value, py, &limits, &mut guard, &mut nodes)
+}
+
+/// Deserialize CBOR body to Python object; byte strings become `bytes`
+fn deserialize_cbor(body: &[u8], py: Python, limits: RequestLimits) -> Result<Py<PyAny>> {
+    let cbor_value: ciborium::Value =
+        ciborium::from_reader(body).map_err(|e| DecodingError::InvalidCbor(e.to_string()))?;
+
+    let mut nodes = 0;
+    let mut guard = RecursionGuard::new(limits.max_depth);
+    cbor_to_python(&cbor_value, py, &limits, &mut guard, &mut nodes)
 }
 
 /// Deserialize form data to Python dict
-fn deserialize_form_data(body: &[u8], py: Python) -> Result<Py<PyAny>> {
+fn deserialize_form_data(body: &[u8], py: Python, limits: RequestLimits) -> Result<Py<PyAny>> {
     let body_str =
         std::str::from_utf8(body).map_err(|e| DecodingError::EncodingError(e.to_string()))?;
 
+    let field_count = body_str.split('&').filter(|pair| !pair.is_empty()).count();
+    if field_count > limits.max_form_fields {
+        return Err(DecodingError::TooManyFields {
+            limit: limits.max_form_fields,
+        });
+    }
+
     let dict = PyDict::new_bound(py);
 
     for pair in body_str.split('&') {
         if let Some((key, value)) = pair.split_once('=') {
-            let decoded_key = percent_decode(key);
-            let decoded_value = percent_decode(value);
-
-            // Handle multiple values for the same key
-            if let Ok(existing) = dict.get_item(&decoded_key) {
-                if let Some(existing_val) = existing {
-                    // Convert to list if not already
-                    if let Ok(list) = existing_val.downcast::<PyList>() {
-                        list.append(decoded_value)?;
-                    } else {
-                        let list = PyList::new_bound(
-                            py,
-                            &[existing_val, PyString::new_bound(py, &decoded_value)],
-                        );
-                        dict.set_item(decoded_key, list)?;
+            let decoded_key = form_urldecode(key);
+            let decoded_value = form_urldecode(value);
+            let value = PyString::new_bound(py, &decoded_value).into_any();
+            append_form_value(&dict, &decoded_key, value)?;
+        } else if !pair.is_empty() {
+            let decoded_key = form_urldecode(pair);
+            dict.set_item(decoded_key, "")?;
+        }
+    }
+
+    Ok(dict.into_py(py))
+}
+
+/// One part of a `multipart/form-data` body; `data` borrows from the body
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultipartPart<'a> {
+    pub name: String,
+    pub filename: Option<String>,
+    pub content_type: Option<String>,
+    pub data: &'a [u8],
+}
+
+/// Iterator over the parts of a `multipart/form-data` body.
+///
+/// Each part is located only when the iterator advances. Any preamble before
+/// the first delimiter is skipped, and iteration ends at `--boundary--`.
+pub struct MultipartParts<'a> {
+    body: &'a [u8],
+    delimiter: Vec<u8>,
+    pos: usize,
+    started: bool,
+    done: bool,
+}
+
+impl<'a> MultipartParts<'a> {
+    pub fn new(body: &'a [u8], boundary: &str) -> Self {
+        let mut delimiter = b"\r\n--".to_vec();
+        delimiter.extend_from_slice(boundary.as_bytes());
+        Self {
+            body,
+            delimiter,
+            pos: 0,
+            started: false,
+            done: false,
+        }
+    }
+
+    fn fail(&mut self, message: &str) -> Option<Result<MultipartPart<'a>>> {
+        self.done = true;
+        Some(Err(DecodingError::ParseError(format!(
+            "multipart body {}",
+            message
+        ))))
+    }
+}
+
+impl<'a> Iterator for MultipartParts<'a> {
+    type Item = Result<MultipartPart<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        if !self.started {
+            // The opening delimiter may start the body or follow a preamble
+            let dash_boundary = &self.delimiter[2..];
+            let opening = if self.body.starts_with(dash_boundary) {
+                Some(dash_boundary.len())
+            } else {
+                find_bytes(self.body, &self.delimiter).map(|i| i + self.delimiter.len())
+            };
+            match opening {
+                Some(pos) => self.pos = pos,
+                None => return self.fail("has no opening boundary"),
+            }
+            self.started = true;
+        }
+
+        let rest = &self.body[self.pos..];
+        if rest.starts_with(b"--") {
+            self.done = true;
+            return None;
+        }
+
+        // Transport padding may follow the boundary before its line break
+        let padding = rest
+            .iter()
+            .take_while(|b| matches!(b, b' ' | b'\t'))
+            .count();
+        if !rest[padding..].starts_with(b"\r\n") {
+            return self.fail("has a malformed boundary line");
+        }
+        let line_break = self.pos + padding;
+
+        // Searching from the boundary's own line break also finds an empty header block
+        let Some(blank_line) = find_bytes(&self.body[line_break..], b"\r\n\r\n") else {
+            return self.fail("has a part without a blank line after its headers");
+        };
+        let headers_end = line_break + blank_line;
+        let headers = self
+            .body
+            .get(line_break + 2..headers_end)
+            .unwrap_or_default();
+        let content_start = headers_end + 4;
+
+        let Some(content_len) = find_bytes(&self.body[content_start..], &self.delimiter) else {
+            return self.fail("is missing its closing boundary");
+        };
+        let data = &self.body[content_start..content_start + content_len];
+        self.pos = content_start + content_len + self.delimiter.len();
+
+        let part =
+            parse_part_headers(headers).map(|(name, filename, content_type)| MultipartPart {
+                name,
+                filename,
+                content_type,
+                data,
+            });
+        if part.is_err() {
+            self.done = true;
+        }
+        Some(part)
+    }
+}
+
+fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// The `name`, `filename` and `Content-Type` of a part from its header block
+fn parse_part_headers(headers: &[u8]) -> Result<(String, Option<String>, Option<String>)> {
+    let headers =
+        std::str::from_utf8(headers).map_err(|e| DecodingError::EncodingError(e.to_string()))?;
+
+    let mut name = None;
+    let mut filename = None;
+    let mut content_type = None;
+    for line in headers.split("\r\n") {
+        let Some((header, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        if header.trim().eq_ignore_ascii_case("content-disposition") {
+            for param in split_header_params(value).into_iter().skip(1) {
+                if let Some((key, param_value)) = param.split_once('=') {
+                    let param_value = unquote(param_value.trim());
+                    match key.trim().to_ascii_lowercase().as_str() {
+                        "name" => name = Some(param_value),
+                        "filename" => filename = Some(param_value),
+                        _ => {}
                     }
-                } else {
-                    dict.set_item(decoded_key, decoded_value)?;
                 }
-            } else {
-                dict.set_item(decoded_key, decoded_value)?;
             }
-        } else if !pair.is_empty() {
-            let decoded_key = percent_decode(pair);
-            dict.set_item(decoded_key, "")?;
+        } else if header.trim().eq_ignore_ascii_case("content-type") {
+            content_type = Some(value.to_string());
+        }
+    }
+
+    let name = name.ok_or_else(|| {
+        DecodingError::ParseError("multipart part has no name in Content-Disposition".to_string())
+    })?;
+    Ok((name, filename, content_type))
+}
+
+/// Split a header value on `;`, ignoring separators inside quoted strings
+fn split_header_params(value: &str) -> Vec<&str> {
+    let mut params = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+    for (i, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ';' if !quoted => {
+                params.push(&value[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    params.push(&value[start..]);
+    params
+}
+
+fn unquote(value: &str) -> String {
+    match value
+        .strip_prefix('"')
+        .and_then(|inner| inner.strip_suffix('"'))
+    {
+        Some(inner) => inner.replace("\\\"", "\"").replace("\\\\", "\\"),
+        None => value.to_string(),
+    }
+}
+
+/// Deserialize `multipart/form-data`: text fields become strings and file
+/// parts become `{filename, content_type, bytes}` dicts
+fn deserialize_multipart(
+    body: &[u8],
+    content_type: &str,
+    py: Python,
+    limits: RequestLimits,
+) -> Result<Py<PyAny>> {
+    let (media_type, parameters) =
+        parse_content_type(content_type).map_err(|e| DecodingError::ParseError(e.to_string()))?;
+    if media_type != "multipart/form-data" {
+        return Err(DecodingError::UnsupportedContentType(media_type));
+    }
+    let boundary = get_boundary(&parameters)
+        .filter(|boundary| !boundary.is_empty())
+        .ok_or_else(|| {
+            DecodingError::ParseError("multipart/form-data without a boundary".to_string())
+        })?;
+
+    let dict = PyDict::new_bound(py);
+
+    for (index, part) in MultipartParts::new(body, boundary).enumerate() {
+        if index >= limits.max_form_fields {
+            return Err(DecodingError::TooManyFields {
+                limit: limits.max_form_fields,
+            });
         }
+        let part = part?;
+        if part.data.len() > limits.max_part_bytes {
+            return Err(DecodingError::PartTooLarge {
+                limit: limits.max_part_bytes,
+            });
+        }
+
+        let value = match part.filename {
+            Some(filename) => {
+                let file = PyDict::new_bound(py);
+                file.set_item("filename", filename)?;
+                file.set_item("content_type", part.content_type)?;
+                file.set_item("bytes", PyBytes::new_bound(py, part.data))?;
+                file.into_any()
+            }
+            None => {
+                let text = std::str::from_utf8(part.data)
+                    .map_err(|e| DecodingError::EncodingError(e.to_string()))?;
+                PyString::new_bound(py, text).into_any()
+            }
+        };
+        append_form_value(&dict, &part.name, value)?;
     }
 
     Ok(dict.into_py(py))
 }
 
+/// Store `value` under `key`, collecting repeated keys into a list
+fn append_form_value<'py>(
+    dict: &Bound<'py, PyDict>,
+    key: &str,
+    value: Bound<'py, PyAny>,
+) -> Result<()> {
+    match dict.get_item(key)? {
+        Some(existing) => {
+            if let Ok(list) = existing.downcast::<PyList>() {
+                list.append(value)?;
+            } else {
+                dict.set_item(key, PyList::new_bound(dict.py(), [existing, value]))?;
+            }
+        }
+        None => dict.set_item(key, value)?,
+    }
+    Ok(())
+}
+
 /// Deserialize plain text
 fn deserialize_text(body: &[u8], py: Python) -> Result<Py<PyAny>> {
     let text =
@@ -88,40 +420,136 @@ fn deserialize_text(body: &[u8], py: Python) -> Result<Py<PyAny>> {
     Ok(PyString::new_bound(py, text).into_py(py))
 }
 
+/// Convert an already-parsed, trusted value such as stored session data to a
+/// Python object; request size and depth limits do not apply
+pub fn value_to_python(value: &Value, py: Python) -> Result<Py<PyAny>> {
+    let limits = RequestLimits {
+        max_depth: usize::MAX,
+        max_length: usize::MAX,
+        ..RequestLimits::default()
+    };
+    let mut guard = RecursionGuard::new(usize::MAX);
+    json_to_python(value, py, &limits, &mut guard, &mut 0)
+}
+
 /// Convert JSON value to Python object
-fn json_to_python(value: &Value, py: Python) -> Result<Py<PyAny>> {
+fn json_to_python(
+    value: &Value,
+    py: Python,
+    limits: &RequestLimits,
+    guard: &mut RecursionGuard,
+    nodes: &mut usize,
+) -> Result<Py<PyAny>> {
+    *nodes += 1;
+    if *nodes > limits.max_length {
+        return Err(DecodingError::LimitExceeded(format!(
+            "more than {} JSON values",
+            limits.max_length
+        )));
+    }
+
     match value {
         Value::Null => Ok(PyNone::get_bound(py).into_py(py)),
         Value::Bool(b) => Ok(PyBool::new_bound(py, *b).into_py(py)),
         Value::Number(n) => {
             if let Some(i) = n.as_i64() {
                 Ok(PyInt::new_bound(py, i).into_py(py))
+            } else if let Some(u) = n.as_u64() {
+                Ok(u.into_py(py))
+            } else if is_integer_literal(n) {
+                // Beyond `u64`, let Python parse the digits into an unbounded int
+                Ok(py
+                    .get_type_bound::<PyInt>()
+                    .call1((n.to_string(),))?
+                    .unbind())
             } else if let Some(f) = n.as_f64() {
                 Ok(PyFloat::new_bound(py, f).into_py(py))
             } else {
-                Err(DecodingError::ParseError("Invalid number".to_string()))
+                // Past the `f64` range, e.g. `1e400`; `float()` gives an infinity
+                // just as `json.loads` does
+                Ok(py
+                    .get_type_bound::<PyFloat>()
+                    .call1((n.to_string(),))?
+                    .unbind())
             }
         }
         Value::String(s) => Ok(PyString::new_bound(py, s).into_py(py)),
         Value::Array(arr) => {
+            guard.enter()?;
             let py_list = PyList::empty_bound(py);
             for item in arr {
-                let py_item = json_to_python(item, py)?;
+                let py_item = json_to_python(item, py, limits, guard, nodes)?;
                 py_list.append(py_item)?;
             }
+            guard.exit();
             Ok(py_list.into_py(py))
         }
         Value::Object(obj) => {
+            guard.enter()?;
             let py_dict = PyDict::new_bound(py);
             for (key, val) in obj {
-                let py_val = json_to_python(val, py)?;
+                let py_val = json_to_python(val, py, limits, guard, nodes)?;
                 py_dict.set_item(key, py_val)?;
             }
+            guard.exit();
             Ok(py_dict.into_py(py))
         }
     }
 }
 
+/// Convert CBOR value to Python object under the same limits as JSON
+fn cbor_to_python(
+    value: &ciborium::Value,
+    py: Python,
+    limits: &RequestLimits,
+    guard: &mut RecursionGuard,
+    nodes: &mut usize,
+) -> Result<Py<PyAny>> {
+    *nodes += 1;
+    if *nodes > limits.max_length {
+        return Err(DecodingError::LimitExceeded(format!(
+            "more than {} CBOR values",
+            limits.max_length
+        )));
+    }
+
+    let py_err = |e: PyErr| DecodingError::ParseError(e.to_string());
+    match value {
+        ciborium::Value::Null => Ok(PyNone::get_bound(py).into_py(py)),
+        ciborium::Value::Bool(b) => Ok(PyBool::new_bound(py, *b).into_py(py)),
+        ciborium::Value::Integer(i) => Ok(i128::from(*i).into_py(py)),
+        ciborium::Value::Float(f) => Ok(PyFloat::new_bound(py, *f).into_py(py)),
+        ciborium::Value::Text(s) => Ok(PyString::new_bound(py, s).into_py(py)),
+        ciborium::Value::Bytes(b) => Ok(PyBytes::new_bound(py, b).into_py(py)),
+        // Tags (dates, bignums, ...) carry no Python-side meaning here; use the tagged item
+        ciborium::Value::Tag(_, inner) => cbor_to_python(inner, py, limits, guard, nodes),
+        ciborium::Value::Array(arr) => {
+            guard.enter()?;
+            let py_list = PyList::empty_bound(py);
+            for item in arr {
+                let py_item = cbor_to_python(item, py, limits, guard, nodes)?;
+                py_list.append(py_item).map_err(py_err)?;
+            }
+            guard.exit();
+            Ok(py_list.into_py(py))
+        }
+        ciborium::Value::Map(entries) => {
+            guard.enter()?;
+            let py_dict = PyDict::new_bound(py);
+            for (key, val) in entries {
+                let py_key = cbor_to_python(key, py, limits, guard, nodes)?;
+                let py_val = cbor_to_python(val, py, limits, guard, nodes)?;
+                py_dict.set_item(py_key, py_val).map_err(py_err)?;
+            }
+            guard.exit();
+            Ok(py_dict.into_py(py))
+        }
+        _ => Err(DecodingError::InvalidCbor(
+            "unsupported CBOR value".to_string(),
+        )),
+    }
+}
+
 /// URL percent decoding
 fn percent_decode(input: &str) -> String {
     percent_encoding::percent_decode_str(input)
@@ -129,6 +557,11 @@ fn percent_decode(input: &str) -> String {
         .into_owned()
 }
 
+/// Form field decoding, where `+` stands for a space
+fn form_urldecode(input: &str) -> String {
+    percent_decode(&input.replace('+', " "))
+}
+
 /// Fast JSON parsing for common cases
 pub fn fast_parse_json_string(json_str: &str) -> Result<Value> {
     serde_json::from_str(json_str).map_err(|e| DecodingError::InvalidJson(e.to_string()))
@@ -152,7 +585,7 @@ mod tests {
     fn test_deserialize_json() {
         Python::with_gil(|py| {
             let json_body = br#"{"name": "John", "age": 30, "active": true}"#;
-            let result = deserialize_json(json_body, py).unwrap();
+            let result = deserialize_json(json_body, py, RequestLimits::default()).unwrap();
 
             let dict = result.downcast_bound::<PyDict>(py).unwrap();
             assert_eq!(
@@ -186,7 +619,7 @@ mod tests {
     fn test_deserialize_form_data() {
         Python::with_gil(|py| {
             let form_body = b"name=John&age=30&active=true";
-            let result = deserialize_form_data(form_body, py).unwrap();
+            let result = deserialize_form_data(form_body, py, RequestLimits::default()).unwrap();
 
             let dict = result.downcast_bound::<PyDict>(py).unwrap();
             assert_eq!(
@@ -212,7 +645,7 @@ mod tests {
     fn test_deserialize_form_data_multiple_values() {
         Python::with_gil(|py| {
             let form_body = b"tags=rust&tags=web&tags=api";
-            let result = deserialize_form_data(form_body, py).unwrap();
+            let result = deserialize_form_data(form_body, py, RequestLimits::default()).unwrap();
 
             let dict = result.downcast_bound::<PyDict>(py).unwrap();
             let tags = dict.get_item("tags").unwrap().unwrap();
@@ -250,13 +683,358 @@ mod tests {
         assert_eq!(percent_decode("Hello%20World"), "Hello World");
         assert_eq!(percent_decode("test%21%40%23"), "test!@#");
         assert_eq!(percent_decode("no_encoding"), "no_encoding");
+        assert_eq!(percent_decode("a+b"), "a+b");
+    }
+
+    #[test]
+    fn test_form_data_plus_is_space() {
+        assert_eq!(form_urldecode("a+b"), "a b");
+        assert_eq!(form_urldecode("a%2Bb"), "a+b");
+        assert_eq!(form_urldecode("1+%2B+1%3D2"), "1 + 1=2");
+
+        Python::with_gil(|py| {
+            let body = b"q=hello+world&sum=1%2B1&name+with+space=x";
+            let result = deserialize_form_data(body, py, RequestLimits::default()).unwrap();
+            let dict = result.downcast_bound::<PyDict>(py).unwrap();
+            let get =
+                |key: &str| -> String { dict.get_item(key).unwrap().unwrap().extract().unwrap() };
+            assert_eq!(get("q"), "hello world");
+            assert_eq!(get("sum"), "1+1");
+            assert_eq!(get("name with space"), "x");
+        });
     }
 
     #[test]
     fn test_invalid_json() {
         let invalid_json = b"invalid json";
         Python::with_gil(|py| {
-            assert!(deserialize_json(invalid_json, py).is_err());
+            assert!(deserialize_json(invalid_json, py, RequestLimits::default()).is_err());
+        });
+    }
+
+    #[test]
+    fn test_deserialize_json_limits() {
+        Python::with_gil(|py| {
+            let limits = RequestLimits {
+                max_depth: 3,
+                max_length: 10,
+                ..RequestLimits::default()
+            };
+
+            assert!(deserialize_json(br#"{"a": {"b": [1]}}"#, py, limits).is_ok());
+            assert!(matches!(
+                deserialize_json(br#"{"a": {"b": {"c": [1]}}}"#, py, limits),
+                Err(DecodingError::LimitExceeded(_))
+            ));
+
+            // The array itself counts as one value
+            assert!(deserialize_json(b"[1, 2, 3, 4, 5, 6, 7, 8, 9]", py, limits).is_ok());
+            assert!(matches!(
+                deserialize_json(b"[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]", py, limits),
+                Err(DecodingError::LimitExceeded(_))
+            ));
+        });
+    }
+
+    #[test]
+    fn test_request_limits_per_content_type() {
+        let limits = RequestLimits {
+            max_body_bytes: 32,
+            max_form_fields: 2,
+            ..RequestLimits::default()
+        };
+        let oversized = vec![b'a'; 33];
+
+        for content_type in [
+            "application/json",
+            "application/x-www-form-urlencoded",
+            "text/plain",
+        ] {
+            assert!(matches!(
+                deserialize_request_with_limits(&oversized, content_type, limits),
+                Err(DecodingError::BodyTooLarge { limit: 32 })
+            ));
+        }
+
+        assert!(deserialize_request_with_limits(
+            b"a=1&b=2",
+            "application/x-www-form-urlencoded",
+            limits
+        )
+        .is_ok());
+        assert!(matches!(
+            deserialize_request_with_limits(
+                b"a=1&b=2&c=3",
+                "application/x-www-form-urlencoded",
+                limits
+            ),
+            Err(DecodingError::TooManyFields { limit: 2 })
+        ));
+
+        let shallow = RequestLimits {
+            max_depth: 1,
+            ..limits
+        };
+        assert!(matches!(
+            deserialize_request_with_limits(b"[[1]]", "application/json", shallow),
+            Err(DecodingError::LimitExceeded(_))
+        ));
+    }
+
+    #[test]
+    fn test_deserialize_cbor() {
+        Python::with_gil(|py| {
+            let value = ciborium::Value::Map(vec![
+                (
+                    ciborium::Value::Text("user".into()),
+                    ciborium::Value::Map(vec![
+                        (
+                            ciborium::Value::Text("name".into()),
+                            ciborium::Value::Text("Ada".into()),
+                        ),
+                        (
+                            ciborium::Value::Text("tags".into()),
+                            ciborium::Value::Array(vec![1.into(), 2.into()]),
+                        ),
+                    ]),
+                ),
+                (
+                    ciborium::Value::Text("blob".into()),
+                    ciborium::Value::Bytes(vec![0, 159, 255]),
+                ),
+            ]);
+            let mut body = Vec::new();
+            ciborium::into_writer(&value, &mut body).unwrap();
+
+            let result = deserialize_request(&body, "application/cbor").unwrap();
+            let dict = result.downcast_bound::<PyDict>(py).unwrap();
+            let user = dict.get_item("user").unwrap().unwrap();
+            let user = user.downcast::<PyDict>().unwrap();
+            assert_eq!(
+                user.get_item("name")
+                    .unwrap()
+                    .unwrap()
+                    .extract::<String>()
+                    .unwrap(),
+                "Ada"
+            );
+            assert_eq!(
+                user.get_item("tags")
+                    .unwrap()
+                    .unwrap()
+                    .extract::<Vec<i64>>()
+                    .unwrap(),
+                vec![1, 2]
+            );
+            let blob = dict.get_item("blob").unwrap().unwrap();
+            assert_eq!(
+                blob.downcast::<PyBytes>().unwrap().as_bytes(),
+                &[0, 159, 255]
+            );
+
+            assert!(matches!(
+                deserialize_request(&[0xff, 0x00], "application/cbor"),
+                Err(DecodingError::InvalidCbor(_))
+            ));
+        });
+    }
+
+    const MULTIPART_BODY: &[u8] = b"preamble to ignore\r\n\
+--XyZ\r\n\
+Content-Disposition: form-data; name=\"title\"\r\n\
+\r\n\
+Quarterly report\r\n\
+--XyZ\r\n\
+Content-Disposition: form-data; name=\"upload\"; filename=\"notes; v2.txt\"\r\n\
+Content-Type: text/plain\r\n\
+\r\n\
+line one\r\nmid-line --XyZ is not a delimiter\r\n\
+--XyZ--\r\n\
+epilogue to ignore";
+
+    #[test]
+    fn test_multipart_parts_split_on_boundary() {
+        let parts: Vec<_> = MultipartParts::new(MULTIPART_BODY, "XyZ")
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(parts.len(), 2);
+
+        assert_eq!(parts[0].name, "title");
+        assert_eq!(parts[0].filename, None);
+        assert_eq!(parts[0].data, b"Quarterly report");
+
+        assert_eq!(parts[1].name, "upload");
+        assert_eq!(parts[1].filename.as_deref(), Some("notes; v2.txt"));
+        assert_eq!(parts[1].content_type.as_deref(), Some("text/plain"));
+        assert_eq!(
+            parts[1].data,
+            b"line one\r\nmid-line --XyZ is not a delimiter"
+        );
+
+        let truncated = &MULTIPART_BODY[..MULTIPART_BODY.len() - 30];
+        let result: Result<Vec<_>> = MultipartParts::new(truncated, "XyZ").collect();
+        assert!(matches!(result, Err(DecodingError::ParseError(_))));
+        assert!(MultipartParts::new(b"no delimiter", "XyZ")
+            .next()
+            .unwrap()
+            .is_err());
+    }
+
+    #[test]
+    fn test_deserialize_multipart() {
+        Python::with_gil(|py| {
+            let result =
+                deserialize_request(MULTIPART_BODY, "multipart/form-data; boundary=\"XyZ\"")
+                    .unwrap();
+            let dict = result.downcast_bound::<PyDict>(py).unwrap();
+            assert_eq!(
+                dict.get_item("title")
+                    .unwrap()
+                    .unwrap()
+                    .extract::<String>()
+                    .unwrap(),
+                "Quarterly report"
+            );
+
+            let upload = dict.get_item("upload").unwrap().unwrap();
+            let upload = upload.downcast::<PyDict>().unwrap();
+            let field = |key: &str| upload.get_item(key).unwrap().unwrap();
+            assert_eq!(
+                field("filename").extract::<String>().unwrap(),
+                "notes; v2.txt"
+            );
+            assert_eq!(
+                field("content_type").extract::<String>().unwrap(),
+                "text/plain"
+            );
+            assert_eq!(
+                field("bytes").downcast::<PyBytes>().unwrap().as_bytes(),
+                b"line one\r\nmid-line --XyZ is not a delimiter"
+            );
+
+            assert!(matches!(
+                deserialize_request(MULTIPART_BODY, "multipart/form-data"),
+                Err(DecodingError::ParseError(_))
+            ));
+        });
+    }
+
+    #[test]
+    fn test_deserialize_multipart_repeated_names() {
+        let body = b"--b\r\n\
+Content-Disposition: form-data; name=\"tag\"\r\n\r\nrust\r\n\
+--b\r\n\
+Content-Disposition: form-data; name=\"tag\"\r\n\r\nweb\r\n\
+--b\r\n\
+Content-Disposition: form-data; name=\"tag\"; filename=\"tag.bin\"\r\n\r\n\x00\x01\r\n\
+--b--";
+        Python::with_gil(|py| {
+            let result = deserialize_request(body, "multipart/form-data; boundary=b").unwrap();
+            let dict = result.downcast_bound::<PyDict>(py).unwrap();
+            let tags = dict.get_item("tag").unwrap().unwrap();
+            let tags = tags.downcast::<PyList>().unwrap();
+            assert_eq!(tags.len(), 3);
+            assert_eq!(
+                tags.get_item(1).unwrap().extract::<String>().unwrap(),
+                "web"
+            );
+            let file = tags.get_item(2).unwrap();
+            let file = file.downcast::<PyDict>().unwrap();
+            assert!(file.get_item("content_type").unwrap().unwrap().is_none());
+
+            let limits = RequestLimits {
+                max_form_fields: 2,
+                ..RequestLimits::default()
+            };
+            assert!(matches!(
+                deserialize_request_with_limits(body, "multipart/form-data; boundary=b", limits),
+                Err(DecodingError::TooManyFields { limit: 2 })
+            ));
+        });
+    }
+
+    #[test]
+    fn test_deserialize_multipart_part_limit() {
+        let body = b"--b\r\n\
+Content-Disposition: form-data; name=\"small\"\r\n\r\nabcd\r\n\
+--b\r\n\
+Content-Disposition: form-data; name=\"large\"; filename=\"a.bin\"\r\n\r\nabcdefghi\r\n\
+--b--";
+        let content_type = "multipart/form-data; boundary=b";
+
+        let limits = RequestLimits {
+            max_part_bytes: 9,
+            ..RequestLimits::default()
+        };
+        assert!(deserialize_request_with_limits(body, content_type, limits).is_ok());
+
+        // The body as a whole is well under the limit; only the second part is too big
+        let limits = RequestLimits {
+            max_part_bytes: 8,
+            ..RequestLimits::default()
+        };
+        assert!(matches!(
+            deserialize_request_with_limits(body, content_type, limits),
+            Err(DecodingError::PartTooLarge { limit: 8 })
+        ));
+    }
+
+    #[test]
+    fn test_json_numbers_keep_int_float_distinction() {
+        Python::with_gil(|py| {
+            let body = b"[1, 1.0, 10000000000000000000, -5, \
+                123456789012345678901234567890, 1e400, -2.5e-3]";
+            let result = deserialize_request(body, "application/json").unwrap();
+            let list = result.downcast_bound::<PyList>(py).unwrap();
+            let type_name = |i: usize| -> String {
+                let item = list.get_item(i).unwrap();
+                item.get_type().name().unwrap().to_string()
+            };
+
+            assert_eq!(type_name(0), "int");
+            assert_eq!(type_name(1), "float");
+            assert_eq!(type_name(2), "int");
+            assert_eq!(type_name(3), "int");
+            assert_eq!(type_name(4), "int");
+            assert_eq!(type_name(5), "float");
+
+            assert_eq!(list.get_item(1).unwrap().extract::<f64>().unwrap(), 1.0);
+            assert_eq!(
+                list.get_item(2).unwrap().extract::<u64>().unwrap(),
+                10_000_000_000_000_000_000
+            );
+            assert_eq!(list.get_item(3).unwrap().extract::<i64>().unwrap(), -5);
+            assert_eq!(
+                list.get_item(4).unwrap().str().unwrap().to_string(),
+                "123456789012345678901234567890"
+            );
+            assert_eq!(
+                list.get_item(5).unwrap().extract::<f64>().unwrap(),
+                f64::INFINITY
+            );
+            assert_eq!(list.get_item(6).unwrap().extract::<f64>().unwrap(), -2.5e-3);
+        });
+    }
+
+    #[test]
+    fn test_value_to_python_ignores_request_limits() {
+        Python::with_gil(|py| {
+            let mut value = serde_json::json!("leaf");
+            for _ in 0..DEFAULT_MAX_DEPTH + 10 {
+                value = serde_json::json!([value]);
+            }
+            let mut item = value_to_python(&value, py).unwrap().into_bound(py);
+            for _ in 0..DEFAULT_MAX_DEPTH + 10 {
+                item = item.get_item(0).unwrap();
+            }
+            assert_eq!(item.extract::<String>().unwrap(), "leaf");
+
+            let big: Value = serde_json::from_str("123456789012345678901234567890").unwrap();
+            let converted = value_to_python(&big, py).unwrap();
+            assert_eq!(
+                converted.bind(py).str().unwrap().to_str().unwrap(),
+                "123456789012345678901234567890"
+            );
         });
     }
 }
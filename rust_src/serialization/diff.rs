@@ -0,0 +1,117 @@
+use serde_json::{json, Map, Value};
+
+/// Structured diff of two JSON values for audit logging.
+///
+/// Returns `{"added": {..}, "removed": {..}, "changed": {..}}` keyed by JSON
+/// Pointer, where each changed entry is `{"old": .., "new": ..}`. Objects are
+/// compared key by key and equal-length arrays element by element; anything
+/// else that differs is reported as a single change.
+pub fn json_diff(old: &Value, new: &Value) -> Value {
+    let mut diff = JsonDiff::default();
+    diff.compare(String::new(), old, new);
+
+    json!({
+        "added": diff.added,
+        "removed": diff.removed,
+        "changed": diff.changed,
+    })
+}
+
+#[derive(Default)]
+struct JsonDiff {
+    added: Map<String, Value>,
+    removed: Map<String, Value>,
+    changed: Map<String, Value>,
+}
+
+impl JsonDiff {
+    fn compare(&mut self, path: String, old: &Value, new: &Value) {
+        match (old, new) {
+            (Value::Object(old_map), Value::Object(new_map)) => {
+                for (key, old_value) in old_map {
+                    let child = child_path(&path, key);
+                    match new_map.get(key) {
+                        Some(new_value) => self.compare(child, old_value, new_value),
+                        None => {
+                            self.removed.insert(child, old_value.clone());
+                        }
+                    }
+                }
+                for (key, new_value) in new_map {
+                    if !old_map.contains_key(key) {
+                        self.added.insert(child_path(&path, key), new_value.clone());
+                    }
+                }
+            }
+            (Value::Array(old_items), Value::Array(new_items))
+                if old_items.len() == new_items.len() =>
+            {
+                for (i, (old_item, new_item)) in old_items.iter().zip(new_items).enumerate() {
+                    self.compare(child_path(&path, &i.to_string()), old_item, new_item);
+                }
+            }
+            _ if old != new => {
+                self.changed
+                    .insert(path, json!({ "old": old.clone(), "new": new.clone() }));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Append a reference token, escaping per RFC 6901
+fn child_path(parent: &str, key: &str) -> String {
+    format!("{}/{}", parent, key.replace('~', "~0").replace('/', "~1"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changed_scalar() {
+        let diff = json_diff(&json!({"name": "a"}), &json!({"name": "b"}));
+        assert_eq!(
+            diff,
+            json!({"added": {}, "removed": {}, "changed": {"/name": {"old": "a", "new": "b"}}})
+        );
+        assert_eq!(
+            json_diff(&json!(1), &json!(2))["changed"],
+            json!({"": {"old": 1, "new": 2}})
+        );
+    }
+
+    #[test]
+    fn test_added_and_removed_keys() {
+        let diff = json_diff(&json!({"a": 1, "b": 2}), &json!({"a": 1, "c": 3}));
+        assert_eq!(diff["added"], json!({"/c": 3}));
+        assert_eq!(diff["removed"], json!({"/b": 2}));
+        assert_eq!(diff["changed"], json!({}));
+    }
+
+    #[test]
+    fn test_nested_change() {
+        let old = json!({"user": {"address": {"city": "Paris"}, "tags": ["x", "y"]}});
+        let new = json!({"user": {"address": {"city": "Lyon"}, "tags": ["x", "z"]}});
+        let diff = json_diff(&old, &new);
+        assert_eq!(
+            diff["changed"],
+            json!({
+                "/user/address/city": {"old": "Paris", "new": "Lyon"},
+                "/user/tags/1": {"old": "y", "new": "z"},
+            })
+        );
+
+        // Arrays of different length are replaced wholesale; keys are escaped
+        let diff = json_diff(&json!({"a/b": [1]}), &json!({"a/b": [1, 2]}));
+        assert_eq!(
+            diff["changed"],
+            json!({"/a~1b": {"old": [1], "new": [1, 2]}})
+        );
+
+        assert_eq!(
+            json_diff(&old, &old),
+            json!({"added": {}, "removed": {}, "changed": {}})
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use bytes::Bytes;
 use chrono::{DateTime, NaiveDateTime, Utc};
 use pyo3::prelude::*;
+use pyo3::sync::GILOnceCell;
 use pyo3::types::{
     PyAny, PyBool, PyBytes, PyDict, PyFloat, PyInt, PyList, PyNone, PyString, PyTuple,
 };
@@ -8,6 +9,8 @@ use serde_json::{Map, Value};
 use std::collections::HashMap;
 use thiserror::Error;
 
+use super::guard::{RecursionError, RecursionGuard};
+
 #[derive(Error, Debug)]
 pub enum EncodingError {
     #[error("Unsupported type for JSON encoding: {0}")]
@@ -18,22 +21,374 @@ pub enum EncodingError {
     InvalidDatetime(String),
     #[error("Circular reference detected")]
     CircularReference,
+    #[error("Limit exceeded: {0}")]
+    LimitExceeded(String),
+}
+
+impl From<RecursionError> for EncodingError {
+    fn from(err: RecursionError) -> Self {
+        match err {
+            RecursionError::CircularReference => EncodingError::CircularReference,
+            RecursionError::TooDeep { .. } => EncodingError::LimitExceeded(err.to_string()),
+        }
+    }
 }
 
 pub type Result<T> = std::result::Result<T, EncodingError>;
 
 pub fn jsonable_encoder(obj: &Bound<PyAny>) -> Result<String> {
-    let value = python_to_json_value(obj, &mut std::collections::HashSet::new())?;
+    jsonable_encoder_with_options(obj, &SerializeOptions::default())
+}
+
+/// `jsonable_encoder` honouring `allow_nan` (except `Literal`) and
+/// `custom_encoder`; keys are always sorted and never indented
+pub fn jsonable_encoder_with_options(
+    obj: &Bound<PyAny>,
+    options: &SerializeOptions,
+) -> Result<String> {
+    let value = python_to_json_value(obj, &mut RecursionGuard::default(), options)?;
     serde_json::to_string(&value).map_err(|e| EncodingError::SerializationError(e.to_string()))
 }
 
+/// Bytes buffered by `encode_to_writer` before each `write` call
+const WRITER_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Stream the `jsonable_encoder` encoding of `obj` into a Python file-like
+/// `writer` in bounded chunks instead of building the whole string
+pub fn encode_to_writer(obj: &Bound<PyAny>, writer: &Bound<PyAny>) -> Result<()> {
+    let mut sink = ChunkedWriter {
+        writer: Some(writer),
+        buffer: Vec::with_capacity(WRITER_CHUNK_SIZE),
+    };
+    // `jsonable_encoder` builds a sorted `Map`, so sort here too
+    let options = SerializeOptions {
+        sort_keys: true,
+        ..SerializeOptions::default()
+    };
+    stream_json_value(obj, &mut sink, &mut RecursionGuard::default(), &options)?;
+    sink.flush()
+}
+
+/// JSON text for `obj`; unlike `jsonable_encoder` this can write the
+/// `NaN`/`Infinity` tokens of `NanMode::Literal` and honours `indent`
+fn encode_json_bytes(obj: &Bound<PyAny>, options: &SerializeOptions) -> Result<Vec<u8>> {
+    let mut sink = ChunkedWriter {
+        writer: None,
+        buffer: Vec::new(),
+    };
+    stream_json_value(obj, &mut sink, &mut RecursionGuard::default(), options)?;
+    Ok(sink.buffer)
+}
+
+/// Without a `writer` everything stays in `buffer`
+struct ChunkedWriter<'a, 'py> {
+    writer: Option<&'a Bound<'py, PyAny>>,
+    buffer: Vec<u8>,
+}
+
+impl ChunkedWriter<'_, '_> {
+    fn push(&mut self, bytes: &[u8]) -> Result<()> {
+        self.buffer.extend_from_slice(bytes);
+        self.flush_if_full()
+    }
+
+    fn push_json<T: serde::Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        serde_json::to_writer(&mut self.buffer, value)
+            .map_err(|e| EncodingError::SerializationError(e.to_string()))?;
+        self.flush_if_full()
+    }
+
+    /// Line break and indentation before an item at nesting `level`, when indenting
+    fn newline(&mut self, indent: Option<usize>, level: usize) -> Result<()> {
+        if let Some(width) = indent {
+            self.buffer.push(b'\n');
+            self.buffer.resize(self.buffer.len() + width * level, b' ');
+            self.flush_if_full()?;
+        }
+        Ok(())
+    }
+
+    fn flush_if_full(&mut self) -> Result<()> {
+        if self.buffer.len() >= WRITER_CHUNK_SIZE {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        let Some(writer) = self.writer else {
+            return Ok(());
+        };
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let chunk = PyBytes::new_bound(writer.py(), &self.buffer);
+        writer
+            .call_method1("write", (chunk,))
+            .map_err(|e| EncodingError::SerializationError(e.to_string()))?;
+        self.buffer.clear();
+        Ok(())
+    }
+}
+
+/// Containers are streamed element by element; anything else is encoded whole
+fn stream_json_value(
+    obj: &Bound<PyAny>,
+    sink: &mut ChunkedWriter,
+    guard: &mut RecursionGuard,
+    options: &SerializeOptions,
+) -> Result<()> {
+    let obj_id = obj.as_ptr() as usize;
+
+    if let Some(encoded) = custom_encoded(obj, options)? {
+        guard.enter_alias(obj_id)?;
+        stream_json_value(&encoded, sink, guard, options)?;
+        guard.exit_alias(obj_id);
+        return Ok(());
+    }
+
+    if let Ok(dict) = obj.downcast::<PyDict>() {
+        guard.enter_object(obj_id)?;
+        // Keys that stringify alike (`1` and `"1"`) keep the first position and
+        // the last value, as inserting into a `Map` would
+        let mut entries: Vec<(String, Bound<PyAny>)> = Vec::with_capacity(dict.len());
+        let mut positions: HashMap<String, usize> = HashMap::new();
+        for (key, value) in dict.iter() {
+            let key = dict_key_string(&key)?;
+            match positions.get(&key) {
+                Some(&i) => entries[i].1 = value,
+                None => {
+                    positions.insert(key.clone(), entries.len());
+                    entries.push((key, value));
+                }
+            }
+        }
+        if options.sort_keys {
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+        }
+
+        let level = guard.depth();
+        sink.push(b"{")?;
+        for (i, (key, value)) in entries.iter().enumerate() {
+            if i > 0 {
+                sink.push(b",")?;
+            }
+            sink.newline(options.indent, level)?;
+            sink.push_json(key)?;
+            sink.push(if options.indent.is_some() {
+                b": "
+            } else {
+                b":"
+            })?;
+            stream_json_value(value, sink, guard, options)?;
+        }
+        if !entries.is_empty() {
+            sink.newline(options.indent, level - 1)?;
+        }
+        sink.push(b"}")?;
+        guard.exit_object(obj_id);
+        return Ok(());
+    }
+
+    let items: Vec<Bound<PyAny>> = if let Ok(list) = obj.downcast::<PyList>() {
+        list.iter().collect()
+    } else if let Ok(tuple) = obj.downcast::<PyTuple>() {
+        tuple.iter().collect()
+    } else if obj.is_none() || obj.downcast::<PyString>().is_ok() || obj.downcast::<PyInt>().is_ok()
+    {
+        // The common leaves (`bool` included) skip the type checks below
+        return sink.push_json(&python_to_json_value(obj, guard, options)?);
+    } else if let Some(num) = non_finite_number(obj) {
+        return sink.push(options.allow_nan.non_finite_token(num)?.as_bytes());
+    } else if let Some(contents) = encoded_contents(obj) {
+        return stream_json_value(&contents, sink, guard, options);
+    } else {
+        let value = python_to_json_value(obj, guard, options)?;
+        return sink.push_json(&value);
+    };
+
+    guard.enter_object(obj_id)?;
+    let level = guard.depth();
+    sink.push(b"[")?;
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            sink.push(b",")?;
+        }
+        sink.newline(options.indent, level)?;
+        stream_json_value(item, sink, guard, options)?;
+    }
+    if !items.is_empty() {
+        sink.newline(options.indent, level - 1)?;
+    }
+    sink.push(b"]")?;
+    guard.exit_object(obj_id);
+    Ok(())
+}
+
+/// Response body encodings supported by `serialize_response`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseFormat {
+    Json,
+    Ndjson,
+    Cbor,
+    Text,
+    Octet,
+}
+
+impl ResponseFormat {
+    /// Resolve a content type, ignoring parameters and case; vendor types such
+    /// as `application/vnd.api+json` encode as JSON. `None` means JSON.
+    pub fn from_content_type(content_type: Option<&str>) -> Result<Self> {
+        let Some(content_type) = content_type else {
+            return Ok(ResponseFormat::Json);
+        };
+        let media_type = content_type
+            .split(';')
+            .next()
+            .unwrap_or("")
+            .trim()
+            .to_ascii_lowercase();
+
+        match media_type.as_str() {
+            "application/json" => Ok(ResponseFormat::Json),
+            "application/x-ndjson" | "application/ndjson" => Ok(ResponseFormat::Ndjson),
+            "application/cbor" => Ok(ResponseFormat::Cbor),
+            "text/plain" => Ok(ResponseFormat::Text),
+            "application/octet-stream" => Ok(ResponseFormat::Octet),
+            mt if mt.ends_with("+json") => Ok(ResponseFormat::Json),
+            _ => Err(EncodingError::UnsupportedType(format!(
+                "Unsupported content type: {}",
+                content_type
+            ))),
+        }
+    }
+}
+
+/// How non-finite floats are encoded, since JSON has no NaN or Infinity
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NanMode {
+    /// Write `null`
+    #[default]
+    Null,
+    /// Fail with a serialization error
+    Error,
+    /// Write the JavaScript tokens `NaN`, `Infinity` and `-Infinity`
+    Literal,
+}
+
+impl NanMode {
+    fn non_finite_token(self, value: f64) -> Result<&'static str> {
+        match self {
+            NanMode::Null => Ok("null"),
+            NanMode::Error => Err(non_finite_error(value)),
+            NanMode::Literal if value.is_nan() => Ok("NaN"),
+            NanMode::Literal if value > 0.0 => Ok("Infinity"),
+            NanMode::Literal => Ok("-Infinity"),
+        }
+    }
+}
+
+impl std::str::FromStr for NanMode {
+    type Err = EncodingError;
+
+    fn from_str(mode: &str) -> Result<Self> {
+        match mode.to_ascii_lowercase().as_str() {
+            "null" => Ok(NanMode::Null),
+            "error" => Ok(NanMode::Error),
+            "literal" => Ok(NanMode::Literal),
+            _ => Err(EncodingError::UnsupportedType(format!(
+                "Unknown NaN mode: {}",
+                mode
+            ))),
+        }
+    }
+}
+
+fn non_finite_error(value: f64) -> EncodingError {
+    EncodingError::SerializationError(format!(
+        "Out of range float values are not JSON compliant: {}",
+        value
+    ))
+}
+
+/// Options for `serialize_response_with_options`
+#[derive(Debug, Clone, Default)]
+pub struct SerializeOptions<'py> {
+    pub allow_nan: NanMode,
+    /// Spaces per nesting level for JSON output; NDJSON stays one line per item
+    pub indent: Option<usize>,
+    /// Sort object keys instead of keeping dict insertion order
+    pub sort_keys: bool,
+    /// FastAPI's `custom_encoder`: `{type: callable}`, where the callable's
+    /// result is encoded in place of any instance of that type
+    pub custom_encoder: Option<Bound<'py, PyDict>>,
+}
+
+/// The result of the `custom_encoder` entry for `obj`'s exact type, or else
+/// the first entry whose type `obj` is an instance of
+fn custom_encoded<'py>(
+    obj: &Bound<'py, PyAny>,
+    options: &SerializeOptions<'py>,
+) -> Result<Option<Bound<'py, PyAny>>> {
+    let Some(encoders) = &options.custom_encoder else {
+        return Ok(None);
+    };
+    let encoder = match encoders.get_item(obj.get_type()).ok().flatten() {
+        Some(encoder) => Some(encoder),
+        None => encoders
+            .iter()
+            .find(|(ty, _)| obj.is_instance(ty).unwrap_or(false))
+            .map(|(_, encoder)| encoder),
+    };
+    encoder
+        .map(|encoder| encoder.call1((obj,)))
+        .transpose()
+        .map_err(|e| EncodingError::SerializationError(e.to_string()))
+}
+
 pub fn serialize_response(data: &Bound<PyAny>, content_type: Option<&str>) -> Result<Vec<u8>> {
-    match content_type {
-        Some("application/json") | None => {
-            let json_str = jsonable_encoder(data)?;
-            Ok(json_str.into_bytes())
+    serialize_response_with_options(data, content_type, &SerializeOptions::default())
+}
+
+pub fn serialize_response_with_options(
+    data: &Bound<PyAny>,
+    content_type: Option<&str>,
+    options: &SerializeOptions,
+) -> Result<Vec<u8>> {
+    match ResponseFormat::from_content_type(content_type)? {
+        ResponseFormat::Json => encode_json_bytes(data, options),
+        ResponseFormat::Ndjson => {
+            // Strings, bytes and dicts iterate, but not as a sequence of rows
+            let is_scalar_or_mapping = data.downcast::<PyString>().is_ok()
+                || data.downcast::<PyBytes>().is_ok()
+                || data.downcast::<PyDict>().is_ok();
+            let items = data
+                .iter()
+                .ok()
+                .filter(|_| !is_scalar_or_mapping)
+                .ok_or_else(|| {
+                    EncodingError::UnsupportedType("Expected an iterable for NDJSON".to_string())
+                })?;
+            let line_options = SerializeOptions {
+                indent: None,
+                ..options.clone()
+            };
+            let mut out = Vec::new();
+            for item in items {
+                let item = item.map_err(|e| EncodingError::SerializationError(e.to_string()))?;
+                out.extend_from_slice(&encode_json_bytes(&item, &line_options)?);
+                out.push(b'\n');
+            }
+            Ok(out)
         }
-        Some("text/plain") => {
+        ResponseFormat::Cbor => {
+            let value = python_to_cbor_value(data, &mut RecursionGuard::default(), options)?;
+            let mut out = Vec::new();
+            ciborium::into_writer(&value, &mut out)
+                .map_err(|e| EncodingError::SerializationError(e.to_string()))?;
+            Ok(out)
+        }
+        ResponseFormat::Text => {
             let text = data
                 .str()
                 .map_err(|e| EncodingError::SerializationError(e.to_string()))?
@@ -41,7 +396,7 @@ pub fn serialize_response(data: &Bound<PyAny>, content_type: Option<&str>) -> Re
                 .map_err(|e| EncodingError::SerializationError(e.to_string()))?;
             Ok(text.as_bytes().to_vec())
         }
-        Some("application/octet-stream") => {
+        ResponseFormat::Octet => {
             if let Ok(bytes) = data.downcast::<PyBytes>() {
                 Ok(bytes.as_bytes().to_vec())
             } else {
@@ -50,21 +405,23 @@ pub fn serialize_response(data: &Bound<PyAny>, content_type: Option<&str>) -> Re
                 ))
             }
         }
-        Some(ct) => Err(EncodingError::UnsupportedType(format!(
-            "Unsupported content type: {}",
-            ct
-        ))),
     }
 }
 
 fn python_to_json_value(
     obj: &Bound<PyAny>,
-    visited: &mut std::collections::HashSet<usize>,
+    guard: &mut RecursionGuard,
+    options: &SerializeOptions,
 ) -> Result<Value> {
-    // Prevent infinite recursion
     let obj_id = obj.as_ptr() as usize;
-    if visited.contains(&obj_id) {
-        return Err(EncodingError::CircularReference);
+
+    // Custom encoders may return another instance of the same type, so the
+    // guard bounds how often they are re-applied
+    if let Some(encoded) = custom_encoded(obj, options)? {
+        guard.enter_alias(obj_id)?;
+        let result = python_to_json_value(&encoded, guard, options);
+        guard.exit_alias(obj_id);
+        return result;
     }
 
     // Handle None
@@ -80,47 +437,58 @@ fn python_to_json_value(
         return Ok(Value::String(text.to_string()));
     }
 
-    if let Ok(i) = obj.downcast::<PyInt>() {
-        let num = i
-            .extract::<i64>()
+    // bool is a subclass of int, so it must be checked first
+    if let Ok(b) = obj.downcast::<PyBool>() {
+        let val = b
+            .extract::<bool>()
             .map_err(|e| EncodingError::SerializationError(e.to_string()))?;
-        return Ok(Value::Number(num.into()));
+        return Ok(Value::Bool(val));
+    }
+
+    if let Ok(i) = obj.downcast::<PyInt>() {
+        return encode_int(i);
     }
 
     if let Ok(f) = obj.downcast::<PyFloat>() {
         let num = f
             .extract::<f64>()
             .map_err(|e| EncodingError::SerializationError(e.to_string()))?;
-        let json_num = serde_json::Number::from_f64(num)
-            .ok_or_else(|| EncodingError::SerializationError("Invalid float value".to_string()))?;
-        return Ok(Value::Number(json_num));
+        // A `Value` has no NaN token, so `Literal` is left to `stream_json_value`
+        return match serde_json::Number::from_f64(num) {
+            Some(json_num) => Ok(Value::Number(json_num)),
+            None if options.allow_nan == NanMode::Null => Ok(Value::Null),
+            None => Err(non_finite_error(num)),
+        };
     }
 
-    if let Ok(b) = obj.downcast::<PyBool>() {
-        let val = b
-            .extract::<bool>()
-            .map_err(|e| EncodingError::SerializationError(e.to_string()))?;
-        return Ok(Value::Bool(val));
+    // Leaves don't count towards the nesting limit
+    if let Ok(bytes) = obj.downcast::<PyBytes>() {
+        return encode_bytes(bytes);
+    }
+    if is_datetime(obj) {
+        return encode_datetime(obj);
+    }
+    if is_decimal(obj) {
+        return encode_decimal(obj, options);
+    }
+    if is_uuid(obj) {
+        return encode_uuid(obj);
     }
 
-    visited.insert(obj_id);
+    guard.enter_object(obj_id)?;
 
     let result = if let Ok(dict) = obj.downcast::<PyDict>() {
-        encode_dict(dict, visited)
+        encode_dict(dict, guard, options)
     } else if let Ok(list) = obj.downcast::<PyList>() {
-        encode_list(list, visited)
+        encode_list(list, guard, options)
     } else if let Ok(tuple) = obj.downcast::<PyTuple>() {
-        encode_tuple(tuple, visited)
-    } else if let Ok(bytes) = obj.downcast::<PyBytes>() {
-        encode_bytes(bytes)
-    } else if is_datetime(obj) {
-        encode_datetime(obj)
+        encode_tuple(tuple, guard, options)
     } else if has_dict_method(obj) {
-        encode_object_with_dict(obj, visited)
+        encode_object_with_dict(obj, guard, options)
     } else if is_enum(obj) {
-        encode_enum(obj)
+        encode_enum(obj, guard, options)
     } else if is_pydantic_model(obj) {
-        encode_pydantic_model(obj, visited)
+        encode_pydantic_model(obj, guard, options)
     } else {
         // Fallback to string representation
         let str_repr = obj
@@ -131,44 +499,182 @@ fn python_to_json_value(
         Ok(Value::String(str_repr.to_string()))
     };
 
-    visited.remove(&obj_id);
+    guard.exit_object(obj_id);
     result
 }
 
+/// Like `python_to_json_value`, but keeps `bytes` as CBOR byte strings
+fn python_to_cbor_value(
+    obj: &Bound<PyAny>,
+    guard: &mut RecursionGuard,
+    options: &SerializeOptions,
+) -> Result<ciborium::Value> {
+    let obj_id = obj.as_ptr() as usize;
+
+    if let Some(encoded) = custom_encoded(obj, options)? {
+        guard.enter_alias(obj_id)?;
+        let result = python_to_cbor_value(&encoded, guard, options);
+        guard.exit_alias(obj_id);
+        return result;
+    }
+
+    if let Ok(bytes) = obj.downcast::<PyBytes>() {
+        return Ok(ciborium::Value::Bytes(bytes.as_bytes().to_vec()));
+    }
+
+    // CBOR has native NaN and infinities, so `Literal` keeps them as floats
+    if let Some(num) = non_finite_number(obj) {
+        return match options.allow_nan {
+            NanMode::Null => Ok(ciborium::Value::Null),
+            NanMode::Error => Err(non_finite_error(num)),
+            NanMode::Literal => Ok(ciborium::Value::Float(num)),
+        };
+    }
+    if let Ok(float) = obj.downcast::<PyFloat>() {
+        return Ok(ciborium::Value::Float(float.value()));
+    }
+
+    let items: Vec<Bound<PyAny>> = if let Ok(list) = obj.downcast::<PyList>() {
+        list.iter().collect()
+    } else if let Ok(tuple) = obj.downcast::<PyTuple>() {
+        tuple.iter().collect()
+    } else if let Ok(dict) = obj.downcast::<PyDict>() {
+        guard.enter_object(obj_id)?;
+        let mut entries = Vec::with_capacity(dict.len());
+        for (key, value) in dict.iter() {
+            let key = python_to_cbor_value(&key, guard, options)?;
+            entries.push((key, python_to_cbor_value(&value, guard, options)?));
+        }
+        guard.exit_object(obj_id);
+        return Ok(ciborium::Value::Map(entries));
+    } else if let Some(contents) = encoded_contents(obj) {
+        return python_to_cbor_value(&contents, guard, options);
+    } else {
+        return json_to_cbor_value(&python_to_json_value(obj, guard, options)?);
+    };
+
+    guard.enter_object(obj_id)?;
+    let mut array = Vec::with_capacity(items.len());
+    for item in &items {
+        array.push(python_to_cbor_value(item, guard, options)?);
+    }
+    guard.exit_object(obj_id);
+    Ok(ciborium::Value::Array(array))
+}
+
+/// Convert field by field: with arbitrary-precision numbers enabled,
+/// `serde_json::Number` does not serialize as a plain number outside JSON
+fn json_to_cbor_value(value: &Value) -> Result<ciborium::Value> {
+    Ok(match value {
+        Value::Null => ciborium::Value::Null,
+        Value::Bool(b) => ciborium::Value::Bool(*b),
+        Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                ciborium::Value::Integer(i.into())
+            } else if let Some(u) = n.as_u64() {
+                ciborium::Value::Integer(u.into())
+            } else if is_integer_literal(n) {
+                let int = n
+                    .to_string()
+                    .parse::<i128>()
+                    .ok()
+                    .and_then(|i| ciborium::value::Integer::try_from(i).ok())
+                    .ok_or_else(|| {
+                        EncodingError::SerializationError(format!(
+                            "Integer {} is out of range for CBOR",
+                            n
+                        ))
+                    })?;
+                ciborium::Value::Integer(int)
+            } else {
+                ciborium::Value::Float(n.as_f64().unwrap_or(f64::NAN))
+            }
+        }
+        Value::String(s) => ciborium::Value::Text(s.clone()),
+        Value::Array(items) => ciborium::Value::Array(
+            items
+                .iter()
+                .map(json_to_cbor_value)
+                .collect::<Result<_>>()?,
+        ),
+        Value::Object(map) => ciborium::Value::Map(
+            map.iter()
+                .map(|(key, value)| {
+                    Ok((
+                        ciborium::Value::Text(key.clone()),
+                        json_to_cbor_value(value)?,
+                    ))
+                })
+                .collect::<Result<_>>()?,
+        ),
+    })
+}
+
+/// Python ints are unbounded; past `u64` the digits are kept as an
+/// arbitrary-precision JSON number rather than rounded or rejected
+fn encode_int(int: &Bound<PyInt>) -> Result<Value> {
+    if let Ok(num) = int.extract::<i64>() {
+        return Ok(Value::Number(num.into()));
+    }
+    if let Ok(num) = int.extract::<u64>() {
+        return Ok(Value::Number(num.into()));
+    }
+    let digits = int
+        .str()
+        .map_err(|e| EncodingError::SerializationError(e.to_string()))?
+        .to_str()
+        .map_err(|e| EncodingError::SerializationError(e.to_string()))?
+        .to_string();
+    serde_json::from_str::<serde_json::Number>(&digits)
+        .map(Value::Number)
+        .map_err(|e| EncodingError::SerializationError(e.to_string()))
+}
+
+/// Whether `n` was written without a fraction or exponent
+pub(crate) fn is_integer_literal(n: &serde_json::Number) -> bool {
+    !n.to_string().contains(['.', 'e', 'E'])
+}
+
 fn encode_dict(
     dict: &Bound<PyDict>,
-    visited: &mut std::collections::HashSet<usize>,
+    guard: &mut RecursionGuard,
+    options: &SerializeOptions,
 ) -> Result<Value> {
     let mut map = Map::new();
 
     for (key, value) in dict.iter() {
-        let key_str = if let Ok(s) = key.downcast::<PyString>() {
-            s.to_str()
-                .map_err(|e| EncodingError::SerializationError(e.to_string()))?
-                .to_string()
-        } else {
-            key.str()
-                .map_err(|e| EncodingError::SerializationError(e.to_string()))?
-                .to_str()
-                .map_err(|e| EncodingError::SerializationError(e.to_string()))?
-                .to_string()
-        };
-
-        let json_value = python_to_json_value(&value, visited)?;
+        let key_str = dict_key_string(&key)?;
+        let json_value = python_to_json_value(&value, guard, options)?;
         map.insert(key_str, json_value);
     }
 
     Ok(Value::Object(map))
 }
 
+fn dict_key_string(key: &Bound<PyAny>) -> Result<String> {
+    if let Ok(s) = key.downcast::<PyString>() {
+        Ok(s.to_str()
+            .map_err(|e| EncodingError::SerializationError(e.to_string()))?
+            .to_string())
+    } else {
+        Ok(key
+            .str()
+            .map_err(|e| EncodingError::SerializationError(e.to_string()))?
+            .to_str()
+            .map_err(|e| EncodingError::SerializationError(e.to_string()))?
+            .to_string())
+    }
+}
+
 fn encode_list(
     list: &Bound<PyList>,
-    visited: &mut std::collections::HashSet<usize>,
+    guard: &mut RecursionGuard,
+    options: &SerializeOptions,
 ) -> Result<Value> {
     let mut vec = Vec::with_capacity(list.len());
 
     for item in list.iter() {
-        let json_value = python_to_json_value(&item, visited)?;
+        let json_value = python_to_json_value(&item, guard, options)?;
         vec.push(json_value);
     }
 
@@ -177,12 +683,13 @@ fn encode_list(
 
 fn encode_tuple(
     tuple: &Bound<PyTuple>,
-    visited: &mut std::collections::HashSet<usize>,
+    guard: &mut RecursionGuard,
+    options: &SerializeOptions,
 ) -> Result<Value> {
     let mut vec = Vec::with_capacity(tuple.len());
 
     for item in tuple.iter() {
-        let json_value = python_to_json_value(&item, visited)?;
+        let json_value = python_to_json_value(&item, guard, options)?;
         vec.push(json_value);
     }
 
@@ -216,13 +723,44 @@ fn encode_datetime(obj: &Bound<PyAny>) -> Result<Value> {
     })
 }
 
+/// A `Decimal` keeps its exact digits as a JSON number rather than a string
+fn encode_decimal(obj: &Bound<PyAny>, options: &SerializeOptions) -> Result<Value> {
+    if let Some(num) = non_finite_number(obj) {
+        return match options.allow_nan {
+            NanMode::Null => Ok(Value::Null),
+            _ => Err(non_finite_error(num)),
+        };
+    }
+    let text = obj
+        .str()
+        .map_err(|e| EncodingError::SerializationError(e.to_string()))?
+        .to_str()
+        .map_err(|e| EncodingError::SerializationError(e.to_string()))?
+        .to_string();
+    serde_json::from_str::<serde_json::Number>(&text)
+        .map(Value::Number)
+        .map_err(|e| EncodingError::SerializationError(e.to_string()))
+}
+
+/// The hyphenated form, as `str(uuid)` gives
+fn encode_uuid(obj: &Bound<PyAny>) -> Result<Value> {
+    let text = obj
+        .str()
+        .map_err(|e| EncodingError::SerializationError(e.to_string()))?
+        .to_str()
+        .map_err(|e| EncodingError::SerializationError(e.to_string()))?
+        .to_string();
+    Ok(Value::String(text))
+}
+
 fn encode_object_with_dict(
     obj: &Bound<PyAny>,
-    visited: &mut std::collections::HashSet<usize>,
+    guard: &mut RecursionGuard,
+    options: &SerializeOptions,
 ) -> Result<Value> {
     if let Ok(dict) = obj.getattr("__dict__") {
         if let Ok(py_dict) = dict.downcast::<PyDict>() {
-            return encode_dict(py_dict, visited);
+            return encode_dict(py_dict, guard, options);
         }
     }
 
@@ -235,9 +773,13 @@ fn encode_object_with_dict(
     Ok(Value::String(str_repr.to_string()))
 }
 
-fn encode_enum(obj: &Bound<PyAny>) -> Result<Value> {
+fn encode_enum(
+    obj: &Bound<PyAny>,
+    guard: &mut RecursionGuard,
+    options: &SerializeOptions,
+) -> Result<Value> {
     if let Ok(value) = obj.getattr("value") {
-        return python_to_json_value(&value, &mut std::collections::HashSet::new());
+        return python_to_json_value(&value, guard, options);
     }
 
     // Fallback to name
@@ -261,13 +803,14 @@ fn encode_enum(obj: &Bound<PyAny>) -> Result<Value> {
 
 fn encode_pydantic_model(
     obj: &Bound<PyAny>,
-    visited: &mut std::collections::HashSet<usize>,
+    guard: &mut RecursionGuard,
+    options: &SerializeOptions,
 ) -> Result<Value> {
     // Try model_dump() first (Pydantic v2)
     if let Ok(dump_method) = obj.getattr("model_dump") {
         if let Ok(result) = dump_method.call0() {
             if let Ok(dict) = result.downcast::<PyDict>() {
-                return encode_dict(dict, visited);
+                return encode_dict(dict, guard, options);
             }
         }
     }
@@ -276,24 +819,109 @@ fn encode_pydantic_model(
     if let Ok(dict_method) = obj.getattr("dict") {
         if let Ok(result) = dict_method.call0() {
             if let Ok(dict) = result.downcast::<PyDict>() {
-                return encode_dict(dict, visited);
+                return encode_dict(dict, guard, options);
             }
         }
     }
 
     // Fallback to __dict__
-    encode_object_with_dict(obj, visited)
+    encode_object_with_dict(obj, guard, options)
 }
 
+/// `isinstance(obj, module.class)` for any of `classes`; the classes are
+/// imported into `types` on first use rather than on every call
+fn is_instance_of(
+    obj: &Bound<PyAny>,
+    types: &'static GILOnceCell<Vec<Py<PyAny>>>,
+    module: &str,
+    classes: &[&str],
+) -> bool {
+    let py = obj.py();
+    let Ok(types) = types.get_or_try_init(py, || {
+        let module = py.import_bound(module)?;
+        classes
+            .iter()
+            .map(|class| module.getattr(*class).map(Bound::unbind))
+            .collect::<PyResult<Vec<_>>>()
+    }) else {
+        return false;
+    };
+    types
+        .iter()
+        .any(|class| obj.is_instance(class.bind(py)).unwrap_or(false))
+}
+
+/// `datetime.date` (including `datetime.datetime`) or `datetime.time`
 fn is_datetime(obj: &Bound<PyAny>) -> bool {
-    Python::with_gil(|py| {
-        if let Ok(datetime_module) = py.import("datetime") {
-            if let Ok(datetime_class) = datetime_module.getattr("datetime") {
-                return obj.is_instance(&datetime_class).unwrap_or(false);
-            }
-        }
-        false
-    })
+    static TYPES: GILOnceCell<Vec<Py<PyAny>>> = GILOnceCell::new();
+    is_instance_of(obj, &TYPES, "datetime", &["date", "time"])
+}
+
+fn is_decimal(obj: &Bound<PyAny>) -> bool {
+    static TYPES: GILOnceCell<Vec<Py<PyAny>>> = GILOnceCell::new();
+    is_instance_of(obj, &TYPES, "decimal", &["Decimal"])
+}
+
+fn is_uuid(obj: &Bound<PyAny>) -> bool {
+    static TYPES: GILOnceCell<Vec<Py<PyAny>>> = GILOnceCell::new();
+    is_instance_of(obj, &TYPES, "uuid", &["UUID"])
+}
+
+/// The value of a NaN or infinite float or `Decimal`
+fn non_finite_number(obj: &Bound<PyAny>) -> Option<f64> {
+    if let Ok(float) = obj.downcast::<PyFloat>() {
+        let num = float.value();
+        return (!num.is_finite()).then_some(num);
+    }
+    if !is_decimal(obj) {
+        return None;
+    }
+    let finite = obj.call_method0("is_finite").ok()?.extract::<bool>().ok()?;
+    // `float()` refuses signalling NaNs
+    (!finite).then(|| obj.extract::<f64>().unwrap_or(f64::NAN))
+}
+
+/// The Python value that `python_to_json_value` encodes in place of an object
+/// it handles through `__dict__`, an enum value or `model_dump()`, so streamed
+/// and CBOR output see the same fields
+fn encoded_contents<'py>(obj: &Bound<'py, PyAny>) -> Option<Bound<'py, PyAny>> {
+    let is_leaf = obj.is_none()
+        || obj.downcast::<PyString>().is_ok()
+        || obj.downcast::<PyInt>().is_ok()
+        || obj.downcast::<PyFloat>().is_ok()
+        || obj.downcast::<PyBytes>().is_ok()
+        || is_datetime(obj)
+        || is_decimal(obj)
+        || is_uuid(obj);
+    let is_container = obj.downcast::<PyDict>().is_ok()
+        || obj.downcast::<PyList>().is_ok()
+        || obj.downcast::<PyTuple>().is_ok();
+    if is_leaf || is_container {
+        return None;
+    }
+
+    let instance_dict = |obj: &Bound<'py, PyAny>| {
+        obj.getattr("__dict__")
+            .ok()
+            .filter(|dict| dict.downcast::<PyDict>().is_ok())
+    };
+    let dumped = |method: &str| {
+        obj.call_method0(method)
+            .ok()
+            .filter(|dict| dict.downcast::<PyDict>().is_ok())
+    };
+
+    if has_dict_method(obj) {
+        instance_dict(obj)
+    } else if is_enum(obj) {
+        obj.getattr("value").ok()
+    } else if is_pydantic_model(obj) {
+        dumped("model_dump")
+            .or_else(|| dumped("dict"))
+            .or_else(|| instance_dict(obj))
+    } else {
+        None
+    }
 }
 
 fn has_dict_method(obj: &Bound<PyAny>) -> bool {
@@ -321,10 +949,14 @@ pub fn encode_string_fast(s: &str) -> String {
 }
 
 pub fn encode_number_fast(n: f64) -> String {
+    encode_number(n, NanMode::Null).unwrap_or_else(|_| "null".to_string())
+}
+
+pub fn encode_number(n: f64, allow_nan: NanMode) -> Result<String> {
     if n.is_finite() {
-        n.to_string()
+        Ok(n.to_string())
     } else {
-        "null".to_string()
+        allow_nan.non_finite_token(n).map(str::to_string)
     }
 }
 
@@ -342,30 +974,42 @@ mod tests {
         Python::with_gil(|py| {
             // String
             let py_str = PyString::new_bound(py, "hello");
-            let result =
-                python_to_json_value(&py_str.as_any(), &mut std::collections::HashSet::new())
-                    .unwrap();
+            let result = python_to_json_value(
+                &py_str.as_any(),
+                &mut RecursionGuard::default(),
+                &SerializeOptions::default(),
+            )
+            .unwrap();
             assert_eq!(result, Value::String("hello".to_string()));
 
             // Integer
             let py_int = PyInt::new_bound(py, 42);
-            let result =
-                python_to_json_value(&py_int.as_any(), &mut std::collections::HashSet::new())
-                    .unwrap();
+            let result = python_to_json_value(
+                &py_int.as_any(),
+                &mut RecursionGuard::default(),
+                &SerializeOptions::default(),
+            )
+            .unwrap();
             assert_eq!(result, Value::Number(42.into()));
 
             // Boolean
             let py_bool = PyBool::new_bound(py, true);
-            let result =
-                python_to_json_value(&py_bool.as_any(), &mut std::collections::HashSet::new())
-                    .unwrap();
+            let result = python_to_json_value(
+                &py_bool.as_any(),
+                &mut RecursionGuard::default(),
+                &SerializeOptions::default(),
+            )
+            .unwrap();
             assert_eq!(result, Value::Bool(true));
 
             // None
             let py_none = PyNone::get_bound(py);
-            let result =
-                python_to_json_value(&py_none.as_any(), &mut std::collections::HashSet::new())
-                    .unwrap();
+            let result = python_to_json_value(
+                &py_none.as_any(),
+                &mut RecursionGuard::default(),
+                &SerializeOptions::default(),
+            )
+            .unwrap();
             assert_eq!(result, Value::Null);
         });
     }
@@ -377,9 +1021,12 @@ mod tests {
             dict.set_item("name", "John").unwrap();
             dict.set_item("age", 30).unwrap();
 
-            let result =
-                python_to_json_value(&dict.as_any(), &mut std::collections::HashSet::new())
-                    .unwrap();
+            let result = python_to_json_value(
+                &dict.as_any(),
+                &mut RecursionGuard::default(),
+                &SerializeOptions::default(),
+            )
+            .unwrap();
 
             if let Value::Object(map) = result {
                 assert_eq!(map.get("name"), Some(&Value::String("John".to_string())));
@@ -395,9 +1042,12 @@ mod tests {
         Python::with_gil(|py| {
             let list = PyList::new_bound(py, &[1, 2, 3]);
 
-            let result =
-                python_to_json_value(&list.as_any(), &mut std::collections::HashSet::new())
-                    .unwrap();
+            let result = python_to_json_value(
+                &list.as_any(),
+                &mut RecursionGuard::default(),
+                &SerializeOptions::default(),
+            )
+            .unwrap();
 
             if let Value::Array(arr) = result {
                 assert_eq!(arr.len(), 3);
@@ -409,4 +1059,472 @@ mod tests {
             }
         });
     }
+
+    #[test]
+    fn test_response_format_from_content_type() {
+        assert_eq!(
+            ResponseFormat::from_content_type(None).unwrap(),
+            ResponseFormat::Json
+        );
+        assert_eq!(
+            ResponseFormat::from_content_type(Some("application/vnd.api+json; charset=utf-8"))
+                .unwrap(),
+            ResponseFormat::Json
+        );
+        assert_eq!(
+            ResponseFormat::from_content_type(Some("Text/Plain")).unwrap(),
+            ResponseFormat::Text
+        );
+        assert_eq!(
+            ResponseFormat::from_content_type(Some("application/x-ndjson")).unwrap(),
+            ResponseFormat::Ndjson
+        );
+        assert!(matches!(
+            ResponseFormat::from_content_type(Some("image/png")),
+            Err(EncodingError::UnsupportedType(_))
+        ));
+
+        Python::with_gil(|py| {
+            let dict = PyDict::new_bound(py);
+            dict.set_item("ok", true).unwrap();
+            let body = serialize_response(dict.as_any(), Some("application/problem+json")).unwrap();
+            assert_eq!(body, br#"{"ok":true}"#.to_vec());
+
+            let list = PyList::new_bound(py, [1, 2]);
+            let body = serialize_response(list.as_any(), Some("application/x-ndjson")).unwrap();
+            assert_eq!(body, b"1\n2\n".to_vec());
+        });
+    }
+
+    #[test]
+    fn test_ndjson_rows() {
+        Python::with_gil(|py| {
+            let rows = PyList::empty_bound(py);
+            for id in 1..=3 {
+                let row = PyDict::new_bound(py);
+                row.set_item("id", id).unwrap();
+                rows.append(row).unwrap();
+            }
+            let body = serialize_response(rows.as_any(), Some("application/x-ndjson")).unwrap();
+            assert_eq!(body, b"{\"id\":1}\n{\"id\":2}\n{\"id\":3}\n".to_vec());
+            assert!(!body.starts_with(b"[") && !body.ends_with(b"]\n"));
+
+            // Any iterable works, one item per line
+            let gen = py
+                .eval_bound("(n * n for n in range(3))", None, None)
+                .unwrap();
+            let body = serialize_response(&gen, Some("application/x-ndjson")).unwrap();
+            assert_eq!(body, b"0\n1\n4\n".to_vec());
+
+            for data in [
+                5.to_object(py).into_bound(py),
+                "abc".to_object(py).into_bound(py),
+                PyDict::new_bound(py).into_any(),
+            ] {
+                assert!(matches!(
+                    serialize_response(&data, Some("application/x-ndjson")),
+                    Err(EncodingError::UnsupportedType(_))
+                ));
+            }
+        });
+    }
+
+    #[test]
+    fn test_cbor_round_trip() {
+        Python::with_gil(|py| {
+            let inner = PyDict::new_bound(py);
+            inner.set_item("name", "Ada").unwrap();
+            inner.set_item("scores", vec![1.5, 2.0]).unwrap();
+            let data = PyDict::new_bound(py);
+            data.set_item("user", inner).unwrap();
+            data.set_item("blob", PyBytes::new_bound(py, &[0, 1, 254]))
+                .unwrap();
+            data.set_item("count", 3).unwrap();
+
+            let body = serialize_response(data.as_any(), Some("application/cbor")).unwrap();
+            let value: ciborium::Value = ciborium::from_reader(body.as_slice()).unwrap();
+            let entries = value.as_map().unwrap();
+            let blob = entries
+                .iter()
+                .find(|(k, _)| k.as_text() == Some("blob"))
+                .map(|(_, v)| v)
+                .unwrap();
+            assert_eq!(blob.as_bytes(), Some(&vec![0, 1, 254]));
+
+            let decoded =
+                crate::serialization::decoders::deserialize_request(&body, "application/cbor")
+                    .unwrap();
+            assert!(decoded.bind(py).eq(data.as_any()).unwrap());
+        });
+    }
+
+    #[test]
+    fn test_encode_to_writer_matches_jsonable_encoder() {
+        Python::with_gil(|py| {
+            let rows = PyList::empty_bound(py);
+            for i in 0..5_000 {
+                let row = PyDict::new_bound(py);
+                row.set_item("z", i).unwrap();
+                row.set_item("a", format!("row \"{}\"", i)).unwrap();
+                row.set_item(7, (true, PyNone::get_bound(py), 1.5)).unwrap();
+                rows.append(row).unwrap();
+            }
+            let data = PyDict::new_bound(py);
+            data.set_item("rows", rows).unwrap();
+            data.set_item("blob", PyBytes::new_bound(py, b"raw"))
+                .unwrap();
+
+            let buffer = py
+                .import_bound("io")
+                .unwrap()
+                .call_method0("BytesIO")
+                .unwrap();
+            encode_to_writer(data.as_any(), &buffer).unwrap();
+            let written: Vec<u8> = buffer.call_method0("getvalue").unwrap().extract().unwrap();
+
+            // Large enough to have been written in several chunks
+            assert!(written.len() > 2 * WRITER_CHUNK_SIZE);
+            assert_eq!(
+                written,
+                jsonable_encoder(data.as_any()).unwrap().into_bytes()
+            );
+
+            let cyclic = PyList::empty_bound(py);
+            cyclic.append(&cyclic).unwrap();
+            let buffer = py
+                .import_bound("io")
+                .unwrap()
+                .call_method0("BytesIO")
+                .unwrap();
+            assert!(matches!(
+                encode_to_writer(cyclic.as_any(), &buffer),
+                Err(EncodingError::CircularReference)
+            ));
+        });
+    }
+
+    #[test]
+    fn test_depth_limit_matches_decoder() {
+        Python::with_gil(|py| {
+            let two_deep = PyList::new_bound(py, [PyList::new_bound(py, [1])]);
+            let three_deep = PyList::new_bound(py, [&two_deep]);
+
+            assert!(python_to_json_value(
+                two_deep.as_any(),
+                &mut RecursionGuard::new(2),
+                &SerializeOptions::default()
+            )
+            .is_ok());
+            assert!(matches!(
+                python_to_json_value(
+                    three_deep.as_any(),
+                    &mut RecursionGuard::new(2),
+                    &SerializeOptions::default()
+                ),
+                Err(EncodingError::LimitExceeded(_))
+            ));
+
+            // The decoder accepts and rejects exactly the same shapes
+            let limits = crate::serialization::decoders::RequestLimits {
+                max_depth: 2,
+                ..Default::default()
+            };
+            let decode = |body: &[u8]| {
+                crate::serialization::decoders::deserialize_request_with_limits(
+                    body,
+                    "application/json",
+                    limits,
+                )
+            };
+            assert!(decode(b"[[1]]").is_ok());
+            assert!(matches!(
+                decode(b"[[[1]]]"),
+                Err(crate::serialization::decoders::DecodingError::LimitExceeded(_))
+            ));
+        });
+    }
+
+    #[test]
+    fn test_large_ints_keep_their_digits() {
+        Python::with_gil(|py| {
+            let data = py
+                .eval_bound(
+                    "{'big': 2**70, 'neg': -(2**70), 'u64': 2**64 - 1}",
+                    None,
+                    None,
+                )
+                .unwrap();
+            let json = jsonable_encoder(&data).unwrap();
+            assert!(json.contains("\"big\":1180591620717411303424"));
+            assert!(json.contains("\"neg\":-1180591620717411303424"));
+            assert!(json.contains("\"u64\":18446744073709551615"));
+
+            let decoded = crate::serialization::decoders::deserialize_request(
+                json.as_bytes(),
+                "application/json",
+            )
+            .unwrap();
+            assert!(decoded.bind(py).eq(&data).unwrap());
+
+            // CBOR integers stop at 64 bits of magnitude
+            let u64_max = py.eval_bound("2**64 - 1", None, None).unwrap();
+            assert!(serialize_response(&u64_max, Some("application/cbor")).is_ok());
+            assert!(
+                serialize_response(&data.get_item("big").unwrap(), Some("application/cbor"))
+                    .is_err()
+            );
+        });
+    }
+
+    #[test]
+    fn test_nan_modes() {
+        Python::with_gil(|py| {
+            let data = py
+                .eval_bound(
+                    "{'limits': [float('inf'), -float('inf'), 1.5], 'ratio': float('nan')}",
+                    None,
+                    None,
+                )
+                .unwrap();
+            let encode = |allow_nan: NanMode, content_type: &str| {
+                serialize_response_with_options(
+                    &data,
+                    Some(content_type),
+                    &SerializeOptions {
+                        allow_nan,
+                        ..SerializeOptions::default()
+                    },
+                )
+            };
+
+            let body = encode(NanMode::Null, "application/json").unwrap();
+            assert_eq!(body, br#"{"limits":[null,null,1.5],"ratio":null}"#);
+            assert_eq!(serialize_response(&data, None).unwrap(), body);
+            assert_eq!(jsonable_encoder(&data).unwrap().as_bytes(), body);
+
+            assert!(matches!(
+                encode(NanMode::Error, "application/json"),
+                Err(EncodingError::SerializationError(_))
+            ));
+            assert!(encode(NanMode::Error, "application/cbor").is_err());
+
+            let body = encode(NanMode::Literal, "application/json").unwrap();
+            assert_eq!(body, br#"{"limits":[Infinity,-Infinity,1.5],"ratio":NaN}"#);
+            let body = encode(NanMode::Literal, "application/cbor").unwrap();
+            let value: ciborium::Value = ciborium::from_reader(body.as_slice()).unwrap();
+            let ratio = value
+                .as_map()
+                .unwrap()
+                .iter()
+                .find(|(k, _)| k.as_text() == Some("ratio"));
+            assert!(ratio.unwrap().1.as_float().unwrap().is_nan());
+
+            assert_eq!(encode_number(f64::NAN, NanMode::Literal).unwrap(), "NaN");
+            assert_eq!(encode_number_fast(f64::INFINITY), "null");
+            assert_eq!("literal".parse::<NanMode>().unwrap(), NanMode::Literal);
+            assert!("allow".parse::<NanMode>().is_err());
+        });
+    }
+
+    #[test]
+    fn test_nan_mode_reaches_object_fields() {
+        Python::with_gil(|py| {
+            let reading = py
+                .eval_bound(
+                    "__import__('types').SimpleNamespace(value=float('nan'))",
+                    None,
+                    None,
+                )
+                .unwrap();
+            let options = SerializeOptions {
+                allow_nan: NanMode::Literal,
+                ..SerializeOptions::default()
+            };
+            let body =
+                serialize_response_with_options(&reading, Some("application/json"), &options)
+                    .unwrap();
+            assert_eq!(body, br#"{"value":NaN}"#);
+            assert_eq!(
+                serialize_response(&reading, Some("application/json")).unwrap(),
+                br#"{"value":null}"#
+            );
+        });
+    }
+
+    #[test]
+    fn test_indent_and_sort_keys() {
+        Python::with_gil(|py| {
+            let data = py
+                .eval_bound("{'b': [1, {'d': None, 'c': []}], 'a': {}}", None, None)
+                .unwrap();
+            let encode = |indent: Option<usize>, sort_keys: bool| {
+                let options = SerializeOptions {
+                    indent,
+                    sort_keys,
+                    ..SerializeOptions::default()
+                };
+                let body = serialize_response_with_options(&data, None, &options).unwrap();
+                String::from_utf8(body).unwrap()
+            };
+
+            assert_eq!(encode(None, false), r#"{"b":[1,{"d":null,"c":[]}],"a":{}}"#);
+            assert_eq!(encode(None, true), r#"{"a":{},"b":[1,{"c":[],"d":null}]}"#);
+            let expected = r#"{
+  "a": {},
+  "b": [
+    1,
+    {
+      "c": [],
+      "d": null
+    }
+  ]
+}"#;
+            assert_eq!(encode(Some(2), true), expected);
+
+            // Same layout as Python's json.dumps
+            let json = py.import_bound("json").unwrap();
+            let kwargs = PyDict::new_bound(py);
+            kwargs.set_item("indent", 4).unwrap();
+            let expected: String = json
+                .call_method("dumps", (&data,), Some(&kwargs))
+                .unwrap()
+                .extract()
+                .unwrap();
+            assert_eq!(encode(Some(4), false), expected);
+
+            // NDJSON keeps one item per line
+            let rows = py.eval_bound("[{'y': 1, 'x': 2}]", None, None).unwrap();
+            let options = SerializeOptions {
+                indent: Some(2),
+                sort_keys: true,
+                ..SerializeOptions::default()
+            };
+            let body =
+                serialize_response_with_options(&rows, Some("application/x-ndjson"), &options)
+                    .unwrap();
+            assert_eq!(body, b"{\"x\":2,\"y\":1}\n");
+        });
+    }
+
+    #[test]
+    fn test_encode_date_time_decimal_and_uuid() {
+        Python::with_gil(|py| {
+            let eval = |code: &str| py.eval_bound(code, None, None).unwrap();
+            let encode = |obj: &Bound<PyAny>| jsonable_encoder(obj).unwrap();
+            let date = eval("__import__('datetime').date(2024, 2, 29)");
+            assert_eq!(encode(&date), r#""2024-02-29""#);
+            let time = eval("__import__('datetime').time(13, 5, 9, 250)");
+            assert_eq!(encode(&time), r#""13:05:09.000250""#);
+            let moment = eval("__import__('datetime').datetime(2024, 2, 29, 13, 5)");
+            assert_eq!(encode(&moment), r#""2024-02-29T13:05:00""#);
+
+            // Decimals stay numbers with every digit and trailing zero
+            let price = eval("__import__('decimal').Decimal('19.90')");
+            assert_eq!(encode(&price), "19.90");
+            let precise = eval("__import__('decimal').Decimal('0.1000000000000000000000000001')");
+            assert_eq!(encode(&precise), "0.1000000000000000000000000001");
+            let nan = eval("__import__('decimal').Decimal('NaN')");
+            assert_eq!(encode(&nan), "null");
+            let options = SerializeOptions {
+                allow_nan: NanMode::Literal,
+                ..SerializeOptions::default()
+            };
+            assert_eq!(
+                serialize_response_with_options(&nan, None, &options).unwrap(),
+                b"NaN"
+            );
+
+            let id = eval("__import__('uuid').UUID('12345678123456781234567812345678')");
+            assert_eq!(encode(&id), r#""12345678-1234-5678-1234-567812345678""#);
+
+            // The streamed encoding agrees
+            let data = PyDict::new_bound(py);
+            data.set_item("price", &price).unwrap();
+            data.set_item("id", &id).unwrap();
+            data.set_item("on", &date).unwrap();
+            let body = serialize_response(data.as_any(), None).unwrap();
+            assert_eq!(
+                body,
+                br#"{"price":19.90,"id":"12345678-1234-5678-1234-567812345678","on":"2024-02-29"}"#
+            );
+        });
+    }
+
+    #[test]
+    fn test_custom_encoder() {
+        Python::with_gil(|py| {
+            let locals = PyDict::new_bound(py);
+            py.run_bound(
+                r#"
+class Point:
+    def __init__(self, x, y):
+        self.x, self.y = x, y
+
+class Point3(Point):
+    pass
+
+class Opaque:
+    __slots__ = ()
+
+encoders = {Point: lambda p: {"coords": [p.x, p.y]}, Opaque: lambda o: o}
+data = {"a": Point(1, 2), "b": [Point3(3, 4)]}
+"#,
+                None,
+                Some(&locals),
+            )
+            .unwrap();
+            let get = |name: &str| locals.get_item(name).unwrap().unwrap();
+            let options = SerializeOptions {
+                custom_encoder: Some(get("encoders").downcast_into::<PyDict>().unwrap()),
+                ..SerializeOptions::default()
+            };
+            let data = get("data");
+
+            // Subclasses match through isinstance, and the result is encoded in turn
+            let expected = r#"{"a":{"coords":[1,2]},"b":[{"coords":[3,4]}]}"#;
+            assert_eq!(
+                jsonable_encoder_with_options(&data, &options).unwrap(),
+                expected
+            );
+            assert_eq!(
+                serialize_response_with_options(&data, None, &options).unwrap(),
+                expected.as_bytes()
+            );
+            assert_eq!(
+                jsonable_encoder(&data).unwrap(),
+                r#"{"a":{"x":1,"y":2},"b":[{"x":3,"y":4}]}"#
+            );
+
+            // The encoded value is indented as if it had been there all along
+            let indented = SerializeOptions {
+                indent: Some(2),
+                ..options.clone()
+            };
+            let point = py.eval_bound("Point(1, 2)", None, Some(&locals)).unwrap();
+            assert_eq!(
+                String::from_utf8(
+                    serialize_response_with_options(&point, None, &indented).unwrap()
+                )
+                .unwrap(),
+                "{\n  \"coords\": [\n    1,\n    2\n  ]\n}"
+            );
+            let nested = py
+                .eval_bound("{'p': Point(1, 2)}", None, Some(&locals))
+                .unwrap();
+            assert_eq!(
+                String::from_utf8(
+                    serialize_response_with_options(&nested, None, &indented).unwrap()
+                )
+                .unwrap(),
+                "{\n  \"p\": {\n    \"coords\": [\n      1,\n      2\n    ]\n  }\n}"
+            );
+
+            // An encoder returning its own input cannot recurse forever
+            let opaque = py.eval_bound("Opaque()", None, Some(&locals)).unwrap();
+            assert!(matches!(
+                jsonable_encoder_with_options(&opaque, &options),
+                Err(EncodingError::CircularReference)
+            ));
+        });
+    }
 }
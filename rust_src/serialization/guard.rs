@@ -0,0 +1,164 @@
+use std::collections::HashSet;
+use thiserror::Error;
+
+/// Nesting limit shared by request decoding and response encoding
+pub const DEFAULT_MAX_DEPTH: usize = 64;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum RecursionError {
+    #[error("nesting deeper than {limit}")]
+    TooDeep { limit: usize },
+    #[error("Circular reference detected")]
+    CircularReference,
+}
+
+/// Tracks container nesting while walking a value, and for Python objects the
+/// ids currently being visited so cycles are caught before the depth limit.
+///
+/// An error leaves the guard mid-walk; start a fresh guard for the next value.
+#[derive(Debug)]
+pub struct RecursionGuard {
+    max_depth: usize,
+    depth: usize,
+    aliases: usize,
+    active: HashSet<usize>,
+}
+
+impl Default for RecursionGuard {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_DEPTH)
+    }
+}
+
+impl RecursionGuard {
+    pub fn new(max_depth: usize) -> Self {
+        Self {
+            max_depth,
+            depth: 0,
+            aliases: 0,
+            active: HashSet::new(),
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Descend into a container; pair with `exit`
+    pub fn enter(&mut self) -> Result<(), RecursionError> {
+        if self.depth >= self.max_depth {
+            return Err(RecursionError::TooDeep {
+                limit: self.max_depth,
+            });
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    pub fn exit(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+    }
+
+    /// Descend into the object with identity `id`; pair with `exit_object`
+    pub fn enter_object(&mut self, id: usize) -> Result<(), RecursionError> {
+        if self.active.contains(&id) {
+            return Err(RecursionError::CircularReference);
+        }
+        self.enter()?;
+        self.active.insert(id);
+        Ok(())
+    }
+
+    pub fn exit_object(&mut self, id: usize) {
+        self.active.remove(&id);
+        self.exit();
+    }
+
+    /// Replace the object with identity `id` by a stand-in, such as a custom
+    /// encoder's result, without adding a nesting level; cycles are still
+    /// caught and up to `max_depth` stand-ins may be active. Pair with `exit_alias`
+    pub fn enter_alias(&mut self, id: usize) -> Result<(), RecursionError> {
+        if self.active.contains(&id) {
+            return Err(RecursionError::CircularReference);
+        }
+        if self.aliases >= self.max_depth {
+            return Err(RecursionError::TooDeep {
+                limit: self.max_depth,
+            });
+        }
+        self.aliases += 1;
+        self.active.insert(id);
+        Ok(())
+    }
+
+    pub fn exit_alias(&mut self, id: usize) {
+        self.active.remove(&id);
+        self.aliases = self.aliases.saturating_sub(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_depth_limit_boundary() {
+        let mut guard = RecursionGuard::new(2);
+        guard.enter().unwrap();
+        guard.enter().unwrap();
+        assert_eq!(guard.depth(), 2);
+        assert_eq!(guard.enter(), Err(RecursionError::TooDeep { limit: 2 }));
+
+        guard.exit();
+        guard.enter().unwrap();
+        guard.exit();
+        guard.exit();
+        assert_eq!(guard.depth(), 0);
+    }
+
+    #[test]
+    fn test_cycle_detection() {
+        let mut guard = RecursionGuard::default();
+        assert_eq!(guard.max_depth(), DEFAULT_MAX_DEPTH);
+
+        guard.enter_object(1).unwrap();
+        guard.enter_object(2).unwrap();
+        assert_eq!(
+            guard.enter_object(1),
+            Err(RecursionError::CircularReference)
+        );
+
+        // Siblings may share an object once the first visit has finished
+        guard.exit_object(2);
+        guard.enter_object(2).unwrap();
+        guard.exit_object(2);
+        guard.exit_object(1);
+        assert_eq!(guard.depth(), 0);
+    }
+
+    #[test]
+    fn test_alias_keeps_depth() {
+        let mut guard = RecursionGuard::new(2);
+        guard.enter_object(1).unwrap();
+        guard.enter_alias(2).unwrap();
+        assert_eq!(guard.depth(), 1);
+        assert_eq!(guard.enter_alias(1), Err(RecursionError::CircularReference));
+        guard.enter_object(3).unwrap();
+        guard.enter_alias(4).unwrap();
+        assert_eq!(
+            guard.enter_alias(5),
+            Err(RecursionError::TooDeep { limit: 2 })
+        );
+
+        guard.exit_alias(4);
+        guard.exit_object(3);
+        guard.exit_alias(2);
+        guard.exit_object(1);
+        assert_eq!(guard.depth(), 0);
+        guard.enter_alias(2).unwrap();
+    }
+}
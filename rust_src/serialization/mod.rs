@@ -1,5 +1,9 @@
 pub mod decoders;
+pub mod diff;
 pub mod encoders;
+pub mod guard;
 
 pub use decoders::*;
+pub use diff::*;
 pub use encoders::*;
+pub use guard::*;
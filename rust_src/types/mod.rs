@@ -1,10 +1,18 @@
 pub mod models;
 
-use crate::core::Route;
-use crate::params::ValidationResult as RustValidationResult;
+use crate::core::{MatchStatus, Route, RouteTree};
+use crate::params::{
+    CompiledSchema as RustCompiledSchema, ValidationResult as RustValidationResult,
+};
+use crate::security::SessionStore as RustSessionStore;
+use crate::serialization::{jsonable_encoder, value_to_python};
+use crate::utils::{py_dict_to_multimap, ResponseCache};
+use indexmap::IndexMap;
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
 use serde_json::Value;
 use std::collections::HashMap;
+use std::sync::Arc;
 
 #[pyclass]
 #[derive(Debug, Clone)]
@@ -17,44 +25,52 @@ pub struct FastApiRoute {
     pub name: Option<String>,
     #[pyo3(get)]
     pub path_format: String,
+    #[pyo3(get)]
+    pub consumes: Vec<String>,
     pub(crate) inner: Route,
 }
 
 #[pymethods]
 impl FastApiRoute {
     #[new]
-    pub fn new(path: String, methods: Vec<String>, name: Option<String>) -> PyResult<Self> {
+    #[pyo3(signature = (path, methods, name = None, consumes = None))]
+    pub fn new(
+        path: String,
+        methods: Vec<String>,
+        name: Option<String>,
+        consumes: Option<Vec<String>>,
+    ) -> PyResult<Self> {
         let route = Route::new(&path, methods.clone(), name.clone())
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?
+            .with_consumes(consumes.unwrap_or_default());
 
         Ok(FastApiRoute {
             path: path.clone(),
             methods,
             name,
             path_format: route.path_format.clone(),
+            consumes: route.consumes.clone(),
             inner: route,
         })
     }
 
+    /// Raise if a request body of this Content-Type must be rejected with 415
+    #[pyo3(signature = (content_type = None))]
+    pub fn check_content_type(&self, content_type: Option<&str>) -> PyResult<()> {
+        self.inner
+            .check_content_type(content_type)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
+    }
+
     pub fn matches(&self, path: &str, method: &str) -> bool {
         if !self.methods.iter().any(|m| m == method) {
             return false;
         }
-        self.inner.regex.is_match(path)
+        self.inner.match_path(path).is_some()
     }
 
     pub fn extract_params(&self, path: &str) -> Option<HashMap<String, String>> {
-        if let Some(captures) = self.inner.regex.captures(path) {
-            let mut params = HashMap::new();
-            for (i, param_name) in self.inner.param_names.iter().enumerate() {
-                if let Some(capture) = captures.get(i + 1) {
-                    params.insert(param_name.clone(), capture.as_str().to_string());
-                }
-            }
-            Some(params)
-        } else {
-            None
-        }
+        self.inner.match_path(path)
     }
 
     fn __repr__(&self) -> String {
@@ -72,6 +88,7 @@ impl FastApiRoute {
             methods: route.methods.iter().cloned().collect(),
             name: route.name.clone(),
             path_format: route.path_format.clone(),
+            consumes: route.consumes.clone(),
             inner: route,
         }
     }
@@ -81,6 +98,142 @@ impl FastApiRoute {
     }
 }
 
+#[pyclass]
+#[derive(Debug, Clone)]
+pub struct RouteMatchResult {
+    #[pyo3(get)]
+    pub status_code: u16,
+    #[pyo3(get)]
+    pub route_index: Option<usize>,
+    #[pyo3(get)]
+    pub params: HashMap<String, String>,
+    #[pyo3(get)]
+    pub allowed_methods: Vec<String>,
+    /// Canonical path for a 307 when only the trailing slash differs
+    #[pyo3(get)]
+    pub redirect_to: Option<String>,
+}
+
+#[pymethods]
+impl RouteMatchResult {
+    pub fn is_match(&self) -> bool {
+        self.route_index.is_some()
+    }
+
+    /// Value for the `Allow` header of a 405 response
+    pub fn allow_header(&self) -> Option<String> {
+        if self.allowed_methods.is_empty() {
+            None
+        } else {
+            Some(self.allowed_methods.join(", "))
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "RouteMatchResult(status_code={}, route_index={:?}, allowed_methods={:?})",
+            self.status_code, self.route_index, self.allowed_methods
+        )
+    }
+}
+
+impl RouteMatchResult {
+    pub fn from(status: MatchStatus) -> Self {
+        match status {
+            MatchStatus::Matched(index, params) => RouteMatchResult {
+                status_code: 200,
+                route_index: Some(index),
+                params,
+                allowed_methods: Vec::new(),
+                redirect_to: None,
+            },
+            MatchStatus::MethodNotAllowed(allowed_methods) => RouteMatchResult {
+                status_code: 405,
+                route_index: None,
+                params: HashMap::new(),
+                allowed_methods,
+                redirect_to: None,
+            },
+            MatchStatus::RedirectTo(location) => RouteMatchResult {
+                status_code: 307,
+                route_index: None,
+                params: HashMap::new(),
+                allowed_methods: Vec::new(),
+                redirect_to: Some(location),
+            },
+            MatchStatus::NotFound => RouteMatchResult {
+                status_code: 404,
+                route_index: None,
+                params: HashMap::new(),
+                allowed_methods: Vec::new(),
+                redirect_to: None,
+            },
+        }
+    }
+}
+
+/// Compiled route table kept alive across requests
+#[pyclass]
+#[derive(Default)]
+pub struct RouteTreeWrapper {
+    pub(crate) inner: RouteTree,
+    /// Shadowing diagnostics from `create_route_tree`
+    #[pyo3(get)]
+    pub warnings: Vec<String>,
+}
+
+#[pymethods]
+impl RouteTreeWrapper {
+    #[new]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `route`, returning the index reported by `match_route`
+    pub fn add_route(&mut self, route: PyRef<FastApiRoute>) -> usize {
+        self.inner.add_route(route.to_rust_route())
+    }
+
+    pub fn match_route(
+        &self,
+        path: &str,
+        method: &str,
+    ) -> Option<(usize, HashMap<String, String>)> {
+        self.inner.match_route(path, method)
+    }
+
+    pub fn match_detailed(&self, path: &str, method: &str) -> RouteMatchResult {
+        RouteMatchResult::from(self.inner.match_detailed(path, method))
+    }
+
+    /// Match `path`, returning a dict tagged by `"status"`: "matched",
+    /// "method_not_allowed" or "not_found"
+    pub fn match_with_status(&self, py: Python, path: &str, method: &str) -> PyResult<Py<PyDict>> {
+        let result = PyDict::new_bound(py);
+        match self.inner.match_with_status(path, method) {
+            MatchStatus::Matched(index, params) => {
+                result.set_item("status", "matched")?;
+                result.set_item("route_index", index)?;
+                result.set_item("params", params)?;
+            }
+            MatchStatus::MethodNotAllowed(allowed) => {
+                result.set_item("status", "method_not_allowed")?;
+                result.set_item("allowed_methods", allowed)?;
+            }
+            _ => result.set_item("status", "not_found")?,
+        }
+        Ok(result.unbind())
+    }
+
+    pub fn route_count(&self) -> usize {
+        self.inner.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("RouteTreeWrapper(routes={})", self.inner.len())
+    }
+}
+
 #[pyclass]
 #[derive(Debug, Clone)]
 pub struct ValidationResult {
@@ -89,13 +242,13 @@ pub struct ValidationResult {
     #[pyo3(get)]
     pub errors: Vec<String>,
     #[pyo3(get)]
-    pub validated_data: HashMap<String, Value>,
+    pub validated_data: IndexMap<String, Value>,
 }
 
 #[pymethods]
 impl ValidationResult {
     #[new]
-    pub fn new(valid: bool, errors: Vec<String>, validated_data: HashMap<String, Value>) -> Self {
+    pub fn new(valid: bool, errors: Vec<String>, validated_data: IndexMap<String, Value>) -> Self {
         Self {
             valid,
             errors,
@@ -111,7 +264,7 @@ impl ValidationResult {
         self.errors.clone()
     }
 
-    pub fn get_data(&self) -> HashMap<String, Value> {
+    pub fn get_data(&self) -> IndexMap<String, Value> {
         self.validated_data.clone()
     }
 
@@ -136,6 +289,383 @@ impl ValidationResult {
     }
 }
 
+/// Schema parsed once at startup so each request only pays for validation
+#[pyclass]
+#[derive(Debug, Clone)]
+pub struct CompiledSchema {
+    pub(crate) inner: RustCompiledSchema,
+}
+
+#[pymethods]
+impl CompiledSchema {
+    #[new]
+    pub fn new(schema: &Bound<PyDict>) -> PyResult<Self> {
+        let json = jsonable_encoder(schema.as_any())
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+        let schema_map: IndexMap<String, Value> = serde_json::from_str(&json)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+        let inner = RustCompiledSchema::new(schema_map)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+
+        Ok(CompiledSchema { inner })
+    }
+
+    /// List values supply every value of a repeated parameter
+    pub fn validate(&self, params: &Bound<PyDict>) -> PyResult<ValidationResult> {
+        let param_map = py_dict_to_multimap(params)?;
+        let result = self
+            .inner
+            .validate_multi(param_map)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+        Ok(ValidationResult::from(result))
+    }
+
+    fn __repr__(&self) -> String {
+        let mut names: Vec<&str> = self
+            .inner
+            .schemas()
+            .iter()
+            .map(|s| s.name.as_str())
+            .collect();
+        names.sort_unstable();
+        format!("CompiledSchema(params={:?})", names)
+    }
+}
+
+/// Server-side session storage for single-process deployments
+#[pyclass]
+#[derive(Debug, Default)]
+pub struct SessionStore {
+    pub(crate) inner: RustSessionStore,
+}
+
+#[pymethods]
+impl SessionStore {
+    #[new]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn create(&self, data: &Bound<PyAny>, ttl: i64) -> PyResult<String> {
+        let json = jsonable_encoder(data)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+        let value: Value = serde_json::from_str(&json)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+        self.inner
+            .create(value, ttl)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
+    }
+
+    pub fn get(&self, session_id: &str) -> PyResult<Option<Py<PyAny>>> {
+        Python::with_gil(|py| {
+            self.inner
+                .get(session_id)
+                .map(|value| {
+                    value_to_python(&value, py)
+                        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
+                })
+                .transpose()
+        })
+    }
+
+    pub fn touch(&self, session_id: &str) -> bool {
+        self.inner.touch(session_id)
+    }
+
+    pub fn destroy(&self, session_id: &str) -> bool {
+        self.inner.destroy(session_id)
+    }
+
+    pub fn purge_expired(&self) -> usize {
+        self.inner.purge_expired()
+    }
+
+    fn __len__(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+/// In-memory LRU response cache for single-process deployments
+#[pyclass]
+#[derive(Debug)]
+pub struct ResponseCacheWrapper {
+    pub(crate) inner: ResponseCache,
+}
+
+#[pymethods]
+impl ResponseCacheWrapper {
+    #[new]
+    #[pyo3(signature = (max_entries = 1024))]
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            inner: ResponseCache::new(max_entries),
+        }
+    }
+
+    pub fn get(&mut self, key: &str) -> PyResult<Option<Py<PyAny>>> {
+        Python::with_gil(|py| {
+            self.inner
+                .get(key)
+                .map(|value| {
+                    value_to_python(value, py)
+                        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
+                })
+                .transpose()
+        })
+    }
+
+    #[pyo3(signature = (key, value, ttl = None))]
+    pub fn put(&mut self, key: String, value: &Bound<PyAny>, ttl: Option<i64>) -> PyResult<()> {
+        let json = jsonable_encoder(value)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+        let value: Value = serde_json::from_str(&json)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+        self.inner
+            .put(key, value, ttl)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
+    }
+
+    pub fn remove(&mut self, key: &str) -> bool {
+        self.inner.remove(key)
+    }
+
+    pub fn purge_expired(&mut self) -> usize {
+        self.inner.purge_expired()
+    }
+
+    fn __len__(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+#[derive(Debug)]
+enum Limiter {
+    Window {
+        algorithm: RateLimitAlgorithm,
+        limit: u32,
+        window_seconds: u32,
+        states: HashMap<String, RateLimitState<SharedClock>>,
+    },
+    TokenBucket {
+        capacity: f64,
+        refill_per_second: f64,
+        buckets: HashMap<String, TokenBucket<SharedClock>>,
+    },
+}
+
+/// Per-key rate limiting with a fixed or sliding window, or a token bucket.
+///
+/// Per-key state lives in a plain `HashMap`, so calls must hold the GIL,
+/// as they do when made from Python.
+#[pyclass]
+#[derive(Debug)]
+pub struct RateLimiterWrapper {
+    limiter: Limiter,
+    clock: SharedClock,
+}
+
+impl RateLimiterWrapper {
+    /// Like `new`, with every key's limiter reading the time from `clock`
+    pub fn new_with_clock(
+        limit: u32,
+        window_seconds: u32,
+        algorithm: &str,
+        clock: SharedClock,
+    ) -> PyResult<Self> {
+        let algorithm = algorithm
+            .parse()
+            .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
+        Ok(Self {
+            limiter: Limiter::Window {
+                algorithm,
+                limit,
+                window_seconds,
+                states: HashMap::new(),
+            },
+            clock,
+        })
+    }
+
+    /// Like `new_token_bucket`, with every bucket reading the time from `clock`
+    pub fn new_token_bucket_with_clock(
+        capacity: f64,
+        refill_per_sec: f64,
+        clock: SharedClock,
+    ) -> PyResult<Self> {
+        if !(capacity.is_finite() && capacity > 0.0)
+            || !(refill_per_sec.is_finite() && refill_per_sec >= 0.0)
+        {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "capacity must be positive and refill_per_sec non-negative",
+            ));
+        }
+        Ok(Self {
+            limiter: Limiter::TokenBucket {
+                capacity,
+                refill_per_second: refill_per_sec,
+                buckets: HashMap::new(),
+            },
+            clock,
+        })
+    }
+}
+
+#[pymethods]
+impl RateLimiterWrapper {
+    #[new]
+    #[pyo3(signature = (limit, window_seconds, algorithm = "sliding"))]
+    pub fn new(limit: u32, window_seconds: u32, algorithm: &str) -> PyResult<Self> {
+        Self::new_with_clock(limit, window_seconds, algorithm, Arc::new(SystemClock))
+    }
+
+    /// Buckets hold up to `capacity` tokens and refill at `refill_per_sec`
+    #[staticmethod]
+    pub fn new_token_bucket(capacity: f64, refill_per_sec: f64) -> PyResult<Self> {
+        Self::new_token_bucket_with_clock(capacity, refill_per_sec, Arc::new(SystemClock))
+    }
+
+    /// Count a request for `key`, returning whether it is within the limit
+    pub fn is_allowed(&mut self, key: &str) -> bool {
+        match &mut self.limiter {
+            Limiter::Window {
+                algorithm,
+                limit,
+                window_seconds,
+                states,
+            } => states
+                .entry(key.to_string())
+                .or_insert_with(|| {
+                    RateLimitState::new_with_clock(
+                        *algorithm,
+                        key.to_string(),
+                        *limit,
+                        *window_seconds,
+                        Arc::clone(&self.clock),
+                    )
+                })
+                .try_acquire(),
+            Limiter::TokenBucket {
+                capacity,
+                refill_per_second,
+                buckets,
+            } => buckets
+                .entry(key.to_string())
+                .or_insert_with(|| {
+                    TokenBucket::new_with_clock(
+                        *capacity,
+                        *refill_per_second,
+                        Arc::clone(&self.clock),
+                    )
+                })
+                .try_consume(1.0),
+        }
+    }
+
+    /// Spend `n` tokens from `key`'s bucket; only for token bucket limiters
+    #[pyo3(signature = (key, n = 1.0))]
+    pub fn consume(&mut self, key: &str, n: f64) -> PyResult<bool> {
+        match &mut self.limiter {
+            Limiter::TokenBucket {
+                capacity,
+                refill_per_second,
+                buckets,
+            } => Ok(buckets
+                .entry(key.to_string())
+                .or_insert_with(|| {
+                    TokenBucket::new_with_clock(
+                        *capacity,
+                        *refill_per_second,
+                        Arc::clone(&self.clock),
+                    )
+                })
+                .try_consume(n)),
+            Limiter::Window { .. } => Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                "consume() needs a limiter built with new_token_bucket()",
+            )),
+        }
+    }
+
+    /// Requests `key` may still make; whole tokens for a token bucket
+    pub fn remaining(&self, key: &str) -> u32 {
+        match &self.limiter {
+            Limiter::Window { limit, states, .. } => {
+                states.get(key).map_or(*limit, |state| state.remaining())
+            }
+            Limiter::TokenBucket {
+                capacity, buckets, ..
+            } => buckets
+                .get(key)
+                .map_or(*capacity, TokenBucket::available)
+                .floor() as u32,
+        }
+    }
+
+    /// Forget `key`; its next request starts from a fresh limiter
+    pub fn reset(&mut self, key: &str) {
+        match &mut self.limiter {
+            Limiter::Window { states, .. } => {
+                states.remove(key);
+            }
+            Limiter::TokenBucket { buckets, .. } => {
+                buckets.remove(key);
+            }
+        }
+    }
+
+    /// Drop keys whose window holds no requests or whose bucket has refilled,
+    /// returning how many were dropped
+    pub fn purge_idle(&mut self) -> usize {
+        match &mut self.limiter {
+            Limiter::Window { limit, states, .. } => {
+                let before = states.len();
+                states.retain(|_, state| state.remaining() < *limit);
+                before - states.len()
+            }
+            Limiter::TokenBucket {
+                capacity, buckets, ..
+            } => {
+                let before = buckets.len();
+                buckets.retain(|_, bucket| bucket.available() < *capacity);
+                before - buckets.len()
+            }
+        }
+    }
+
+    fn __len__(&self) -> usize {
+        match &self.limiter {
+            Limiter::Window { states, .. } => states.len(),
+            Limiter::TokenBucket { buckets, .. } => buckets.len(),
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        match &self.limiter {
+            Limiter::Window {
+                algorithm,
+                limit,
+                window_seconds,
+                states,
+            } => format!(
+                "RateLimiterWrapper(algorithm={:?}, limit={}, window_seconds={}, keys={})",
+                algorithm,
+                limit,
+                window_seconds,
+                states.len()
+            ),
+            Limiter::TokenBucket {
+                capacity,
+                refill_per_second,
+                buckets,
+            } => format!(
+                "RateLimiterWrapper(token_bucket, capacity={}, refill_per_sec={}, keys={})",
+                capacity,
+                refill_per_second,
+                buckets.len()
+            ),
+        }
+    }
+}
+
 #[pyclass]
 #[derive(Debug, Clone)]
 pub struct RequestData {
@@ -231,3 +761,78 @@ impl RequestData {
 }
 
 pub use models::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_rate_limiter_wrapper_uses_given_clock() {
+        let clock = MockClock::default();
+
+        let mut window =
+            RateLimiterWrapper::new_with_clock(2, 60, "sliding", Arc::new(clock.clone())).unwrap();
+        assert!(window.is_allowed("a"));
+        assert!(window.is_allowed("a"));
+        assert!(!window.is_allowed("a"));
+        assert!(window.is_allowed("b"));
+        clock.advance(Duration::seconds(59));
+        assert_eq!(window.remaining("a"), 0);
+        clock.advance(Duration::seconds(1));
+        assert_eq!(window.remaining("a"), 2);
+        assert!(window.is_allowed("a"));
+
+        let mut bucket =
+            RateLimiterWrapper::new_token_bucket_with_clock(2.0, 0.5, Arc::new(clock.clone()))
+                .unwrap();
+        assert!(bucket.consume("a", 2.0).unwrap());
+        assert!(!bucket.is_allowed("a"));
+        clock.advance(Duration::seconds(1));
+        assert_eq!(bucket.remaining("a"), 0);
+        clock.advance(Duration::seconds(1));
+        assert!(bucket.is_allowed("a"));
+    }
+
+    #[test]
+    fn test_rate_limiter_wrapper_forgets_idle_keys() {
+        let clock = MockClock::default();
+        let mut window =
+            RateLimiterWrapper::new_with_clock(2, 60, "sliding", Arc::new(clock.clone())).unwrap();
+        for key in ["a", "b", "c"] {
+            assert!(window.is_allowed(key));
+        }
+        window.reset("c");
+        assert_eq!(window.__len__(), 2);
+
+        clock.advance(Duration::seconds(30));
+        assert!(window.is_allowed("b"));
+        assert_eq!(window.purge_idle(), 0);
+        clock.advance(Duration::seconds(30));
+        assert_eq!(window.purge_idle(), 1);
+        assert_eq!(window.__len__(), 1);
+        assert_eq!(window.remaining("a"), 2);
+        assert_eq!(window.remaining("b"), 1);
+    }
+
+    #[test]
+    fn test_token_bucket_wrapper_forgets_full_buckets() {
+        let clock = MockClock::default();
+        let mut bucket =
+            RateLimiterWrapper::new_token_bucket_with_clock(2.0, 1.0, Arc::new(clock.clone()))
+                .unwrap();
+        assert!(bucket.consume("a", 2.0).unwrap());
+        assert!(bucket.is_allowed("b"));
+        assert!(bucket.is_allowed("c"));
+        bucket.reset("c");
+        assert_eq!(bucket.__len__(), 2);
+
+        clock.advance(Duration::seconds(1));
+        assert_eq!(bucket.purge_idle(), 1);
+        assert_eq!(bucket.__len__(), 1);
+        clock.advance(Duration::seconds(1));
+        assert_eq!(bucket.purge_idle(), 1);
+        assert_eq!(bucket.__len__(), 0);
+        assert_eq!(bucket.remaining("a"), 2);
+    }
+}
@@ -1,6 +1,8 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 
 /// Rust equivalent of FastAPI route model
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -207,6 +209,7 @@ pub struct AppConfigModel {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct CorsConfigModel {
     pub allow_origins: Vec<String>,
     pub allow_methods: Vec<String>,
@@ -255,14 +258,316 @@ impl Default for PerformanceMetrics {
     }
 }
 
+/// Source of the current time, so limiters can run against a simulated clock
+pub trait Clock: std::fmt::Debug {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// A clock picked at runtime, shared by every limiter a wrapper creates
+pub type SharedClock = Arc<dyn Clock + Send + Sync>;
+
+impl<C: Clock + ?Sized> Clock for Arc<C> {
+    fn now(&self) -> DateTime<Utc> {
+        (**self).now()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A clock that only moves when advanced; clones share the same time
+#[derive(Debug, Clone)]
+pub struct MockClock(Arc<Mutex<DateTime<Utc>>>);
+
+impl MockClock {
+    pub fn new(start: DateTime<Utc>) -> Self {
+        Self(Arc::new(Mutex::new(start)))
+    }
+
+    pub fn advance(&self, by: Duration) {
+        let mut now = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        *now += by;
+    }
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new(Utc::now())
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
 /// Rate limiting model
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct RateLimitModel {
+pub struct RateLimitModel<C: Clock = SystemClock> {
     pub key: String,
     pub limit: u32,
     pub window_seconds: u32,
     pub current_count: u32,
     pub reset_time: DateTime<Utc>,
+    #[serde(skip)]
+    clock: C,
+}
+
+impl RateLimitModel {
+    pub fn new(key: String, limit: u32, window_seconds: u32) -> Self {
+        Self::new_with_clock(key, limit, window_seconds, SystemClock)
+    }
+}
+
+impl<C: Clock> RateLimitModel<C> {
+    pub fn new_with_clock(key: String, limit: u32, window_seconds: u32, clock: C) -> Self {
+        Self {
+            key,
+            limit,
+            window_seconds,
+            current_count: 0,
+            reset_time: clock.now(),
+            clock,
+        }
+    }
+
+    /// Count a request, starting a new window once the last one has ended
+    pub fn try_acquire(&mut self) -> bool {
+        let now = self.clock.now();
+        if now >= self.reset_time {
+            self.current_count = 0;
+            self.reset_time = now + Duration::seconds(i64::from(self.window_seconds));
+        }
+        if self.current_count < self.limit {
+            self.current_count += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn remaining(&self) -> u32 {
+        if self.clock.now() >= self.reset_time {
+            self.limit
+        } else {
+            self.limit.saturating_sub(self.current_count)
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.current_count = 0;
+        self.reset_time = self.clock.now();
+    }
+}
+
+/// Allows at most `limit` requests in any `window_seconds` span, so unlike
+/// `RateLimitModel` a burst cannot straddle a window boundary
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlidingWindowRateLimiter<C: Clock = SystemClock> {
+    pub key: String,
+    pub limit: u32,
+    pub window_seconds: u32,
+    pub timestamps: VecDeque<DateTime<Utc>>,
+    #[serde(skip)]
+    clock: C,
+}
+
+impl SlidingWindowRateLimiter {
+    pub fn new(key: String, limit: u32, window_seconds: u32) -> Self {
+        Self::new_with_clock(key, limit, window_seconds, SystemClock)
+    }
+}
+
+impl<C: Clock> SlidingWindowRateLimiter<C> {
+    pub fn new_with_clock(key: String, limit: u32, window_seconds: u32, clock: C) -> Self {
+        Self {
+            key,
+            limit,
+            window_seconds,
+            timestamps: VecDeque::new(),
+            clock,
+        }
+    }
+
+    fn window_start(&self) -> DateTime<Utc> {
+        self.clock.now() - Duration::seconds(i64::from(self.window_seconds))
+    }
+
+    pub fn try_acquire(&mut self) -> bool {
+        let window_start = self.window_start();
+        while self.timestamps.front().is_some_and(|&t| t <= window_start) {
+            self.timestamps.pop_front();
+        }
+        if self.timestamps.len() < self.limit as usize {
+            self.timestamps.push_back(self.clock.now());
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn remaining(&self) -> u32 {
+        let window_start = self.window_start();
+        let in_window = self
+            .timestamps
+            .iter()
+            .filter(|&&t| t > window_start)
+            .count();
+        self.limit.saturating_sub(in_window as u32)
+    }
+
+    pub fn reset(&mut self) {
+        self.timestamps.clear();
+    }
+}
+
+/// Holds up to `capacity` tokens, topped up continuously at
+/// `refill_tokens_per_second`; each request spends one or more of them
+#[derive(Debug, Clone)]
+pub struct TokenBucket<C: Clock = SystemClock> {
+    pub capacity: f64,
+    pub refill_tokens_per_second: f64,
+    tokens: f64,
+    last_refill: DateTime<Utc>,
+    clock: C,
+}
+
+impl TokenBucket {
+    pub fn new(capacity: f64, refill_tokens_per_second: f64) -> Self {
+        Self::new_with_clock(capacity, refill_tokens_per_second, SystemClock)
+    }
+}
+
+impl<C: Clock> TokenBucket<C> {
+    /// A full bucket that reads the time from `clock`
+    pub fn new_with_clock(capacity: f64, refill_tokens_per_second: f64, clock: C) -> Self {
+        let last_refill = clock.now();
+        Self {
+            capacity,
+            refill_tokens_per_second,
+            tokens: capacity,
+            last_refill,
+            clock,
+        }
+    }
+
+    /// Tokens available now, including the fractional refill since the last update
+    pub fn available(&self) -> f64 {
+        let elapsed = (self.clock.now() - self.last_refill)
+            .to_std()
+            .map_or(0.0, |elapsed| elapsed.as_secs_f64());
+        (self.tokens + elapsed * self.refill_tokens_per_second).min(self.capacity)
+    }
+
+    /// Spend `n` tokens if that many are available; otherwise spend none
+    pub fn try_consume(&mut self, n: f64) -> bool {
+        self.tokens = self.available();
+        self.last_refill = self.clock.now();
+        if self.tokens >= n {
+            self.tokens -= n;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.tokens = self.capacity;
+        self.last_refill = self.clock.now();
+    }
+}
+
+/// Which counting scheme a rate limiter uses
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RateLimitAlgorithm {
+    Fixed,
+    #[default]
+    Sliding,
+}
+
+impl FromStr for RateLimitAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "fixed" => Ok(Self::Fixed),
+            "sliding" => Ok(Self::Sliding),
+            other => Err(format!("Unknown rate limit algorithm: {}", other)),
+        }
+    }
+}
+
+/// Per-key limiter state for either algorithm
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(serialize = "", deserialize = "C: Default"))]
+pub enum RateLimitState<C: Clock = SystemClock> {
+    Fixed(RateLimitModel<C>),
+    Sliding(SlidingWindowRateLimiter<C>),
+}
+
+impl RateLimitState {
+    pub fn new(
+        algorithm: RateLimitAlgorithm,
+        key: String,
+        limit: u32,
+        window_seconds: u32,
+    ) -> Self {
+        Self::new_with_clock(algorithm, key, limit, window_seconds, SystemClock)
+    }
+}
+
+impl<C: Clock> RateLimitState<C> {
+    pub fn new_with_clock(
+        algorithm: RateLimitAlgorithm,
+        key: String,
+        limit: u32,
+        window_seconds: u32,
+        clock: C,
+    ) -> Self {
+        match algorithm {
+            RateLimitAlgorithm::Fixed => Self::Fixed(RateLimitModel::new_with_clock(
+                key,
+                limit,
+                window_seconds,
+                clock,
+            )),
+            RateLimitAlgorithm::Sliding => Self::Sliding(SlidingWindowRateLimiter::new_with_clock(
+                key,
+                limit,
+                window_seconds,
+                clock,
+            )),
+        }
+    }
+
+    pub fn try_acquire(&mut self) -> bool {
+        match self {
+            Self::Fixed(limiter) => limiter.try_acquire(),
+            Self::Sliding(limiter) => limiter.try_acquire(),
+        }
+    }
+
+    pub fn remaining(&self) -> u32 {
+        match self {
+            Self::Fixed(limiter) => limiter.remaining(),
+            Self::Sliding(limiter) => limiter.remaining(),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        match self {
+            Self::Fixed(limiter) => limiter.reset(),
+            Self::Sliding(limiter) => limiter.reset(),
+        }
+    }
 }
 
 /// Cache entry model
@@ -294,6 +599,13 @@ impl CacheEntryModel {
         self
     }
 
+    /// Like `with_ttl`, but `None` when the expiry time would be out of range
+    pub fn try_with_ttl(mut self, ttl_seconds: i64) -> Option<Self> {
+        let ttl = chrono::Duration::try_seconds(ttl_seconds)?;
+        self.expires_at = Some(Utc::now().checked_add_signed(ttl)?);
+        Some(self)
+    }
+
     pub fn is_expired(&self) -> bool {
         if let Some(expires_at) = self.expires_at {
             Utc::now() > expires_at
@@ -401,4 +713,88 @@ mod tests {
         assert!(error.details.is_some());
         assert_eq!(error.request_id, Some("req_123".to_string()));
     }
+
+    #[test]
+    fn test_rate_limit_model_resets_after_window() {
+        let clock = MockClock::default();
+        let mut model = RateLimitModel::new_with_clock("k".to_string(), 2, 60, clock.clone());
+
+        assert!(model.try_acquire());
+        assert!(model.try_acquire());
+        assert!(!model.try_acquire());
+        assert_eq!(model.remaining(), 0);
+
+        clock.advance(Duration::seconds(59));
+        assert!(!model.try_acquire());
+
+        clock.advance(Duration::seconds(1));
+        assert_eq!(model.remaining(), 2);
+        assert!(model.try_acquire());
+        assert_eq!(model.current_count, 1);
+
+        // The clock is not part of the serialized model
+        let json = serde_json::to_value(&model).unwrap();
+        assert!(json.get("clock").is_none());
+        let restored: RateLimitModel = serde_json::from_value(json).unwrap();
+        assert_eq!(restored.current_count, 1);
+    }
+
+    #[test]
+    fn test_sliding_window_prevents_boundary_burst() {
+        let clock = MockClock::default();
+
+        // A fixed window that opened a second ago lets three requests through
+        // in its last second and three more in the next window's first second
+        let mut fixed = RateLimitModel::new_with_clock("k".to_string(), 3, 60, clock.clone());
+        fixed.reset_time = clock.now() + Duration::seconds(1);
+        assert_eq!((0..3).filter(|_| fixed.try_acquire()).count(), 3);
+        clock.advance(Duration::seconds(1));
+        assert_eq!((0..3).filter(|_| fixed.try_acquire()).count(), 3);
+
+        let mut sliding = RateLimitState::new_with_clock(
+            RateLimitAlgorithm::Sliding,
+            "k".to_string(),
+            3,
+            60,
+            clock.clone(),
+        );
+        assert_eq!((0..3).filter(|_| sliding.try_acquire()).count(), 3);
+        clock.advance(Duration::seconds(1));
+        assert_eq!(sliding.remaining(), 0);
+        assert!(!sliding.try_acquire());
+        clock.advance(Duration::seconds(59));
+        assert_eq!(sliding.remaining(), 3);
+        assert!(sliding.try_acquire());
+
+        sliding.reset();
+        assert_eq!(sliding.remaining(), 3);
+        assert_eq!("Fixed".parse(), Ok(RateLimitAlgorithm::Fixed));
+        assert!("leaky".parse::<RateLimitAlgorithm>().is_err());
+    }
+
+    #[test]
+    fn test_token_bucket_burst_then_refill() {
+        let clock = MockClock::default();
+        let mut bucket = TokenBucket::new_with_clock(5.0, 2.0, clock.clone());
+
+        // The full bucket absorbs a burst of five, then runs dry
+        assert_eq!((0..6).filter(|_| bucket.try_consume(1.0)).count(), 5);
+
+        // Refill is continuous: half a token after 250ms, one after 500ms
+        clock.advance(Duration::milliseconds(250));
+        assert!(!bucket.try_consume(1.0));
+        clock.advance(Duration::milliseconds(250));
+        assert!(bucket.try_consume(1.0));
+        assert!(!bucket.try_consume(1.0));
+
+        // It never holds more than its capacity
+        clock.advance(Duration::seconds(10));
+        assert_eq!(bucket.available(), 5.0);
+        assert!(!bucket.try_consume(6.0));
+        assert!(bucket.try_consume(3.0));
+        assert_eq!(bucket.available(), 2.0);
+
+        bucket.reset();
+        assert_eq!(bucket.available(), 5.0);
+    }
 }
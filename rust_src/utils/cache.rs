@@ -0,0 +1,181 @@
+use crate::types::models::CacheEntryModel;
+use indexmap::IndexMap;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+/// A TTL so large that the expiry time cannot be represented
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("TTL of {0} seconds is out of range")]
+pub struct InvalidTtl(pub i64);
+
+#[derive(Debug)]
+struct Slot {
+    entry: CacheEntryModel,
+    last_used: u64,
+}
+
+/// In-memory response cache with per-entry TTLs. Once `max_entries` is
+/// exceeded the least recently used entry is evicted; expired entries are
+/// dropped lazily on access or by `purge_expired`.
+#[derive(Debug)]
+pub struct ResponseCache {
+    entries: IndexMap<String, Slot>,
+    /// Keys by the tick of their last use, oldest first
+    recency: BTreeMap<u64, String>,
+    tick: u64,
+    max_entries: usize,
+}
+
+impl ResponseCache {
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            entries: IndexMap::new(),
+            recency: BTreeMap::new(),
+            tick: 0,
+            max_entries,
+        }
+    }
+
+    /// The live value for `key`, marking it most recently used
+    pub fn get(&mut self, key: &str) -> Option<&Value> {
+        if self.entries.get(key)?.entry.is_expired() {
+            self.remove(key);
+            return None;
+        }
+        self.tick += 1;
+        let slot = self.entries.get_mut(key)?;
+        self.recency.remove(&slot.last_used);
+        self.recency.insert(self.tick, key.to_string());
+        slot.last_used = self.tick;
+        slot.entry.touch();
+        Some(&slot.entry.value)
+    }
+
+    /// Store `value` under `key`, expiring after `ttl_seconds` if given
+    pub fn put(
+        &mut self,
+        key: String,
+        value: Value,
+        ttl_seconds: Option<i64>,
+    ) -> Result<(), InvalidTtl> {
+        let mut entry = CacheEntryModel::new(key.clone(), value);
+        if let Some(ttl) = ttl_seconds {
+            entry = entry.try_with_ttl(ttl).ok_or(InvalidTtl(ttl))?;
+        }
+        self.remove(&key);
+        self.tick += 1;
+        self.recency.insert(self.tick, key.clone());
+        self.entries.insert(
+            key,
+            Slot {
+                entry,
+                last_used: self.tick,
+            },
+        );
+        while self.entries.len() > self.max_entries {
+            let Some((_, oldest)) = self.recency.pop_first() else {
+                break;
+            };
+            self.entries.swap_remove(&oldest);
+        }
+        Ok(())
+    }
+
+    pub fn remove(&mut self, key: &str) -> bool {
+        match self.entries.swap_remove(key) {
+            Some(slot) => {
+                self.recency.remove(&slot.last_used);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Drop every expired entry, returning how many were removed
+    pub fn purge_expired(&mut self) -> usize {
+        let expired: Vec<String> = self
+            .entries
+            .iter()
+            .filter(|(_, slot)| slot.entry.is_expired())
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in &expired {
+            self.remove(key);
+        }
+        expired.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_expired_entries_are_removed() {
+        let mut cache = ResponseCache::new(10);
+        cache.put("stale".to_string(), json!(1), Some(-1)).unwrap();
+        cache.put("fresh".to_string(), json!(2), Some(60)).unwrap();
+        cache.put("forever".to_string(), json!(3), None).unwrap();
+
+        assert_eq!(cache.get("stale"), None);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get("fresh"), Some(&json!(2)));
+
+        cache.put("stale".to_string(), json!(1), Some(-1)).unwrap();
+        assert_eq!(cache.purge_expired(), 1);
+        assert_eq!(cache.get("forever"), Some(&json!(3)));
+
+        assert_eq!(
+            cache.put("far".to_string(), json!(4), Some(i64::MAX)),
+            Err(InvalidTtl(i64::MAX))
+        );
+        assert_eq!(cache.get("far"), None);
+    }
+
+    #[test]
+    fn test_least_recently_used_is_evicted() {
+        let mut cache = ResponseCache::new(2);
+        cache.put("a".to_string(), json!("a"), None).unwrap();
+        cache.put("b".to_string(), json!("b"), None).unwrap();
+        assert!(cache.get("a").is_some());
+
+        // "b" is now the least recently used
+        cache.put("c".to_string(), json!("c"), None).unwrap();
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get("b"), None);
+        assert!(cache.get("a").is_some());
+
+        // Overwriting counts as a use
+        cache.put("c".to_string(), json!("c2"), None).unwrap();
+        cache.put("d".to_string(), json!("d"), None).unwrap();
+        assert_eq!(cache.get("a"), None);
+        assert_eq!(cache.get("c"), Some(&json!("c2")));
+        assert!(cache.remove("d"));
+        assert!(!cache.remove("d"));
+        assert_eq!(cache.recency.len(), cache.len());
+    }
+
+    #[test]
+    fn test_get_counts_accesses() {
+        let mut cache = ResponseCache::new(4);
+        cache
+            .put("k".to_string(), json!({"ok": true}), Some(60))
+            .unwrap();
+        assert_eq!(cache.entries["k"].entry.access_count, 0);
+
+        cache.get("k");
+        cache.get("k");
+        assert_eq!(cache.entries["k"].entry.access_count, 2);
+        assert_eq!(cache.get("missing"), None);
+    }
+}
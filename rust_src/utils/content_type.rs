@@ -81,18 +81,21 @@ pub fn normalize_media_type(media_type: &str) -> String {
     media_type.trim().to_lowercase()
 }
 
-/// Build content type string from media type and parameters
+/// Build content type string from media type and parameters, sorted by name
 pub fn build_content_type(media_type: &str, parameters: &HashMap<String, String>) -> String {
     if parameters.is_empty() {
         media_type.to_string()
     } else {
-        let params: Vec<String> = parameters
-            .iter()
+        let mut sorted: Vec<(&String, &String)> = parameters.iter().collect();
+        sorted.sort_unstable();
+
+        let params: Vec<String> = sorted
+            .into_iter()
             .map(|(k, v)| {
-                if v.contains(' ') || v.contains(';') || v.contains(',') {
-                    format!("{}=\"{}\"", k, v)
-                } else {
+                if is_token(v) {
                     format!("{}={}", k, v)
+                } else {
+                    format!("{}={}", k, quote_string(v))
                 }
             })
             .collect();
@@ -100,6 +103,177 @@ pub fn build_content_type(media_type: &str, parameters: &HashMap<String, String>
     }
 }
 
+/// Pick the best of `available` for an Accept-Charset header, honouring `q` weights.
+/// An empty or `*` header prefers UTF-8; returns `None` when nothing is acceptable.
+pub fn negotiate_charset(accept_charset: &str, available: &[String]) -> Option<String> {
+    let header = accept_charset.trim();
+    if header.is_empty() || header == "*" {
+        return available
+            .iter()
+            .find(|charset| is_utf8_charset(charset))
+            .or_else(|| available.first())
+            .cloned();
+    }
+
+    // (charset, quality) in header order; malformed weights make the entry unusable
+    let preferences: Vec<(String, f32)> = header
+        .split(',')
+        .filter_map(|entry| {
+            let mut parts = entry.split(';');
+            let charset = parts.next()?.trim().to_lowercase();
+            if charset.is_empty() {
+                return None;
+            }
+            let mut quality = 1.0;
+            for param in parts {
+                if let Some((name, value)) = param.split_once('=') {
+                    if name.trim().eq_ignore_ascii_case("q") {
+                        quality = value.trim().parse::<f32>().ok()?;
+                    }
+                }
+            }
+            Some((charset, quality.clamp(0.0, 1.0)))
+        })
+        .collect();
+
+    let wildcard = preferences
+        .iter()
+        .find(|(charset, _)| charset == "*")
+        .map(|(_, quality)| *quality);
+
+    let mut best: Option<(&String, f32, bool)> = None;
+    for charset in available {
+        let explicit = preferences
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(charset))
+            .map(|(_, quality)| *quality);
+        let (quality, is_explicit) = match (explicit, wildcard) {
+            (Some(quality), _) => (quality, true),
+            (None, Some(quality)) => (quality, false),
+            (None, None) => continue,
+        };
+        if quality <= 0.0 {
+            continue;
+        }
+
+        // Higher weight wins; on a tie a named charset beats one matched by `*`
+        let better = match best {
+            None => true,
+            Some((_, best_quality, best_explicit)) => {
+                quality > best_quality || (quality == best_quality && is_explicit && !best_explicit)
+            }
+        };
+        if better {
+            best = Some((charset, quality, is_explicit));
+        }
+    }
+
+    best.map(|(charset, _, _)| charset.clone())
+}
+
+/// Pick the best of `available` for an Accept header, honouring `q` weights and
+/// `type/*` or `*/*` ranges. Each type takes its weight from the most specific
+/// range matching it; ties prefer the more specific match, then `available`
+/// order. An empty header accepts anything; returns `None` when nothing is acceptable.
+pub fn negotiate(accept_header: &str, available: &[&str]) -> Option<String> {
+    let header = accept_header.trim();
+    if header.is_empty() {
+        return available.first().map(|media_type| media_type.to_string());
+    }
+
+    // (type, subtype, quality); malformed ranges and weights make the entry unusable
+    let ranges: Vec<(String, String, f32)> = header
+        .split(',')
+        .filter_map(|entry| {
+            let mut parts = entry.split(';');
+            let range = parts.next()?.trim().to_lowercase();
+            let (main, sub) = range.split_once('/')?;
+            if main.is_empty() || sub.is_empty() || (main == "*" && sub != "*") {
+                return None;
+            }
+            let mut quality = 1.0;
+            for param in parts {
+                if let Some((name, value)) = param.split_once('=') {
+                    if name.trim().eq_ignore_ascii_case("q") {
+                        quality = value.trim().parse::<f32>().ok()?;
+                    }
+                }
+            }
+            Some((main.to_string(), sub.to_string(), quality.clamp(0.0, 1.0)))
+        })
+        .collect();
+
+    let mut best: Option<(&str, f32, u8)> = None;
+    for media_type in available {
+        let normalized = normalize_media_type(media_type);
+        let Some((main, sub)) = normalized.split_once('/') else {
+            continue;
+        };
+
+        // 2 for `type/subtype`, 1 for `type/*`, 0 for `*/*`
+        let matched = ranges
+            .iter()
+            .filter_map(|(range_main, range_sub, quality)| {
+                let specificity = match (range_main.as_str(), range_sub.as_str()) {
+                    ("*", "*") => 0,
+                    (m, "*") if m == main => 1,
+                    (m, s) if m == main && s == sub => 2,
+                    _ => return None,
+                };
+                Some((*quality, specificity))
+            })
+            .max_by_key(|(_, specificity)| *specificity);
+        let Some((quality, specificity)) = matched else {
+            continue;
+        };
+        if quality <= 0.0 {
+            continue;
+        }
+
+        let better = match best {
+            None => true,
+            Some((_, best_quality, best_specificity)) => {
+                quality > best_quality
+                    || (quality == best_quality && specificity > best_specificity)
+            }
+        };
+        if better {
+            best = Some((media_type, quality, specificity));
+        }
+    }
+
+    best.map(|(media_type, _, _)| media_type.to_string())
+}
+
+/// Punctuation allowed in an RFC 7230 `token` besides letters and digits
+const TCHAR_SYMBOLS: &[u8] = b"!#$%&'*+-.^_`|~";
+
+/// RFC 7230 `token`: one or more `tchar`s, which may be sent unquoted
+pub fn is_token(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || TCHAR_SYMBOLS.contains(&b))
+}
+
+/// RFC 7230 `quoted-string`, backslash-escaping `"` and `\`
+fn quote_string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        if c == '"' || c == '\\' {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn is_utf8_charset(charset: &str) -> bool {
+    charset.eq_ignore_ascii_case("utf-8") || charset.eq_ignore_ascii_case("utf8")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -184,4 +358,130 @@ mod tests {
             "multipart/form-data; boundary=\"form boundary\""
         );
     }
+
+    #[test]
+    fn test_build_content_type_is_deterministic() {
+        let expected = "multipart/form-data; boundary=xyz; charset=utf-8";
+        for _ in 0..20 {
+            // Fresh maps get fresh random hash seeds, so iteration order varies
+            let mut params = HashMap::new();
+            params.insert("charset".to_string(), "utf-8".to_string());
+            params.insert("boundary".to_string(), "xyz".to_string());
+            assert_eq!(build_content_type("multipart/form-data", &params), expected);
+        }
+    }
+
+    #[test]
+    fn test_negotiate_charset() {
+        let available = vec![
+            "utf-8".to_string(),
+            "ISO-8859-1".to_string(),
+            "windows-1252".to_string(),
+        ];
+
+        // Explicit preference, including case-insensitive names
+        assert_eq!(
+            negotiate_charset("iso-8859-1, utf-8;q=0.7", &available),
+            Some("ISO-8859-1".to_string())
+        );
+        assert_eq!(
+            negotiate_charset("utf-8;q=0.2, windows-1252;q=0.9", &available),
+            Some("windows-1252".to_string())
+        );
+
+        // Absent header or bare wildcard defaults to UTF-8
+        assert_eq!(negotiate_charset("", &available), Some("utf-8".to_string()));
+        assert_eq!(
+            negotiate_charset("*", &available),
+            Some("utf-8".to_string())
+        );
+
+        // Wildcard covers unnamed charsets; q=0 excludes
+        assert_eq!(
+            negotiate_charset("utf-8;q=0, *;q=0.5", &available),
+            Some("ISO-8859-1".to_string())
+        );
+        assert_eq!(
+            negotiate_charset("*;q=0.5, windows-1252;q=0.5", &available),
+            Some("windows-1252".to_string())
+        );
+
+        // Only unsupported charsets requested
+        assert_eq!(negotiate_charset("shift_jis, koi8-r", &available), None);
+        assert_eq!(negotiate_charset("", &[]), None);
+    }
+
+    #[test]
+    fn test_negotiate_media_type() {
+        let available = ["application/json", "text/plain"];
+
+        assert_eq!(
+            negotiate("text/html;q=0.9, application/json", &available),
+            Some("application/json".to_string())
+        );
+        assert_eq!(
+            negotiate("application/json;q=0.5, text/plain;q=0.8", &available),
+            Some("text/plain".to_string())
+        );
+
+        // Wildcards, and the most specific range deciding a type's weight
+        assert_eq!(
+            negotiate("text/*", &available),
+            Some("text/plain".to_string())
+        );
+        assert_eq!(
+            negotiate("*/*;q=0.8, application/json;q=0", &available),
+            Some("text/plain".to_string())
+        );
+
+        // Equal weights: the more specific match, then `available` order
+        assert_eq!(
+            negotiate("*/*, text/plain", &available),
+            Some("text/plain".to_string())
+        );
+        assert_eq!(
+            negotiate("*/*", &available),
+            Some("application/json".to_string())
+        );
+        assert_eq!(
+            negotiate("", &available),
+            Some("application/json".to_string())
+        );
+
+        // Nothing acceptable, and malformed ranges ignored
+        assert_eq!(negotiate("image/png, text/html", &available), None);
+        assert_eq!(negotiate("*/json, text/plain;q=abc", &available), None);
+        assert_eq!(negotiate("application/json", &[]), None);
+    }
+
+    #[test]
+    fn test_build_content_type_quotes_non_tokens() {
+        let build = |name: &str, value: &str| {
+            let mut params = HashMap::new();
+            params.insert(name.to_string(), value.to_string());
+            build_content_type("multipart/form-data", &params)
+        };
+
+        assert_eq!(
+            build("boundary", "----=_Part_0"),
+            "multipart/form-data; boundary=\"----=_Part_0\""
+        );
+        assert_eq!(
+            build("boundary", r#"say "hi"\now"#),
+            r#"multipart/form-data; boundary="say \"hi\"\\now""#
+        );
+        assert_eq!(
+            build("boundary", "a/b@c"),
+            "multipart/form-data; boundary=\"a/b@c\""
+        );
+        assert_eq!(build("name", ""), "multipart/form-data; name=\"\"");
+        assert_eq!(
+            build("boundary", "simple-1.2_x"),
+            "multipart/form-data; boundary=simple-1.2_x"
+        );
+
+        assert!(is_token("utf-8"));
+        assert!(!is_token("caf\u{e9}"));
+        assert!(!is_token("a b"));
+    }
 }
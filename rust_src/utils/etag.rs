@@ -0,0 +1,79 @@
+use crate::security::utils::{hash_sha256, hex_encode};
+
+/// Strong ETag for `body`: its SHA-256 digest in hex, quoted
+pub fn compute_etag(body: &[u8]) -> String {
+    format!("\"{}\"", hex_encode(&hash_sha256(body)))
+}
+
+/// The opaque part of an entity tag, without any `W/` prefix
+fn opaque_tag(tag: &str) -> &str {
+    tag.trim().strip_prefix("W/").unwrap_or(tag.trim())
+}
+
+/// Entity tags in an `If-None-Match` list. Commas may appear inside a quoted
+/// tag, so the list is split on commas outside quotes.
+fn entity_tags(header: &str) -> Vec<&str> {
+    let mut tags = Vec::new();
+    let mut start = 0;
+    let mut in_quotes = false;
+    for (i, c) in header.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => {
+                tags.push(header[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    tags.push(header[start..].trim());
+    tags.retain(|tag| !tag.is_empty());
+    tags
+}
+
+/// Whether `If-None-Match` matches `etag`, so a GET or HEAD should get 304.
+///
+/// Uses the weak comparison RFC 9110 requires for `If-None-Match`: `W/"x"`
+/// and `"x"` match each other. `*` matches any current representation.
+pub fn evaluate_preconditions(if_none_match: Option<&str>, etag: &str) -> bool {
+    let Some(header) = if_none_match else {
+        return false;
+    };
+    let current = opaque_tag(etag);
+    entity_tags(header)
+        .into_iter()
+        .any(|tag| tag == "*" || opaque_tag(tag) == current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compute_etag() {
+        assert_eq!(
+            compute_etag(b""),
+            "\"e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855\""
+        );
+        assert_ne!(compute_etag(b"a"), compute_etag(b"b"));
+    }
+
+    #[test]
+    fn test_evaluate_preconditions() {
+        let etag = compute_etag(b"hello");
+
+        assert!(evaluate_preconditions(Some(&etag), &etag));
+        assert!(evaluate_preconditions(
+            Some(&format!("\"other\", W/{}", etag)),
+            &etag
+        ));
+        assert!(evaluate_preconditions(Some("*"), &etag));
+
+        assert!(!evaluate_preconditions(None, &etag));
+        assert!(!evaluate_preconditions(Some("\"other\""), &etag));
+        assert!(!evaluate_preconditions(Some(""), &etag));
+        // A comma inside a quoted tag does not split it
+        assert!(evaluate_preconditions(Some("\"a,b\""), "\"a,b\""));
+        assert!(!evaluate_preconditions(Some("\"a,b\""), "\"a\""));
+    }
+}
@@ -1,91 +1,52 @@
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
 
-/// Generate unique operation ID for FastAPI routes
+/// Operation ID in FastAPI's `{route_name}{path_format}_{method}` form.
+///
+/// Converters are dropped as in FastAPI's `path_format`, every non-word
+/// character becomes `_`, runs of `_` collapse to one and leading or trailing
+/// `_` are trimmed, so `get_user` + `GET /users/{id}` gives `get_user_users_id_get`.
 pub fn generate_unique_id(route_name: &str, method: &str, path: &str) -> String {
-    // Convert method to lowercase for consistency
-    let method_lower = method.to_lowercase();
-
-    // Clean up route name
-    let clean_name = route_name
-        .replace("_", " ")
-        .split_whitespace()
-        .map(|word| {
-            let mut chars = word.chars();
-            match chars.next() {
-                None => String::new(),
-                Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
-            }
-        })
-        .collect::<Vec<_>>()
-        .join("");
-
-    // If we have a clean name, use method + name pattern
-    if !clean_name.is_empty() && clean_name != route_name {
-        format!("{}_{}", method_lower, snake_case(&clean_name))
-    } else {
-        // Fallback to path-based generation
-        generate_id_from_path(&method_lower, path)
-    }
-}
-
-/// Generate ID from HTTP method and path
-fn generate_id_from_path(method: &str, path: &str) -> String {
-    let mut parts = Vec::new();
-    parts.push(method.to_string());
-
-    for segment in path.split('/') {
-        if segment.is_empty() {
-            continue;
-        }
-
-        if segment.starts_with('{') && segment.ends_with('}') {
-            // Extract parameter name without type annotation
-            let param_name = segment
-                .trim_start_matches('{')
-                .trim_end_matches('}')
-                .split(':')
-                .next()
-                .unwrap_or("param");
-            parts.push(format!("by_{}", param_name));
+    let raw = format!(
+        "{}{}_{}",
+        route_name,
+        strip_converters(path),
+        method.to_lowercase()
+    );
+    let mut slug = String::with_capacity(raw.len());
+    for c in raw.chars() {
+        let c = if c.is_alphanumeric() || c == '_' {
+            c
         } else {
-            parts.push(clean_path_segment(segment));
+            '_'
+        };
+        if c != '_' || !(slug.is_empty() || slug.ends_with('_')) {
+            slug.push(c);
         }
     }
-
-    parts.join("_")
-}
-
-/// Clean path segment for use in identifiers
-fn clean_path_segment(segment: &str) -> String {
-    segment
-        .chars()
-        .filter(|c| c.is_alphanumeric() || *c == '_')
-        .collect::<String>()
-        .to_lowercase()
+    slug.trim_end_matches('_').to_string()
 }
 
-/// Convert CamelCase to snake_case
-fn snake_case(input: &str) -> String {
-    let mut result = String::new();
-    let mut chars = input.chars().peekable();
-
-    while let Some(ch) = chars.next() {
-        if ch.is_uppercase() {
-            if !result.is_empty() && !result.ends_with('_') {
-                if let Some(&next_ch) = chars.peek() {
-                    if next_ch.is_lowercase() {
-                        result.push('_');
-                    }
-                }
+/// `path` with each `{name:converter}` reduced to `{name}`
+fn strip_converters(path: &str) -> String {
+    let mut stripped = String::with_capacity(path.len());
+    let mut in_braces = false;
+    let mut in_converter = false;
+    for c in path.chars() {
+        match c {
+            '{' => in_braces = true,
+            '}' => {
+                in_braces = false;
+                in_converter = false;
             }
-            result.push(ch.to_lowercase().next().unwrap());
-        } else {
-            result.push(ch);
+            ':' if in_braces => in_converter = true,
+            _ => {}
+        }
+        if !in_converter {
+            stripped.push(c);
         }
     }
-
-    result
+    stripped
 }
 
 /// Generate UUID-like identifier
@@ -192,34 +153,21 @@ mod tests {
 
     #[test]
     fn test_generate_unique_id() {
-        // Test with clean route name
-        let id = generate_unique_id("get_users", "GET", "/users");
-        assert_eq!(id, "get_get_users");
-
-        // Test with path-based generation
-        let id = generate_unique_id("", "GET", "/users/{id}");
-        assert_eq!(id, "get_users_by_id");
-
-        // Test with complex path
-        let id = generate_unique_id("", "POST", "/api/v1/users/{user_id}/posts");
-        assert_eq!(id, "post_api_v1_users_by_user_id_posts");
-    }
-
-    #[test]
-    fn test_snake_case() {
-        assert_eq!(snake_case("CamelCase"), "camel_case");
-        assert_eq!(snake_case("HTMLParser"), "html_parser");
-        assert_eq!(snake_case("XMLHttpRequest"), "xml_http_request");
-        assert_eq!(snake_case("simple"), "simple");
-        assert_eq!(snake_case(""), "");
-    }
-
-    #[test]
-    fn test_clean_path_segment() {
-        assert_eq!(clean_path_segment("users"), "users");
-        assert_eq!(clean_path_segment("user-profile"), "userprofile");
-        assert_eq!(clean_path_segment("api@v1"), "apiv1");
-        assert_eq!(clean_path_segment("123abc"), "123abc");
+        assert_eq!(
+            generate_unique_id("get_user", "GET", "/users/{id}"),
+            "get_user_users_id_get"
+        );
+        assert_eq!(generate_unique_id("root", "GET", "/"), "root_get");
+        assert_eq!(
+            generate_unique_id("create_post", "POST", "/api/v1/users/{user_id}/posts"),
+            "create_post_api_v1_users_user_id_posts_post"
+        );
+        // The converter is not part of the slug
+        assert_eq!(
+            generate_unique_id("read_file", "get", "/files/{file_path:path}"),
+            "read_file_files_file_path_get"
+        );
+        assert_eq!(generate_unique_id("_hidden_", "GET", "/x/"), "hidden_x_get");
     }
 
     #[test]
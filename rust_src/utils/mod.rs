@@ -1,14 +1,18 @@
 pub mod async_tools;
+pub mod cache;
 pub mod content_type;
+pub mod etag;
 pub mod id_generation;
 pub mod type_conv;
 
 use pyo3::prelude::*;
-use pyo3::types::PyDict;
+use pyo3::types::{PyDict, PyList, PyTuple};
 use std::collections::HashMap;
 
 pub use async_tools::*;
+pub use cache::*;
 pub use content_type::*;
+pub use etag::*;
 pub use id_generation::*;
 pub use type_conv::*;
 
@@ -24,3 +28,24 @@ pub fn py_dict_to_hashmap(dict: &Bound<PyDict>) -> PyResult<HashMap<String, Stri
 
     Ok(map)
 }
+
+/// Convert a Python dict to a multi-value map; list and tuple values supply
+/// one string per item, anything else a single string
+pub fn py_dict_to_multimap(dict: &Bound<PyDict>) -> PyResult<HashMap<String, Vec<String>>> {
+    let mut map = HashMap::new();
+
+    for (key, value) in dict.iter() {
+        let key_str = key.str()?.to_str()?.to_string();
+        let values = if value.is_instance_of::<PyList>() || value.is_instance_of::<PyTuple>() {
+            value
+                .iter()?
+                .map(|item| Ok(item?.str()?.to_str()?.to_string()))
+                .collect::<PyResult<Vec<_>>>()?
+        } else {
+            vec![value.str()?.to_str()?.to_string()]
+        };
+        map.insert(key_str, values);
+    }
+
+    Ok(map)
+}
@@ -283,8 +283,16 @@ pub fn python_to_rust_value(py_obj: &Bound<PyAny>) -> Result<RustValue> {
     Ok(RustValue::String(str_repr))
 }
 
+/// How `RustValue::deep_merge_with` combines two arrays
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArrayMergeStrategy {
+    #[default]
+    Replace,
+    Concat,
+}
+
 /// Rust representation of Python values
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum RustValue {
     None,
     String(String),
@@ -339,6 +347,34 @@ impl RustValue {
             _ => None,
         }
     }
+
+    /// Overlay `other` onto `self`, replacing arrays
+    pub fn deep_merge(&mut self, other: &RustValue) {
+        self.deep_merge_with(other, ArrayMergeStrategy::Replace);
+    }
+
+    /// Recursively merge objects; on any other conflict `other` wins, except
+    /// that arrays are appended when `arrays` is `Concat`
+    pub fn deep_merge_with(&mut self, other: &RustValue, arrays: ArrayMergeStrategy) {
+        match (self, other) {
+            (RustValue::Object(base), RustValue::Object(overlay)) => {
+                for (key, value) in overlay {
+                    match base.get_mut(key) {
+                        Some(existing) => existing.deep_merge_with(value, arrays),
+                        None => {
+                            base.insert(key.clone(), value.clone());
+                        }
+                    }
+                }
+            }
+            (RustValue::Array(base), RustValue::Array(overlay))
+                if arrays == ArrayMergeStrategy::Concat =>
+            {
+                base.extend(overlay.iter().cloned());
+            }
+            (this, other) => *this = other.clone(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -395,4 +431,79 @@ mod tests {
             "object"
         );
     }
+
+    fn object(entries: Vec<(&str, RustValue)>) -> RustValue {
+        RustValue::Object(
+            entries
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_deep_merge_nested_objects() {
+        let mut base = object(vec![
+            (
+                "db",
+                object(vec![
+                    ("host", RustValue::String("localhost".to_string())),
+                    ("port", RustValue::Integer(5432)),
+                ]),
+            ),
+            ("debug", RustValue::Boolean(false)),
+        ]);
+        let overlay = object(vec![
+            ("db", object(vec![("port", RustValue::Integer(6543))])),
+            ("debug", RustValue::Boolean(true)),
+            ("name", RustValue::String("api".to_string())),
+        ]);
+
+        base.deep_merge(&overlay);
+        assert_eq!(
+            base,
+            object(vec![
+                (
+                    "db",
+                    object(vec![
+                        ("host", RustValue::String("localhost".to_string())),
+                        ("port", RustValue::Integer(6543)),
+                    ]),
+                ),
+                ("debug", RustValue::Boolean(true)),
+                ("name", RustValue::String("api".to_string())),
+            ])
+        );
+
+        // Scalars and mismatched types are simply overridden
+        let mut value = RustValue::Integer(1);
+        value.deep_merge(&object(vec![]));
+        assert_eq!(value, object(vec![]));
+    }
+
+    #[test]
+    fn test_deep_merge_array_strategies() {
+        let base = object(vec![(
+            "tags",
+            RustValue::Array(vec![RustValue::Integer(1)]),
+        )]);
+        let overlay = object(vec![(
+            "tags",
+            RustValue::Array(vec![RustValue::Integer(2)]),
+        )]);
+
+        let mut replaced = base.clone();
+        replaced.deep_merge(&overlay);
+        assert_eq!(replaced, overlay);
+
+        let mut concatenated = base.clone();
+        concatenated.deep_merge_with(&overlay, ArrayMergeStrategy::Concat);
+        assert_eq!(
+            concatenated,
+            object(vec![(
+                "tags",
+                RustValue::Array(vec![RustValue::Integer(1), RustValue::Integer(2)])
+            )])
+        );
+    }
 }